@@ -1,10 +1,58 @@
+use log::*;
 use std::{
-    env,
-    path::PathBuf
+    env, fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
 };
+use walkdir::WalkDir;
 
 pub fn tempfiles_location() -> PathBuf {
     let mut temp_dir = env::temp_dir();
     temp_dir.push("extract_text_from_file");
     temp_dir
 }
+
+/// Whether a folder name is a simple uuid, as the temp folder of each file extracted is named, e.g. `870eabfb3dc44ae185b84f6056f73397`.
+fn is_temp_dir_name(name: &str) -> bool {
+    name.len() == 32 && name.bytes().all(|x| x.is_ascii_digit() || (b'a'..=b'f').contains(&x))
+}
+
+/// The time anything in a folder, or the folder itself, was last modified.
+fn last_modified(dir: &Path) -> Option<SystemTime> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .max()
+}
+
+/// Removes the temp folders left under `temp_root` by runs that crashed or were killed before they could remove them:
+/// the folders named as extraction names them, see is_temp_dir_name, in which nothing has been modified for `max_age`.
+/// The folders of runs still going are written to as they extract, so are kept, as is anything else under `temp_root`.
+///
+/// # Returns
+///
+/// * The number of folders removed
+pub fn remove_stale_temp_dirs(temp_root: &Path, max_age: Duration) -> usize {
+    let Ok(entries) = fs::read_dir(temp_root) else {
+        return 0;
+    };
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() || !is_temp_dir_name(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        let is_stale = last_modified(&path).is_some_and(|x| now.duration_since(x).unwrap_or_default() > max_age);
+        if is_stale {
+            match fs::remove_dir_all(&path) {
+                Ok(_) => {
+                    debug!("Removed stale temp folder {:?}", path);
+                    removed += 1;
+                }
+                Err(e) => warn!("Error removing stale temp folder {:?}: {}", path, e),
+            }
+        }
+    }
+    removed
+}
@@ -1,3 +1,13 @@
 pub mod doc;
+pub mod docprops;
 pub mod docx;
+pub mod dxf;
+pub mod hwpx;
+pub mod markdown;
 pub mod odt;
+pub mod pptx;
+pub mod rtf;
+pub mod xls;
+pub mod xlsb;
+pub mod xlsx;
+pub mod xps;
@@ -1,5 +1,6 @@
 //use log::*;
-use zip::ZipArchive;
+use flate2::read::DeflateDecoder;
+use zip::{CompressionMethod, ZipArchive};
 
 use quick_xml::events::Event;
 use quick_xml::reader::Reader;
@@ -7,6 +8,7 @@ use quick_xml::reader::Reader;
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
+use std::io::{BufReader, Cursor, SeekFrom};
 use std::path::Path;
 
 pub trait MsDoc<T>: Read {
@@ -17,65 +19,154 @@ pub trait OpenOfficeDoc<T>: Read {
     fn open<P: AsRef<Path>>(path: P) -> io::Result<T>;
 }
 
-pub(crate) fn open_doc_read_data<P: AsRef<Path>>(
-    path: P,
-    content_name: &str,
-    tags: &[&str],
-) -> io::Result<String> {
+/// Opens a part of a zip as an owned reader of its uncompressed bytes.
+/// Stored and deflated parts are decompressed on demand straight from the file, anything else is read into memory.
+/// A `NotFound` error is returned if the part does not exist, so a missing part is not mistaken for an empty document.
+fn open_zip_part<P: AsRef<Path>>(path: P, content_name: &str) -> io::Result<Box<dyn Read + Send>> {
     let file = File::open(path.as_ref())?;
     let mut archive = ZipArchive::new(file)?;
 
-    let mut xml_data = String::new();
+    let (compression, data_start, compressed_size) = match archive.by_name(content_name) {
+        Ok(c_file) => (c_file.compression(), c_file.data_start(), c_file.compressed_size()),
+        Err(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Content part {} not found", content_name),
+            ))
+        }
+    };
 
-    for i in 0..archive.len() {
-        let mut c_file = archive.by_index(i).unwrap();
-        if c_file.name() == content_name {
-            c_file.read_to_string(&mut xml_data)?;
-            break;
+    match compression {
+        CompressionMethod::Stored | CompressionMethod::Deflated => {
+            let mut file = archive.into_inner();
+            file.seek(SeekFrom::Start(data_start))?;
+            let data = file.take(compressed_size);
+            if compression == CompressionMethod::Stored {
+                Ok(Box::new(data))
+            } else {
+                Ok(Box::new(DeflateDecoder::new(data)))
+            }
+        }
+        _ => {
+            let mut data = Vec::new();
+            archive.by_name(content_name)?.read_to_end(&mut data)?;
+            Ok(Box::new(Cursor::new(data)))
         }
     }
+}
 
-    let mut xml_reader = Reader::from_str(xml_data.as_ref());
+/// Text of a document's XML content part, parsed incrementally as it is read.
+pub(crate) struct XmlTextStream {
+    xml_reader: Reader<BufReader<Box<dyn Read + Send>>>,
+    buf: Vec<u8>,
+    tags: &'static [&'static str],
+    paragraph_tag: &'static str,
+    paragraph_separator: Vec<u8>,
+    skipped_tags: &'static [&'static str],
+    /// Number of skipped elements the parser is inside of
+    skip_depth: usize,
+    to_read: bool,
+    pending: Vec<u8>,
+    pending_pos: usize,
+    done: bool,
+}
+
+impl XmlTextStream {
+    /// # Arguments
+    ///
+    /// * `content_name` - The zip part holding the document XML
+    /// * `tags` - Local names of the elements whose first text child is read, whatever their prefix,
+    ///   so Strict OOXML and documents with unusual prefixes read the same as any other
+    /// * `paragraph_tag` - Local name of the element that starts a new paragraph
+    pub(crate) fn open<P: AsRef<Path>>(
+        path: P,
+        content_name: &str,
+        tags: &'static [&'static str],
+        paragraph_tag: &'static str,
+    ) -> io::Result<XmlTextStream> {
+        let part = open_zip_part(path, content_name)?;
+        Ok(XmlTextStream {
+            xml_reader: Reader::from_reader(BufReader::new(part)),
+            buf: Vec::new(),
+            tags,
+            paragraph_tag,
+            paragraph_separator: b"\n\n".to_vec(),
+            skipped_tags: &[],
+            skip_depth: 0,
+            to_read: false,
+            pending: Vec::new(),
+            pending_pos: 0,
+            done: false,
+        })
+    }
 
-    let mut buf = Vec::new();
-    let mut txt = Vec::new();
+    /// Sets the text written before each paragraph, `\n\n` by default.
+    pub(crate) fn set_paragraph_separator(&mut self, separator: &str) {
+        self.paragraph_separator = separator.as_bytes().to_vec();
+    }
 
-    if xml_data.len() > 0 {
-        let mut to_read = false;
-        loop {
-            match xml_reader.read_event_into(&mut buf) {
+    /// Sets the local names of the elements whose text is left out, with the text of any element inside them.
+    pub(crate) fn set_skipped_tags(&mut self, skipped_tags: &'static [&'static str]) {
+        self.skipped_tags = skipped_tags;
+    }
+
+    /// Parses XML events until some text is pending or the end of the part is reached.
+    fn fill_pending(&mut self) -> io::Result<()> {
+        self.pending.clear();
+        self.pending_pos = 0;
+        while self.pending.is_empty() && !self.done {
+            self.buf.clear();
+            match self.xml_reader.read_event_into(&mut self.buf) {
+                Ok(Event::Start(ref e)) if self.skipped_tags.iter().any(|x| e.local_name().as_ref() == x.as_bytes()) => {
+                    self.skip_depth += 1;
+                }
+                Ok(Event::End(ref e)) if self.skipped_tags.iter().any(|x| e.local_name().as_ref() == x.as_bytes()) => {
+                    self.skip_depth = self.skip_depth.saturating_sub(1);
+                }
+                Ok(Event::Start(_)) if self.skip_depth > 0 => (),
                 Ok(Event::Start(ref e)) => {
-                    for tag in tags {
-                        if e.name().as_ref() == tag.as_bytes() {
-                            to_read = true;
-                            if e.name().as_ref() == b"text:p" {
-                                txt.push("\n\n".to_string());
+                    for tag in self.tags {
+                        if e.local_name().as_ref() == tag.as_bytes() {
+                            self.to_read = true;
+                            if e.local_name().as_ref() == self.paragraph_tag.as_bytes() {
+                                self.pending.extend_from_slice(&self.paragraph_separator);
                             }
                             break;
                         }
                     }
                 }
                 Ok(Event::Text(e)) => {
-                    if to_read {
-                        txt.push(e.decode().unwrap().into_owned());
-                        to_read = false;
+                    // a paragraph's text can follow a skipped element in it, so it is still to be read after it
+                    if self.to_read && self.skip_depth == 0 {
+                        self.pending.extend_from_slice(e.decode().unwrap().as_bytes());
+                        self.to_read = false;
                     }
                 }
-                Ok(Event::Eof) => break,
+                Ok(Event::Eof) => self.done = true,
                 Err(e) => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        format!(
-                            "Error at position {}: {:?}",
-                            xml_reader.buffer_position(),
-                            e
-                        ),
-                    ))
+                    self.done = true;
+                    return Err(io::Error::other(format!(
+                        "Error at position {}: {:?}",
+                        self.xml_reader.buffer_position(),
+                        e
+                    )));
                 }
                 _ => (),
             }
         }
+        Ok(())
     }
+}
 
-    Ok(txt.join(""))
+impl Read for XmlTextStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending_pos >= self.pending.len() {
+            self.fill_pending()?;
+        }
+        let available = &self.pending[self.pending_pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.pending_pos += len;
+        Ok(len)
+    }
 }
@@ -0,0 +1,85 @@
+use zip::ZipArchive;
+
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use super::xlsx::{read_zip_part, resolve_general_ref, xml_error};
+
+/// Properties read from docProps/core.xml, by element local name, in output order.
+const CORE_PROPERTIES: [(&str, &str); 7] = [
+    ("title", "Title"),
+    ("subject", "Subject"),
+    ("creator", "Author"),
+    ("keywords", "Keywords"),
+    ("description", "Description"),
+    ("category", "Category"),
+    ("lastModifiedBy", "Last Modified By"),
+];
+
+/// Properties read from docProps/app.xml, by element local name, in output order.
+const APP_PROPERTIES: [(&str, &str); 2] = [("Company", "Company"), ("Manager", "Manager")];
+
+/// Reads the text of the top-level elements of a properties part that are named in `properties`.
+fn read_properties_part(xml_data: &str, properties: &[(&str, &'static str)]) -> io::Result<Vec<(&'static str, String)>> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut values: Vec<(&'static str, String)> = Vec::new();
+    let mut depth = 0;
+    let mut current: Option<(&'static str, String)> = None;
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) => {
+                depth += 1;
+                // the root is at depth 1, the properties are its children
+                if depth == 2 {
+                    current = properties
+                        .iter()
+                        .find(|(name, _)| e.local_name().as_ref() == name.as_bytes())
+                        .map(|(_, label)| (*label, String::new()));
+                }
+            }
+            Ok(Event::Text(e)) => {
+                if let Some((_, value)) = current.as_mut() {
+                    value.push_str(&e.decode().unwrap_or_default());
+                }
+            }
+            Ok(Event::GeneralRef(e)) => {
+                if let Some((_, value)) = current.as_mut() {
+                    value.push_str(&resolve_general_ref(&e));
+                }
+            }
+            Ok(Event::End(_)) => {
+                if depth == 2
+                    && let Some((label, value)) = current.take()
+                    && !value.trim().is_empty()
+                {
+                    values.push((label, value.trim().to_string()));
+                }
+                depth -= 1;
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+    // output order follows the table, not the part
+    values.sort_by_key(|(label, _)| properties.iter().position(|(_, x)| x == label));
+    Ok(values)
+}
+
+/// Reads the document properties (title, author, keywords, company...) of an OOXML file from docProps/core.xml and docProps/app.xml.
+///
+/// # Returns
+///
+/// * A list of (label, value) for the properties that are set, core properties first
+pub fn read_properties<P: AsRef<Path>>(path: P) -> io::Result<Vec<(&'static str, String)>> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+
+    let mut properties = read_properties_part(&read_zip_part(&mut archive, "docProps/core.xml")?, &CORE_PROPERTIES)?;
+    properties.extend(read_properties_part(&read_zip_part(&mut archive, "docProps/app.xml")?, &APP_PROPERTIES)?);
+    Ok(properties)
+}
@@ -1,80 +1,63 @@
 use zip::ZipArchive;
 
-use quick_xml::events::Event;
-use quick_xml::reader::Reader;
-
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
-use std::io::Cursor;
 use std::path::{Path};
 
-use super::doc::{MsDoc};
+use super::doc::{MsDoc, XmlTextStream};
+use super::xlsx::{read_relationships, read_zip_part, resolve_target};
+
+const MAIN_DOCUMENT_PART: &str = "word/document.xml";
 
 pub struct Docx {
-    data: Cursor<String>,
+    data: XmlTextStream,
 }
 
-impl MsDoc<Docx> for Docx {
-    fn open<P: AsRef<Path>>(path: P) -> io::Result<Docx> {
-        let file = File::open(path.as_ref())?;
-        let mut archive = ZipArchive::new(file)?;
-
-        let mut xml_data = String::new();
+/// Finds the main document part. It is normally `word/document.xml`, otherwise the target of the package's officeDocument relationship,
+/// or failing that the first `word/document*.xml` part, as written by some generators.
+fn main_document_part<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+    if archive.index_for_name(MAIN_DOCUMENT_PART).is_some() {
+        return Ok(MAIN_DOCUMENT_PART.to_string());
+    }
 
-        for i in 0..archive.len() {
-            let mut c_file = archive.by_index(i).unwrap();
-            if c_file.name() == "word/document.xml" {
-                c_file.read_to_string(&mut xml_data)?;
-                break;
-            }
+    let package_rels = read_zip_part(&mut archive, "_rels/.rels")?;
+    for (_, typ, target) in read_relationships(&package_rels).unwrap_or_default() {
+        let part = resolve_target("", &target);
+        if typ.ends_with("/officeDocument") && archive.index_for_name(&part).is_some() {
+            return Ok(part);
         }
+    }
 
-        let mut xml_reader = Reader::from_str(xml_data.as_ref());
-
-        let mut buf = Vec::new();
-        let mut txt = Vec::new();
+    archive
+        .file_names()
+        .filter(|x| x.starts_with("word/document") && x.ends_with(".xml"))
+        .min()
+        .map(|x| x.to_string())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No main document part found in docx"))
+}
 
-        if xml_data.len() > 0 {
-            let mut to_read = false;
-            loop {
-                match xml_reader.read_event_into(&mut buf) {
-                    Ok(Event::Start(ref e)) => match e.name().as_ref() {
-                        b"w:p" => {
-                            to_read = true;
-                            txt.push("\n\n".to_string());
-                        }
-                        b"w:t" => to_read = true,
-                        _ => (),
-                    },
-                    Ok(Event::Text(e)) => {
-                        if to_read {
-                            txt.push(e.decode().unwrap().into_owned());
-                            to_read = false;
-                        }
-                    }
-                    Ok(Event::Eof) => break, // exits the loop when reaching end of file
-                    Err(e) => {
-                        return Err(io::Error::new(
-                            io::ErrorKind::Other,
-                            format!(
-                                "Error at position {}: {:?}",
-                                xml_reader.buffer_position(),
-                                e
-                            ),
-                        ))
-                    }
-                    _ => (),
-                }
-            }
-        }
+impl MsDoc<Docx> for Docx {
+    fn open<P: AsRef<Path>>(path: P) -> io::Result<Docx> {
+        let content_name = main_document_part(path.as_ref())?;
+        // the document XML is parsed as the text is read, rather than up front
+        let data = XmlTextStream::open(path.as_ref(), &content_name, &["p", "t"], "p")?;
 
         Ok(Docx {
-            data: Cursor::new(txt.join("")),
+            data,
         })
     }
 }
 
+impl Docx {
+    /// Sets the text written before each paragraph, `\n\n` by default.
+    pub fn set_paragraph_separator(&mut self, separator: &str) {
+        self.data.set_paragraph_separator(separator);
+    }
+}
+
 impl Read for Docx {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.data.read(buf)
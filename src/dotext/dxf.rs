@@ -0,0 +1,145 @@
+/// Entities of the ENTITIES section whose text is read, from group code 1, and 3 for the chunks of a long MTEXT.
+const TEXT_ENTITIES: [&str; 3] = ["ATTRIB", "MTEXT", "TEXT"];
+
+/// The character of a `\U+XXXX` escape, as DXF writes characters outside of its code page.
+fn unicode_escape(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<char> {
+    if chars.peek() != Some(&'+') {
+        return None;
+    }
+    chars.next();
+    let hex: String = chars.by_ref().take(4).collect();
+    u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+}
+
+/// Replaces the control codes of a TEXT or ATTRIB value, `%%d` for a degree sign, `%%p` plus or minus, `%%c` a diameter,
+/// and drops the `%%u` and `%%o` underline and overline toggles.
+fn text_value(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' if chars.peek() == Some(&'%') => {
+                chars.next();
+                match chars.next().map(|x| x.to_ascii_lowercase()) {
+                    Some('d') => text.push('°'),
+                    Some('p') => text.push('±'),
+                    Some('c') => text.push('⌀'),
+                    Some('%') => text.push('%'),
+                    Some('u' | 'o') | None => (),
+                    Some(other) => {
+                        text.push_str("%%");
+                        text.push(other);
+                    }
+                }
+            }
+            '\\' if chars.peek() == Some(&'U') => {
+                chars.next();
+                match unicode_escape(&mut chars) {
+                    Some(unicode_char) => text.push(unicode_char),
+                    None => text.push_str("\\U"),
+                }
+            }
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+/// Removes the inline formatting of an MTEXT value: `\P` is a new paragraph, `\~` a non-breaking space, `\S1^2;` a stacked fraction,
+/// font, height, color... codes such as `\fArial|b1;` end at a `;`, and braces group formatting.
+fn mtext_value(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => (),
+            '\\' => match chars.next() {
+                Some('P') => text.push('\n'),
+                Some('~') => text.push(' '),
+                Some(escaped @ ('\\' | '{' | '}')) => text.push(escaped),
+                Some('U') => {
+                    if let Some(unicode_char) = unicode_escape(&mut chars) {
+                        text.push(unicode_char);
+                    }
+                }
+                Some('S') => {
+                    for stacked in chars.by_ref().take_while(|x| *x != ';') {
+                        text.push(if matches!(stacked, '^' | '#') { '/' } else { stacked });
+                    }
+                }
+                // underline, overline and strike-through toggles
+                Some('L' | 'l' | 'O' | 'o' | 'K' | 'k') | None => (),
+                // font, height, width, oblique, tracking, alignment, color and paragraph codes take a value up to a ;
+                Some(_) => while chars.next().is_some_and(|x| x != ';') {},
+            },
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+/// Reads the text of an ASCII DXF drawing: the TEXT and MTEXT annotations of the ENTITIES section, and the ATTRIB values
+/// of the blocks inserted there, such as title block fields, as `TAG: value`. The geometry, and the numeric group codes
+/// that make up most of the file, are left out.
+///
+/// # Returns
+///
+/// * A line per text, in drawing order
+pub fn to_text(dxf: &str) -> String {
+    let mut lines = dxf.lines();
+    let mut in_entities = false;
+    // the entity being read, its MTEXT chunks and text, and an ATTRIB's tag
+    let mut entity = String::new();
+    let mut chunks = String::new();
+    let mut value = String::new();
+    let mut tag = String::new();
+    let mut text = String::new();
+
+    let mut end_entity = |entity: &str, chunks: &mut String, value: &mut String, tag: &mut String| {
+        let entity_text = match entity {
+            "MTEXT" => mtext_value(&format!("{}{}", chunks, value)),
+            "ATTRIB" if !tag.is_empty() && !value.trim().is_empty() => format!("{}: {}", tag, text_value(value)),
+            _ => text_value(value),
+        };
+        for line in entity_text.lines().filter(|x| !x.trim().is_empty()) {
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        chunks.clear();
+        value.clear();
+        tag.clear();
+    };
+
+    while let (Some(code), Some(pair_value)) = (lines.next(), lines.next()) {
+        let Ok(code) = code.trim().parse::<i32>() else {
+            break;
+        };
+        if code == 0 {
+            if TEXT_ENTITIES.contains(&entity.as_str()) {
+                end_entity(&entity, &mut chunks, &mut value, &mut tag);
+            }
+            entity = pair_value.trim().to_string();
+            if entity == "ENDSEC" {
+                in_entities = false;
+            }
+            continue;
+        }
+        if entity == "SECTION" && code == 2 {
+            in_entities = pair_value.trim() == "ENTITIES";
+            continue;
+        }
+        if !in_entities || !TEXT_ENTITIES.contains(&entity.as_str()) {
+            continue;
+        }
+        match code {
+            1 => value = pair_value.to_string(),
+            2 if entity == "ATTRIB" => tag = pair_value.trim().to_string(),
+            3 if entity == "MTEXT" => chunks.push_str(pair_value),
+            _ => (),
+        }
+    }
+    if in_entities && TEXT_ENTITIES.contains(&entity.as_str()) {
+        end_entity(&entity, &mut chunks, &mut value, &mut tag);
+    }
+    text
+}
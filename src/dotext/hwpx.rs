@@ -0,0 +1,70 @@
+use zip::ZipArchive;
+
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use super::xlsx::{read_zip_part, resolve_general_ref, xml_error};
+
+/// The number of a `Contents/sectionN.xml` part, None for any other part.
+fn section_number(part: &str) -> Option<u32> {
+    part.strip_prefix("Contents/section")?.strip_suffix(".xml")?.parse().ok()
+}
+
+/// Reads the text of a section part, a line per `hp:p` paragraph. The text is in the `hp:t` runs of a paragraph,
+/// where `hp:tab` and `hp:lineBreak` stand for a tab and a line break. The paragraphs of table cells are lines of their own.
+fn read_section_text(xml_data: &str) -> io::Result<String> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut text = String::new();
+    let mut in_text = false;
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"t" => in_text = true,
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" if !text.is_empty() && !text.ends_with('\n') => text.push('\n'),
+                _ => (),
+            },
+            Ok(Event::Empty(ref e)) if in_text => match e.local_name().as_ref() {
+                b"tab" => text.push('\t'),
+                b"lineBreak" => text.push('\n'),
+                _ => (),
+            },
+            Ok(Event::Text(e)) if in_text => text.push_str(&e.decode().unwrap_or_default()),
+            Ok(Event::GeneralRef(e)) if in_text => text.push_str(&resolve_general_ref(&e)),
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+    Ok(text)
+}
+
+/// Reads the text of the sections of an HWPX (Hancom OWPML) document, the `Contents/section0.xml`,
+/// `Contents/section1.xml`... parts, in section order.
+///
+/// # Returns
+///
+/// * The text of each section
+pub fn read_sections<P: AsRef<Path>>(path: P) -> io::Result<Vec<String>> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+
+    let mut section_parts: Vec<(u32, String)> = archive
+        .file_names()
+        .filter_map(|x| section_number(x).map(|number| (number, x.to_string())))
+        .collect();
+    if section_parts.is_empty() {
+        return Err(io::Error::other("No sections in HWPX package"));
+    }
+    section_parts.sort();
+
+    let mut sections = Vec::new();
+    for (_, part) in section_parts {
+        sections.push(read_section_text(&read_zip_part(&mut archive, &part)?)?);
+    }
+    Ok(sections)
+}
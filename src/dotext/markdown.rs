@@ -0,0 +1,57 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+/// Ends the current line, if any text has been written since the last one.
+fn end_line(output: &mut String) {
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+}
+
+/// Renders Markdown to plain text, without its syntax (`#` headings, `*` emphasis, code fences, link targets...).
+///
+/// Each block is written on its own lines, with a blank line between paragraphs, and table cells separated by tabs.
+/// Inline HTML is dropped. With `link_urls`, the URL of each link is written after its text, e.g. `docs (https://example.com)`,
+/// unless the text is the URL itself.
+pub fn to_text(markdown: &str, link_urls: bool) -> String {
+    let mut output = String::new();
+    // url of the link being written, and where its text starts in the output
+    let mut links: Vec<(String, usize)> = Vec::new();
+
+    let parser = Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS);
+    for event in parser {
+        match event {
+            Event::Start(Tag::Paragraph | Tag::Heading { .. } | Tag::CodeBlock(_) | Tag::Table(_)) => {
+                end_line(&mut output);
+                // a blank line between blocks
+                if !output.is_empty() && !output.ends_with("\n\n") {
+                    output.push('\n');
+                }
+            }
+            Event::Start(Tag::Item) => end_line(&mut output),
+            Event::Start(Tag::Link { dest_url, .. }) => links.push((dest_url.to_string(), output.len())),
+            Event::End(TagEnd::Link) => {
+                if let Some((url, start)) = links.pop()
+                    && link_urls
+                    && !url.is_empty()
+                    && output[start..] != url
+                {
+                    output.push_str(&format!(" ({})", url));
+                }
+            }
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock | TagEnd::Item) => end_line(&mut output),
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => {
+                if output.ends_with('\t') {
+                    output.pop();
+                }
+                end_line(&mut output);
+            }
+            Event::End(TagEnd::TableCell) => output.push('\t'),
+            Event::Text(text) | Event::Code(text) => output.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => output.push('\n'),
+            Event::Rule => end_line(&mut output),
+            Event::TaskListMarker(checked) => output.push_str(if checked { "[x] " } else { "[ ] " }),
+            _ => (),
+        }
+    }
+    output
+}
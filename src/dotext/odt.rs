@@ -1,26 +1,127 @@
+use zip::ZipArchive;
+
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+use std::fs::File;
 use std::io;
 use std::io::prelude::*;
-use std::io::Cursor;
 use std::path::{Path};
 
-use super::doc::{self, OpenOfficeDoc};
+use super::doc::{OpenOfficeDoc, XmlTextStream};
+use super::xlsx::{read_zip_part, resolve_general_ref, xml_error};
+
+/// Comments (`office:annotation`) and footnotes and endnotes (`text:note`), whose text is not part of the body text.
+const SEPARATE_TEXT_TAGS: [&str; 2] = ["annotation", "note"];
 
 pub struct Odt {
-    data: Cursor<String>,
+    data: XmlTextStream,
 }
 
 impl OpenOfficeDoc<Odt> for Odt {
     fn open<P: AsRef<Path>>(path: P) -> io::Result<Odt> {
-        let text = doc::open_doc_read_data(path.as_ref(), "content.xml", &["text:p", "text:span"])?;
+        let mut data = XmlTextStream::open(path.as_ref(), "content.xml", &["p", "span"], "p")?;
+        data.set_skipped_tags(&SEPARATE_TEXT_TAGS);
 
         Ok(Odt {
-            data: Cursor::new(text),
+            data,
         })
     }
 }
 
+impl Odt {
+    /// Sets the text written before each paragraph, `\n\n` by default.
+    pub fn set_paragraph_separator(&mut self, separator: &str) {
+        self.data.set_paragraph_separator(separator);
+    }
+}
+
 impl Read for Odt {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.data.read(buf)
     }
 }
+
+/// Reads the text of each `element` of an ODF content part, and of its `label_tag` child such as the author of a comment.
+/// The text is that of the paragraphs of the element, a line each.
+fn read_element_texts(xml_data: &str, element: &[u8], label_tag: &[u8]) -> io::Result<Vec<(String, String)>> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut element_texts = Vec::new();
+    // the number of `element`s and paragraphs the parser is inside of
+    let mut element_depth: usize = 0;
+    let mut paragraph_depth: usize = 0;
+    let mut in_label = false;
+    let mut label = String::new();
+    let mut text = String::new();
+    loop {
+        let in_text = paragraph_depth > 0 || in_label;
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == element => {
+                if element_depth == 0 {
+                    label.clear();
+                    text.clear();
+                }
+                element_depth += 1;
+            }
+            Ok(Event::Start(ref e)) if element_depth > 0 => match e.local_name().as_ref() {
+                x if x == label_tag => in_label = true,
+                b"p" | b"h" => {
+                    if paragraph_depth == 0 && !text.is_empty() {
+                        text.push('\n');
+                    }
+                    paragraph_depth += 1;
+                }
+                _ => (),
+            },
+            Ok(Event::Empty(ref e)) if in_text => match e.local_name().as_ref() {
+                b"s" => text.push(' '),
+                b"tab" => text.push('\t'),
+                b"line-break" => text.push('\n'),
+                _ => (),
+            },
+            Ok(Event::Text(e)) if in_text => {
+                let decoded = e.decode().unwrap_or_default();
+                if in_label { label.push_str(&decoded) } else { text.push_str(&decoded) }
+            }
+            Ok(Event::GeneralRef(e)) if in_text => {
+                let resolved = resolve_general_ref(&e);
+                if in_label { label.push_str(&resolved) } else { text.push_str(&resolved) }
+            }
+            Ok(Event::End(ref e)) if element_depth > 0 => match e.local_name().as_ref() {
+                x if x == element => {
+                    element_depth -= 1;
+                    if element_depth == 0 {
+                        element_texts.push((label.trim().to_string(), text.trim().to_string()));
+                    }
+                }
+                x if x == label_tag => in_label = false,
+                b"p" | b"h" => paragraph_depth = paragraph_depth.saturating_sub(1),
+                _ => (),
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+    Ok(element_texts)
+}
+
+/// Reads the comments of an ODF text document, its `office:annotation` elements, in document order.
+///
+/// # Returns
+///
+/// * The author and the text of each comment
+pub fn read_annotations<P: AsRef<Path>>(path: P) -> io::Result<Vec<(String, String)>> {
+    let mut archive = ZipArchive::new(File::open(path.as_ref())?)?;
+    read_element_texts(&read_zip_part(&mut archive, "content.xml")?, b"annotation", b"creator")
+}
+
+/// Reads the footnotes and endnotes of an ODF text document, its `text:note` elements, in document order.
+///
+/// # Returns
+///
+/// * The citation, e.g. `1` or `i`, and the text of each note
+pub fn read_notes<P: AsRef<Path>>(path: P) -> io::Result<Vec<(String, String)>> {
+    let mut archive = ZipArchive::new(File::open(path.as_ref())?)?;
+    read_element_texts(&read_zip_part(&mut archive, "content.xml")?, b"note", b"note-citation")
+}
@@ -0,0 +1,145 @@
+use zip::ZipArchive;
+
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use super::xlsx::{attribute_value, is_relationship_id, read_relationships, read_zip_part, resolve_general_ref, resolve_target, xml_error};
+
+/// The text of one slide of a presentation.
+#[derive(Debug, Default)]
+pub struct Slide {
+    /// Text of the slide's shapes, a line per paragraph
+    pub text: String,
+    /// Descriptions (alternative text) of the slide's shapes and pictures
+    pub alt_texts: Vec<String>,
+    /// Text of the speaker notes, empty if the slide has none
+    pub notes: String,
+}
+
+/// Reads the paragraphs of a slide part, and the alt text of its shapes.
+fn read_slide_text(xml_data: &str) -> io::Result<(String, Vec<String>)> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut text = String::new();
+    let mut alt_texts = Vec::new();
+    let mut to_read = false;
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"cNvPr" => {
+                if let Ok(Some(descr)) = e.try_get_attribute("descr") {
+                    let descr = attribute_value(&descr, xml_reader.decoder());
+                    if !descr.trim().is_empty() {
+                        alt_texts.push(descr);
+                    }
+                }
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"t" => to_read = true,
+            Ok(Event::Text(e)) if to_read => text.push_str(&e.decode().unwrap_or_default()),
+            Ok(Event::GeneralRef(e)) if to_read => text.push_str(&resolve_general_ref(&e)),
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"t" => to_read = false,
+                b"p" => text.push('\n'),
+                _ => (),
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+    Ok((text, alt_texts))
+}
+
+/// Reads the text of the body placeholder of a notes slide part. The other placeholders hold the slide image and number.
+fn read_notes_text(xml_data: &str) -> io::Result<String> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut notes = String::new();
+    let mut shape_text = String::new();
+    let mut is_body = false;
+    let mut to_read = false;
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sp" => {
+                shape_text.clear();
+                is_body = false;
+            }
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"ph" => {
+                is_body = e
+                    .try_get_attribute("type")
+                    .ok()
+                    .flatten()
+                    .is_some_and(|x| attribute_value(&x, xml_reader.decoder()) == "body");
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"t" => to_read = true,
+            Ok(Event::Text(e)) if to_read => shape_text.push_str(&e.decode().unwrap_or_default()),
+            Ok(Event::GeneralRef(e)) if to_read => shape_text.push_str(&resolve_general_ref(&e)),
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"t" => to_read = false,
+                b"p" => shape_text.push('\n'),
+                b"sp" if is_body => notes.push_str(&shape_text),
+                _ => (),
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+    Ok(notes)
+}
+
+/// Reads the slides of a PresentationML file (pptx, pptm, ppsx) in presentation order.
+pub fn read_slides<P: AsRef<Path>>(path: P) -> io::Result<Vec<Slide>> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+
+    // slide relationship ids, in presentation order
+    let presentation_xml = read_zip_part(&mut archive, "ppt/presentation.xml")?;
+    let mut slide_rids: Vec<String> = Vec::new();
+    let mut xml_reader = Reader::from_str(&presentation_xml);
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"sldId" => {
+                if let Some(rid) = e.attributes().flatten().find(|x| is_relationship_id(x.key)) {
+                    slide_rids.push(attribute_value(&rid, xml_reader.decoder()));
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+
+    let presentation_rels: HashMap<String, String> =
+        read_relationships(&read_zip_part(&mut archive, "ppt/_rels/presentation.xml.rels")?)?
+            .into_iter()
+            .map(|(id, _, target)| (id, resolve_target("ppt", &target)))
+            .collect();
+
+    let mut slides = Vec::new();
+    for rid in slide_rids {
+        let Some(slide_part) = presentation_rels.get(&rid) else {
+            continue;
+        };
+        let (text, alt_texts) = read_slide_text(&read_zip_part(&mut archive, slide_part)?)?;
+
+        let (slide_dir, slide_file) = slide_part.rsplit_once('/').unwrap_or(("", slide_part));
+        let slide_rels_part = format!("{}/_rels/{}.rels", slide_dir, slide_file);
+        let mut notes = String::new();
+        for (_, typ, target) in read_relationships(&read_zip_part(&mut archive, &slide_rels_part)?)? {
+            if typ.ends_with("/notesSlide") {
+                notes = read_notes_text(&read_zip_part(&mut archive, &resolve_target(slide_dir, &target))?)?;
+            }
+        }
+
+        slides.push(Slide {
+            text,
+            alt_texts,
+            notes,
+        });
+    }
+
+    Ok(slides)
+}
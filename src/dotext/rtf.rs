@@ -0,0 +1,319 @@
+use encoding_rs::WINDOWS_1252;
+use mail_parser::decoders::html::html_to_text;
+
+use std::io;
+
+/// Initial dictionary contents of the compressed RTF format, see [MS-OXRTFCP] 2.1.3.1.1.
+const COMPRESSED_RTF_PREBUF: &[u8] = b"{\\rtf1\\ansi\\mac\\deff0\\deftab720{\\fonttbl;}{\\f0\\fnil \\froman \\fswiss \\fmodern \\fscript \\fdecor MS Sans SerifSymbolArialTimes New RomanCourier{\\colortbl\\red0\\green0\\blue0\r\n\\par \\pard\\plain\\f0\\fs20\\b\\i\\u\\tab\\tx";
+const COMPRESSED_RTF_DICTIONARY_SIZE: usize = 4096;
+const COMPRESSED_RTF_HEADER_SIZE: usize = 16;
+/// "LZFu", compressed
+const COMPRESSED_RTF_COMPRESSED: u32 = 0x75465a4c;
+/// "MELA", stored uncompressed
+const COMPRESSED_RTF_UNCOMPRESSED: u32 = 0x414c454d;
+
+/// Destinations whose text is not part of the document body.
+const SKIPPED_DESTINATIONS: [&str; 10] = [
+    "colortbl",
+    "fonttbl",
+    "footer",
+    "header",
+    "info",
+    "listoverridetable",
+    "listtable",
+    "object",
+    "pict",
+    "stylesheet",
+];
+
+/// Picture formats of `\pict` groups that are read, with the extension of their image files. Metafiles and bitmaps are not read by OCR.
+const PICTURE_FORMATS: [(&str, &str); 2] = [("jpegblip", "jpg"), ("pngblip", "png")];
+
+/// Decompresses the RTF body of an Outlook message (`PR_RTF_COMPRESSED`), see [MS-OXRTFCP].
+pub fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    if data.len() < COMPRESSED_RTF_HEADER_SIZE {
+        return Err(io::Error::other("Compressed RTF is shorter than its header"));
+    }
+    let header_u32 = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+    let raw_size = header_u32(4) as usize;
+    let comp_type = header_u32(8);
+    let content = &data[COMPRESSED_RTF_HEADER_SIZE..];
+
+    match comp_type {
+        COMPRESSED_RTF_UNCOMPRESSED => Ok(content[..raw_size.min(content.len())].to_vec()),
+        COMPRESSED_RTF_COMPRESSED => {
+            let mut dictionary = [0u8; COMPRESSED_RTF_DICTIONARY_SIZE];
+            dictionary[..COMPRESSED_RTF_PREBUF.len()].copy_from_slice(COMPRESSED_RTF_PREBUF);
+            let mut write_pos = COMPRESSED_RTF_PREBUF.len();
+            // the size in the header is not trusted, no input byte gives more than 9 output bytes
+            let mut output = Vec::with_capacity(raw_size.min(content.len() * 9));
+            let mut pos = 0;
+            while pos < content.len() {
+                // each bit of a control byte, lowest first, says if the next item is a literal byte (0) or a dictionary reference (1)
+                let control = content[pos];
+                pos += 1;
+                for bit in 0..8 {
+                    if pos >= content.len() {
+                        break;
+                    }
+                    if control & (1 << bit) == 0 {
+                        output.push(content[pos]);
+                        dictionary[write_pos] = content[pos];
+                        write_pos = (write_pos + 1) % COMPRESSED_RTF_DICTIONARY_SIZE;
+                        pos += 1;
+                    } else {
+                        if pos + 1 >= content.len() {
+                            return Err(io::Error::other("Compressed RTF ends inside a dictionary reference"));
+                        }
+                        let reference = u16::from_be_bytes([content[pos], content[pos + 1]]) as usize;
+                        pos += 2;
+                        let offset = reference >> 4;
+                        // a reference to the write position marks the end
+                        if offset == write_pos {
+                            return Ok(output);
+                        }
+                        let length = (reference & 0xF) + 2;
+                        for i in 0..length {
+                            let byte = dictionary[(offset + i) % COMPRESSED_RTF_DICTIONARY_SIZE];
+                            output.push(byte);
+                            dictionary[write_pos] = byte;
+                            write_pos = (write_pos + 1) % COMPRESSED_RTF_DICTIONARY_SIZE;
+                        }
+                    }
+                }
+            }
+            Ok(output)
+        }
+        _ => Err(io::Error::other(format!("Unknown compressed RTF type {:#x}", comp_type))),
+    }
+}
+
+/// Reads the control word at `pos`, just after its backslash, and its numeric parameter.
+///
+/// # Returns
+///
+/// * The word, its parameter, and the position after it and the space that ends it, if any
+fn read_control_word(rtf: &[u8], mut pos: usize) -> (String, Option<i32>, usize) {
+    let word_start = pos;
+    while pos < rtf.len() && rtf[pos].is_ascii_alphabetic() {
+        pos += 1;
+    }
+    let word = String::from_utf8_lossy(&rtf[word_start..pos]).to_string();
+    let param_start = pos;
+    if pos < rtf.len() && rtf[pos] == b'-' {
+        pos += 1;
+    }
+    while pos < rtf.len() && rtf[pos].is_ascii_digit() {
+        pos += 1;
+    }
+    let param: Option<i32> = std::str::from_utf8(&rtf[param_start..pos]).ok().and_then(|x| x.parse().ok());
+    // a space ends the control word, and is not text
+    if pos < rtf.len() && rtf[pos] == b' ' {
+        pos += 1;
+    }
+    (word, param, pos)
+}
+
+/// A `\pict` group being read by read_pictures.
+struct PictGroup {
+    /// Group depth of the `\pict`, its data is not in the groups nested in it
+    depth: usize,
+    /// Extension of the picture format, None for formats that are not read
+    extension: Option<&'static str>,
+    data: Vec<u8>,
+    /// The first hex digit of a byte, until the second one is read
+    high_digit: Option<u8>,
+}
+
+/// Reads the PNG and JPEG pictures of an RTF document, e.g. screenshots pasted into an Outlook message, from the hex digits
+/// or `\bin` data of its `\pict` groups. The groups nested in a `\pict`, such as its `\*\picprop` properties, are not picture data.
+///
+/// # Returns
+///
+/// * The extension and data of each picture, in document order
+pub fn read_pictures(rtf: &[u8]) -> Vec<(&'static str, Vec<u8>)> {
+    let mut pictures = Vec::new();
+    let mut depth: usize = 0;
+    let mut pict: Option<PictGroup> = None;
+
+    let mut pos = 0;
+    while pos < rtf.len() {
+        let in_pict_data = pict.as_ref().is_some_and(|x| x.depth == depth);
+        match rtf[pos] {
+            b'{' => {
+                depth += 1;
+                pos += 1;
+            }
+            b'}' => {
+                // a picture of another format is dropped too
+                if in_pict_data && let Some(PictGroup { extension: Some(extension), data, .. }) = pict.take() {
+                    pictures.push((extension, data));
+                }
+                depth = depth.saturating_sub(1);
+                pos += 1;
+            }
+            b'\\' if rtf.get(pos + 1).is_some_and(|x| x.is_ascii_alphabetic()) => {
+                let (word, param, next_pos) = read_control_word(rtf, pos + 1);
+                pos = next_pos;
+                if word == "pict" {
+                    pict = Some(PictGroup { depth, extension: None, data: Vec::new(), high_digit: None });
+                } else if word == "bin" {
+                    let bin_end = (pos + param.unwrap_or(0).max(0) as usize).min(rtf.len());
+                    if in_pict_data && let Some(pict) = pict.as_mut() {
+                        pict.data.extend_from_slice(&rtf[pos..bin_end]);
+                    }
+                    pos = bin_end;
+                } else if in_pict_data && let Some(pict) = pict.as_mut()
+                    && let Some((_, extension)) = PICTURE_FORMATS.iter().find(|(blip, _)| *blip == word)
+                {
+                    pict.extension = Some(extension);
+                }
+            }
+            b'\\' => pos += 2,
+            digit if in_pict_data && digit.is_ascii_hexdigit() => {
+                if let Some(pict) = pict.as_mut() {
+                    let nibble = (digit as char).to_digit(16).unwrap() as u8;
+                    match pict.high_digit.take() {
+                        Some(high) => pict.data.push(high << 4 | nibble),
+                        None => pict.high_digit = Some(nibble),
+                    }
+                }
+                pos += 1;
+            }
+            _ => pos += 1,
+        }
+    }
+    pictures
+}
+
+/// Formatting state of an RTF group, restored when the group ends.
+#[derive(Clone, Copy)]
+struct GroupState {
+    /// Inside a destination whose text is not wanted
+    skip: bool,
+    /// Inside a `\*\htmltag` destination, HTML of an encapsulated message
+    html_tag: bool,
+    /// `\htmlrtf` is on, the text only exists in the RTF rendering of an encapsulated message
+    html_rtf: bool,
+    /// Number of fallback characters that follow a `\u` character
+    unicode_skip: usize,
+}
+
+/// Reads the text of an RTF document.
+///
+/// RTF that encapsulates HTML (`\fromhtml1`, as Outlook stores HTML messages) is de-encapsulated, see [MS-OXRTFEX],
+/// and the HTML converted to text, which is cleaner than the RTF rendering of it.
+/// Bytes escaped as `\'hh` are read as Windows-1252.
+pub fn to_text(rtf: &[u8]) -> String {
+    let mut from_html = false;
+    let mut output = String::new();
+    let mut stack: Vec<GroupState> = Vec::new();
+    let mut state = GroupState {
+        skip: false,
+        html_tag: false,
+        html_rtf: false,
+        unicode_skip: 1,
+    };
+    // the next control word names an optional destination, `\*`
+    let mut ignorable = false;
+    // fallback characters still to skip after a `\u` character
+    let mut to_skip: usize = 0;
+
+    let mut pos = 0;
+    while pos < rtf.len() {
+        let is_emitting = |state: &GroupState| !state.skip && (!from_html || state.html_tag || !state.html_rtf);
+        match rtf[pos] {
+            b'{' => {
+                stack.push(state);
+                pos += 1;
+            }
+            b'}' => {
+                if let Some(outer) = stack.pop() {
+                    state = outer;
+                }
+                pos += 1;
+            }
+            b'\r' | b'\n' => pos += 1,
+            b'\\' if pos + 1 < rtf.len() => {
+                let next = rtf[pos + 1];
+                if next.is_ascii_alphabetic() {
+                    let (word, param, next_pos) = read_control_word(rtf, pos + 1);
+                    pos = next_pos;
+
+                    if ignorable {
+                        ignorable = false;
+                        if from_html && word == "htmltag" {
+                            state.html_tag = true;
+                        } else {
+                            state.skip = true;
+                        }
+                        continue;
+                    }
+                    if SKIPPED_DESTINATIONS.contains(&word.as_str()) {
+                        state.skip = true;
+                        continue;
+                    }
+                    match word.as_str() {
+                        "fromhtml" => from_html = param != Some(0),
+                        "htmlrtf" => state.html_rtf = param != Some(0),
+                        "uc" => state.unicode_skip = param.unwrap_or(1).max(0) as usize,
+                        "bin" => pos += param.unwrap_or(0).max(0) as usize,
+                        _ if !is_emitting(&state) => (),
+                        "par" | "line" => output.push('\n'),
+                        "tab" => output.push('\t'),
+                        "emdash" => output.push('\u{2014}'),
+                        "endash" => output.push('\u{2013}'),
+                        "bullet" => output.push('\u{2022}'),
+                        "lquote" => output.push('\u{2018}'),
+                        "rquote" => output.push('\u{2019}'),
+                        "ldblquote" => output.push('\u{201C}'),
+                        "rdblquote" => output.push('\u{201D}'),
+                        "u" => {
+                            // negative values are the upper half of the 16 bit range
+                            let code = param.unwrap_or(0);
+                            let code = if code < 0 { code + 0x10000 } else { code };
+                            output.push(char::from_u32(code as u32).unwrap_or('\u{FFFD}'));
+                            to_skip = state.unicode_skip;
+                        }
+                        _ => (),
+                    }
+                } else {
+                    pos += 2;
+                    match next {
+                        b'*' => ignorable = true,
+                        b'\'' => {
+                            let hex = rtf.get(pos..pos + 2).and_then(|x| std::str::from_utf8(x).ok());
+                            if let Some(byte) = hex.and_then(|x| u8::from_str_radix(x, 16).ok()) {
+                                pos += 2;
+                                if to_skip > 0 {
+                                    to_skip -= 1;
+                                } else if is_emitting(&state) {
+                                    output.push_str(&WINDOWS_1252.decode_without_bom_handling(&[byte]).0);
+                                }
+                            }
+                        }
+                        b'\\' | b'{' | b'}' if is_emitting(&state) => output.push(next as char),
+                        b'~' if is_emitting(&state) => output.push('\u{00A0}'),
+                        b'_' if is_emitting(&state) => output.push('-'),
+                        b'\r' | b'\n' if is_emitting(&state) => output.push('\n'),
+                        _ => (),
+                    }
+                }
+            }
+            byte => {
+                pos += 1;
+                if to_skip > 0 {
+                    to_skip -= 1;
+                } else if is_emitting(&state) {
+                    output.push_str(&WINDOWS_1252.decode_without_bom_handling(&[byte]).0);
+                }
+            }
+        }
+    }
+
+    if from_html {
+        html_to_text(&output)
+    } else {
+        output
+    }
+}
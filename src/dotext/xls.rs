@@ -0,0 +1,162 @@
+use encoding_rs::UTF_16LE;
+
+use std::collections::HashMap;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// BOF, starts the globals and each sheet of the workbook stream
+const BOF: u16 = 0x0809;
+/// CONTINUE, more of the data of the record before it, here the text of a TXO
+const CONTINUE: u16 = 0x003C;
+/// FILEPASS, the records after it are encrypted
+const FILEPASS: u16 = 0x002F;
+/// BOUNDSHEET8, a sheet of the workbook and the offset of its BOF
+const BOUNDSHEET: u16 = 0x0085;
+/// NOTE, the cell and author of a comment, linked to its text by the id of its object
+const NOTE: u16 = 0x001C;
+/// OBJ, a drawing object, a comment when its FtCmo kind is OT_NOTE
+const OBJ: u16 = 0x005D;
+/// TXO, the text box of the object before it, its text in the CONTINUE records after it
+const TXO: u16 = 0x01B6;
+/// The FtCmo kind of the object of a comment
+const OT_NOTE: u16 = 0x0019;
+
+/// Reads the records of a BIFF8 workbook stream as (offset, type, data), see [MS-XLS] 2.1.4.
+fn read_records(data: &[u8]) -> Vec<(usize, u16, &[u8])> {
+    let mut records = Vec::new();
+    let mut pos = 0;
+    while let (Some(typ), Some(size)) = (read_u16(data, pos), read_u16(data, pos + 2)) {
+        let Some(record) = data.get(pos + 4..pos + 4 + size as usize) else {
+            break;
+        };
+        records.push((pos, typ, record));
+        pos += 4 + size as usize;
+    }
+    records
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|x| u16::from_le_bytes(x.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+}
+
+/// Reads `count` characters at `offset`, one byte each (the low bytes of UTF-16) or UTF-16 when `is_wide`, with their size in bytes.
+/// Fewer characters are read if the data ends first.
+fn read_chars(data: &[u8], offset: usize, count: usize, is_wide: bool) -> (String, usize) {
+    let data = data.get(offset..).unwrap_or_default();
+    if is_wide {
+        let bytes = &data[..(count * 2).min(data.len() & !1)];
+        (UTF_16LE.decode_without_bom_handling(bytes).0.into_owned(), bytes.len())
+    } else {
+        let bytes = &data[..count.min(data.len())];
+        (bytes.iter().map(|x| *x as char).collect(), bytes.len())
+    }
+}
+
+/// Reads a ShortXLUnicodeString (a 1 byte count) or an XLUnicodeString (a 2 byte count) at `offset`.
+fn read_unicode_string(data: &[u8], offset: usize, is_short: bool) -> Option<String> {
+    let (count, offset) = if is_short {
+        (*data.get(offset)? as usize, offset + 1)
+    } else {
+        (read_u16(data, offset)? as usize, offset + 2)
+    };
+    let is_wide = data.get(offset)? & 0x01 != 0;
+    Some(read_chars(data, offset + 1, count, is_wide).0)
+}
+
+/// The A1 name of a zero based cell, e.g. `B2` for row 1, column 1.
+fn cell_name(row: u16, col: u16) -> String {
+    let mut col = col as u32 + 1;
+    let mut letters = Vec::new();
+    while col > 0 {
+        letters.push((b'A' + ((col - 1) % 26) as u8) as char);
+        col = (col - 1) / 26;
+    }
+    format!("{}{}", letters.iter().rev().collect::<String>(), row as u32 + 1)
+}
+
+/// Reads the cell comments (notes) of every worksheet in a BIFF8 .xls workbook. An encrypted workbook has none that can be read.
+///
+/// # Returns
+///
+/// * A list of (location, author, comment text), where location is `Sheet!A1`
+pub fn read_comments<P: AsRef<Path>>(path: P) -> io::Result<Vec<(String, String, String)>> {
+    let mut cfbf = cfb::open(path.as_ref())?;
+    let stream_path = if cfbf.is_stream("/Workbook") { "/Workbook" } else { "/Book" };
+    let mut data = Vec::new();
+    cfbf.open_stream(stream_path)?.read_to_end(&mut data)?;
+    let records = read_records(&data);
+
+    let mut sheet_names: HashMap<u32, String> = HashMap::new();
+    let mut sheet_name: Option<String> = None;
+    // the id of the last comment object, whose text the next TXO holds
+    let mut note_object_id: Option<u16> = None;
+    let mut texts: HashMap<u16, String> = HashMap::new();
+    let mut comments = Vec::new();
+    for (irecord, (offset, typ, record)) in records.iter().enumerate() {
+        match *typ {
+            FILEPASS => break,
+            BOUNDSHEET => {
+                // the position of the sheet's BOF, its state and kind, then its name
+                if let (Some(position), Some(name)) = (read_u32(record, 0), read_unicode_string(record, 6, true)) {
+                    sheet_names.insert(position, name);
+                }
+            }
+            BOF => {
+                sheet_name = sheet_names.get(&(*offset as u32)).cloned();
+                note_object_id = None;
+                texts.clear();
+            }
+            // the FtCmo comes first: its type and size, then the object kind and id
+            OBJ => {
+                note_object_id = match (read_u16(record, 4), read_u16(record, 6)) {
+                    (Some(OT_NOTE), Some(id)) => Some(id),
+                    _ => None,
+                };
+            }
+            TXO => {
+                let Some(id) = note_object_id.take() else {
+                    continue;
+                };
+                let mut count = read_u16(record, 10).unwrap_or_default() as usize;
+                let mut text = String::new();
+                // each CONTINUE starts with its own flag for one byte or two byte characters
+                for (_, typ, record) in records.iter().skip(irecord + 1) {
+                    if *typ != CONTINUE || count == 0 {
+                        break;
+                    }
+                    let is_wide = record.first().is_some_and(|x| x & 0x01 != 0);
+                    let (chars, size) = read_chars(record, 1, count, is_wide);
+                    count -= if is_wide { size / 2 } else { size };
+                    text.push_str(&chars);
+                }
+                texts.insert(id, text);
+            }
+            NOTE => {
+                let (Some(sheet_name), Some(row), Some(col), Some(id)) = (&sheet_name, read_u16(record, 0), read_u16(record, 2), read_u16(record, 6)) else {
+                    continue;
+                };
+                let author = read_unicode_string(record, 8, false).unwrap_or_default();
+                let text = texts.get(&id).map(|x| comment_text(x, &author)).unwrap_or_default();
+                comments.push((format!("{}!{}", sheet_name, cell_name(row, col)), author, text));
+            }
+            _ => (),
+        }
+    }
+
+    Ok(comments)
+}
+
+/// The text of a comment without the `Author:` line Excel starts it with.
+pub(crate) fn comment_text(text: &str, author: &str) -> String {
+    let text = text.trim();
+    let text = match text.strip_prefix(author).and_then(|x| x.strip_prefix(':')) {
+        Some(rest) if !author.is_empty() => rest,
+        _ => text,
+    };
+    text.trim().to_string()
+}
@@ -0,0 +1,171 @@
+use zip::ZipArchive;
+
+use encoding_rs::UTF_16LE;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use super::xlsx::{read_relationships, read_zip_part, resolve_target};
+
+/// A cell of a worksheet, zero based.
+#[derive(Debug)]
+pub struct Cell {
+    pub row: u32,
+    pub col: u32,
+    pub text: String,
+}
+
+/// BrtRowHdr, starts the cells of a row
+const BRT_ROW_HDR: u16 = 0x0000;
+/// BrtFmlaError, a formula whose cached value is an error
+const BRT_FMLA_ERROR: u16 = 0x000B;
+/// BrtCellRString, a rich text string stored in the cell
+const BRT_CELL_RSTRING: u16 = 0x003E;
+/// BrtBundleSh, a sheet of the workbook
+const BRT_BUNDLE_SH: u16 = 0x009C;
+/// BrtEndBundleShs, ends the sheets of the workbook
+const BRT_END_BUNDLE_SHS: u16 = 0x0090;
+/// BrtEndSheetData, ends the cells of a worksheet
+const BRT_END_SHEET_DATA: u16 = 0x0092;
+
+/// Reads the records of a binary part as (type, data), see [MS-XLSB] 2.1.4.
+fn read_records(data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut records = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        // the type is 1 or 2 bytes and the size 1 to 4 bytes, 7 bits each, the high bit says another byte follows
+        let mut typ = (data[pos] & 0x7F) as u16;
+        if data[pos] & 0x80 != 0 {
+            let Some(byte) = data.get(pos + 1) else {
+                break;
+            };
+            typ |= ((byte & 0x7F) as u16) << 7;
+            pos += 1;
+        }
+        pos += 1;
+        let mut size = 0;
+        for i in 0..4 {
+            let Some(byte) = data.get(pos) else {
+                break;
+            };
+            pos += 1;
+            size |= ((byte & 0x7F) as usize) << (7 * i);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        let Some(record) = data.get(pos..pos + size) else {
+            break;
+        };
+        records.push((typ, record));
+        pos += size;
+    }
+    records
+}
+
+/// Reads a named part of the zip, empty if the part does not exist.
+fn read_binary_part(archive: &mut ZipArchive<File>, content_name: &str) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    if let Ok(mut c_file) = archive.by_name(content_name) {
+        c_file.read_to_end(&mut data)?;
+    }
+    Ok(data)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+}
+
+/// Reads an XLWideString (a character count then UTF-16) at `offset`, with its size in bytes.
+fn read_wide_string(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let size = read_u32(data, offset)? as usize * 2;
+    let bytes = data.get(offset + 4..offset + 4 + size)?;
+    Some((UTF_16LE.decode_without_bom_handling(bytes).0.into_owned(), 4 + size))
+}
+
+/// The text Excel shows for an error value, see [MS-XLSB] 2.5.97.2 BErr.
+fn error_text(code: u8) -> &'static str {
+    match code {
+        0x00 => "#NULL!",
+        0x07 => "#DIV/0!",
+        0x0F => "#VALUE!",
+        0x17 => "#REF!",
+        0x1D => "#NAME?",
+        0x24 => "#NUM!",
+        0x2A => "#N/A",
+        0x2B => "#GETTING_DATA",
+        _ => "#ERROR!",
+    }
+}
+
+/// Reads the cells of a worksheet part that calamine skips, formulas with an error value and rich text strings.
+fn read_sheet_cells(data: &[u8]) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    let mut row = 0;
+    for (typ, record) in read_records(data) {
+        // every cell record starts with a Cell, the column then the style
+        let text = match typ {
+            BRT_ROW_HDR => {
+                row = read_u32(record, 0).unwrap_or_default();
+                continue;
+            }
+            BRT_FMLA_ERROR => record.get(8).map(|x| error_text(*x).to_string()),
+            // a flags byte comes before the string, the formatting runs after it are not text
+            BRT_CELL_RSTRING => read_wide_string(record, 9).map(|(text, _)| text),
+            BRT_END_SHEET_DATA => break,
+            _ => continue,
+        };
+        if let (Some(col), Some(text)) = (read_u32(record, 0), text) {
+            cells.push(Cell { row, col, text });
+        }
+    }
+    cells
+}
+
+/// Reads the cells of an xlsb workbook that calamine does not read, so they can be added to the sheets it returns.
+///
+/// # Returns
+///
+/// * The cells of each worksheet that has any, by sheet name
+pub fn read_skipped_cells<P: AsRef<Path>>(path: P) -> io::Result<HashMap<String, Vec<Cell>>> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+
+    // sheet names and relationship ids, see [MS-XLSB] 2.4.304 BrtBundleSh
+    let workbook_bin = read_binary_part(&mut archive, "xl/workbook.bin")?;
+    let mut sheets: Vec<(String, String)> = Vec::new();
+    for (typ, record) in read_records(&workbook_bin) {
+        match typ {
+            BRT_BUNDLE_SH => {
+                if let Some((rid, rid_size)) = read_wide_string(record, 8)
+                    && let Some((name, _)) = read_wide_string(record, 8 + rid_size)
+                {
+                    sheets.push((name, rid));
+                }
+            }
+            BRT_END_BUNDLE_SHS => break,
+            _ => (),
+        }
+    }
+
+    let workbook_rels: HashMap<String, String> =
+        read_relationships(&read_zip_part(&mut archive, "xl/_rels/workbook.bin.rels")?)?
+            .into_iter()
+            .map(|(id, _, target)| (id, resolve_target("xl", &target)))
+            .collect();
+
+    let mut cells = HashMap::new();
+    for (sheet_name, rid) in sheets {
+        let Some(sheet_part) = workbook_rels.get(&rid) else {
+            continue;
+        };
+        let sheet_cells = read_sheet_cells(&read_binary_part(&mut archive, sheet_part)?);
+        if !sheet_cells.is_empty() {
+            cells.insert(sheet_name, sheet_cells);
+        }
+    }
+    Ok(cells)
+}
@@ -0,0 +1,545 @@
+use zip::ZipArchive;
+
+use quick_xml::encoding::Decoder;
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesRef, Event};
+use quick_xml::name::QName;
+use quick_xml::reader::Reader;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use super::xls::comment_text;
+
+/// Reads a named part of the zip as a string, an empty string if the part does not exist.
+pub(crate) fn read_zip_part(archive: &mut ZipArchive<File>, content_name: &str) -> io::Result<String> {
+    let mut xml_data = String::new();
+    if let Ok(mut c_file) = archive.by_name(content_name) {
+        c_file.read_to_string(&mut xml_data)?;
+    }
+    Ok(xml_data)
+}
+
+/// Resolves a relationship target relative to the folder of the part that owns it, e.g.
+/// `../comments1.xml` from `xl/worksheets` becomes `xl/comments1.xml`.
+pub(crate) fn resolve_target(base_dir: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|x| !x.is_empty()).collect();
+    for component in target.split('/') {
+        match component {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => (),
+            _ => parts.push(component),
+        }
+    }
+    parts.join("/")
+}
+
+pub(crate) fn attribute_value(attr: &Attribute, decoder: Decoder) -> String {
+    attr.decode_and_unescape_value(decoder)
+        .unwrap_or_default()
+        .into_owned()
+}
+
+/// Resolves a character or predefined entity reference (`&#233;`, `&amp;`), empty if it is neither.
+pub(crate) fn resolve_general_ref(e: &BytesRef) -> String {
+    match e.resolve_char_ref() {
+        Ok(Some(c)) => c.to_string(),
+        _ => quick_xml::escape::resolve_predefined_entity(&e.decode().unwrap_or_default())
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+pub(crate) fn xml_error<T>(xml_reader: &Reader<&[u8]>, e: quick_xml::Error) -> io::Result<T> {
+    Err(io::Error::other(
+        format!(
+            "Error at position {}: {:?}",
+            xml_reader.buffer_position(),
+            e
+        ),
+    ))
+}
+
+/// Whether an attribute is the relationship id of an element, `r:id`, under whatever prefix the part gives the relationships namespace,
+/// which is different in Strict OOXML. An unprefixed `id` is an attribute of the element itself, e.g. the `id` of a `p:sldId`.
+pub(crate) fn is_relationship_id(key: QName) -> bool {
+    key.local_name().as_ref() == b"id" && key.prefix().is_some()
+}
+
+/// Reads the `Relationship` elements of a .rels part as (Id, Type, Target).
+pub(crate) fn read_relationships(xml_data: &str) -> io::Result<Vec<(String, String, String)>> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut relationships = Vec::new();
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"Relationship" => {
+                let mut id = String::new();
+                let mut typ = String::new();
+                let mut target = String::new();
+                for attr in e.attributes().flatten() {
+                    let value = attribute_value(&attr, xml_reader.decoder());
+                    match attr.key.as_ref() {
+                        b"Id" => id = value,
+                        b"Type" => typ = value,
+                        b"Target" => target = value,
+                        _ => (),
+                    }
+                }
+                relationships.push((id, typ, target));
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+    Ok(relationships)
+}
+
+/// Reads the sheets of an OOXML workbook as (sheet name, part name), in workbook order.
+fn read_sheet_parts(archive: &mut ZipArchive<File>) -> io::Result<Vec<(String, String)>> {
+    // sheet names and relationship ids, in workbook order
+    let workbook_xml = read_zip_part(archive, "xl/workbook.xml")?;
+    let mut sheets: Vec<(String, String)> = Vec::new();
+    let mut xml_reader = Reader::from_str(&workbook_xml);
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"sheet" => {
+                let mut name = String::new();
+                let mut rid = String::new();
+                for attr in e.attributes().flatten() {
+                    let value = attribute_value(&attr, xml_reader.decoder());
+                    if is_relationship_id(attr.key) {
+                        rid = value;
+                    } else if attr.key.as_ref() == b"name" {
+                        name = value;
+                    }
+                }
+                sheets.push((name, rid));
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+
+    let workbook_rels: HashMap<String, String> =
+        read_relationships(&read_zip_part(archive, "xl/_rels/workbook.xml.rels")?)?
+            .into_iter()
+            .map(|(id, _, target)| (id, resolve_target("xl", &target)))
+            .collect();
+
+    Ok(sheets
+        .into_iter()
+        .filter_map(|(sheet_name, rid)| workbook_rels.get(&rid).map(|sheet_part| (sheet_name, sheet_part.clone())))
+        .collect())
+}
+
+/// Reads the names of the parts related to a part by relationships of a type, e.g. the `/drawing` of a worksheet.
+fn read_related_parts(archive: &mut ZipArchive<File>, part: &str, type_suffix: &str) -> io::Result<Vec<String>> {
+    let (dir, file) = part.rsplit_once('/').unwrap_or(("", part));
+    let rels_part = if dir.is_empty() {
+        format!("_rels/{}.rels", file)
+    } else {
+        format!("{}/_rels/{}.rels", dir, file)
+    };
+    Ok(read_relationships(&read_zip_part(archive, &rels_part)?)?
+        .into_iter()
+        .filter(|(_, typ, _)| typ.ends_with(type_suffix))
+        .map(|(_, _, target)| resolve_target(dir, &target))
+        .collect())
+}
+
+/// Reads the legacy cell comments (notes) of every worksheet in an OOXML workbook.
+///
+/// # Returns
+///
+/// * A list of (location, author, comment text), where location is `Sheet!A1`
+pub fn read_comments<P: AsRef<Path>>(path: P) -> io::Result<Vec<(String, String, String)>> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+
+    let mut comments = Vec::new();
+    for (sheet_name, sheet_part) in read_sheet_parts(&mut archive)? {
+        for comments_part in read_related_parts(&mut archive, &sheet_part, "/comments")? {
+            let comments_xml = read_zip_part(&mut archive, &comments_part)?;
+            let mut xml_reader = Reader::from_str(&comments_xml);
+            // the authors come first, a comment refers to its author by index
+            let mut authors: Vec<String> = Vec::new();
+            let mut in_author = false;
+            let mut cell_ref: Option<String> = None;
+            let mut author_id: Option<usize> = None;
+            let mut text = String::new();
+            let mut to_read = false;
+            loop {
+                match xml_reader.read_event() {
+                    Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                        b"author" => {
+                            in_author = true;
+                            authors.push(String::new());
+                        }
+                        b"comment" => {
+                            cell_ref = e
+                                .try_get_attribute("ref")
+                                .ok()
+                                .flatten()
+                                .map(|x| attribute_value(&x, xml_reader.decoder()));
+                            author_id = e
+                                .try_get_attribute("authorId")
+                                .ok()
+                                .flatten()
+                                .and_then(|x| attribute_value(&x, xml_reader.decoder()).parse().ok());
+                            text.clear();
+                        }
+                        b"t" => to_read = true,
+                        _ => (),
+                    },
+                    Ok(Event::Text(e)) if in_author => {
+                        if let Some(author) = authors.last_mut() {
+                            author.push_str(&e.decode().unwrap_or_default());
+                        }
+                    }
+                    Ok(Event::Text(e)) if to_read && cell_ref.is_some() => {
+                        text.push_str(&e.decode().unwrap_or_default());
+                    }
+                    Ok(Event::GeneralRef(e)) if to_read && cell_ref.is_some() => {
+                        text.push_str(&resolve_general_ref(&e));
+                    }
+                    Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                        b"author" => in_author = false,
+                        b"t" => to_read = false,
+                        b"comment" => {
+                            if let Some(cell_ref) = cell_ref.take() {
+                                let author = author_id.take().and_then(|x| authors.get(x)).cloned().unwrap_or_default();
+                                let text = comment_text(&text, &author);
+                                comments.push((format!("{}!{}", sheet_name, cell_ref), author, text));
+                            }
+                        }
+                        _ => (),
+                    },
+                    Ok(Event::Eof) => break,
+                    Err(e) => return xml_error(&xml_reader, e),
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    Ok(comments)
+}
+
+/// Reads the text of a chart part: its title, the titles of its axes and the names of its series.
+/// Titles and names linked to cells are read from the cached value stored with the chart.
+fn read_chart_part_text(xml_data: &str) -> io::Result<Vec<(&'static str, String)>> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut texts = Vec::new();
+    // what the text being read is, None outside of any text that is read
+    let mut label: Option<&'static str> = None;
+    let mut in_axis = false;
+    let mut in_series = false;
+    let mut text = String::new();
+    let mut to_read = false;
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"catAx" | b"dateAx" | b"serAx" | b"valAx" => in_axis = true,
+                b"ser" => in_series = true,
+                b"title" => {
+                    label = Some(if in_axis { "axis title" } else { "title" });
+                    text.clear();
+                }
+                b"tx" if in_series && label.is_none() => {
+                    label = Some("series");
+                    text.clear();
+                }
+                // rich text runs, or the cached value of a cell reference
+                b"t" | b"v" if label.is_some() => to_read = true,
+                _ => (),
+            },
+            Ok(Event::Text(e)) if to_read => {
+                text.push_str(&e.decode().unwrap_or_default());
+            }
+            Ok(Event::GeneralRef(e)) if to_read => {
+                text.push_str(&resolve_general_ref(&e));
+            }
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"t" | b"v" => to_read = false,
+                // paragraphs of a title
+                b"p" if label.is_some() && !text.is_empty() => text.push(' '),
+                b"catAx" | b"dateAx" | b"serAx" | b"valAx" => in_axis = false,
+                b"ser" => in_series = false,
+                // the end of a title, or of the name of a series; a title holds its text in a tx element too
+                name @ (b"title" | b"tx") if label.is_some() && (name == b"tx") == (label == Some("series")) => {
+                    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+                    if !text.is_empty() {
+                        texts.push((label.unwrap(), text));
+                    }
+                    label = None;
+                }
+                _ => (),
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+    Ok(texts)
+}
+
+/// Reads the text of the charts in every sheet of an OOXML workbook, see read_chart_part_text.
+///
+/// # Returns
+///
+/// * A list of (location, label, text), where location is `Sheet chart 1`, numbered within the sheet,
+///   and label `title`, `axis title` or `series`
+pub fn read_charts<P: AsRef<Path>>(path: P) -> io::Result<Vec<(String, &'static str, String)>> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+
+    let mut charts = Vec::new();
+    for (sheet_name, sheet_part) in read_sheet_parts(&mut archive)? {
+        let mut chart_number = 0;
+        for drawing_part in read_related_parts(&mut archive, &sheet_part, "/drawing")? {
+            for chart_part in read_related_parts(&mut archive, &drawing_part, "/chart")? {
+                chart_number += 1;
+                for (label, text) in read_chart_part_text(&read_zip_part(&mut archive, &chart_part)?)? {
+                    charts.push((format!("{} chart {}", sheet_name, chart_number), label, text));
+                }
+            }
+        }
+    }
+    Ok(charts)
+}
+
+/// Reads the input prompts and error alerts of the data validations of every worksheet in an OOXML workbook,
+/// the messages shown when a cell is selected or given a value that is not allowed.
+///
+/// # Returns
+///
+/// * A list of (location, label, text), where location is `Sheet!B1:B9`, label `prompt` or `error`, and text `title: message`
+pub fn read_data_validations<P: AsRef<Path>>(path: P) -> io::Result<Vec<(String, &'static str, String)>> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+
+    let mut validations = Vec::new();
+    for (sheet_name, sheet_part) in read_sheet_parts(&mut archive)? {
+        let sheet_xml = read_zip_part(&mut archive, &sheet_part)?;
+        if !sheet_xml.contains("dataValidation") {
+            continue;
+        }
+        let mut xml_reader = Reader::from_str(&sheet_xml);
+        // the attributes of the dataValidation being read, by name
+        let mut attributes: Option<HashMap<Vec<u8>, String>> = None;
+        // the range of a validation stored in an extension, as the text of an `xm:sqref` element
+        let mut sqref = String::new();
+        let mut to_read = false;
+        loop {
+            let event = xml_reader.read_event();
+            let ended = match event {
+                Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"dataValidation" => {
+                    attributes = Some(
+                        e.attributes()
+                            .flatten()
+                            .map(|x| (x.key.local_name().as_ref().to_vec(), attribute_value(&x, xml_reader.decoder())))
+                            .collect(),
+                    );
+                    sqref.clear();
+                    matches!(event, Ok(Event::Empty(_)))
+                }
+                Ok(Event::Start(ref e)) if attributes.is_some() && e.local_name().as_ref() == b"sqref" => {
+                    to_read = true;
+                    false
+                }
+                Ok(Event::Text(ref e)) if to_read => {
+                    sqref.push_str(&e.decode().unwrap_or_default());
+                    false
+                }
+                Ok(Event::End(ref e)) => {
+                    to_read = false;
+                    e.local_name().as_ref() == b"dataValidation"
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => return xml_error(&xml_reader, e),
+                _ => false,
+            };
+            if ended && let Some(attributes) = attributes.take() {
+                let get = |name: &[u8]| attributes.get(name).map(|x| x.trim()).unwrap_or_default();
+                let range = if get(b"sqref").is_empty() { sqref.trim() } else { get(b"sqref") };
+                let location = format!("{}!{}", sheet_name, range);
+                for (label, title, message) in [("prompt", get(b"promptTitle"), get(b"prompt")), ("error", get(b"errorTitle"), get(b"error"))] {
+                    let text = match (title.is_empty(), message.is_empty()) {
+                        (false, false) => format!("{}: {}", title, message),
+                        (false, true) => title.to_string(),
+                        (true, false) => message.to_string(),
+                        (true, true) => continue,
+                    };
+                    validations.push((location.clone(), label, text));
+                }
+            }
+        }
+    }
+    Ok(validations)
+}
+
+/// Reads the text of the `si` items of a shared strings part, in order, with the runs of rich text joined.
+/// Phonetic readings (`rPh`) are left out. Reading stops at the first malformed XML, keeping the items read so far.
+fn read_shared_strings(xml_data: &str) -> Vec<String> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut shared_strings = Vec::new();
+    let mut text = String::new();
+    let mut in_phonetic = false;
+    let mut to_read = false;
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"si" => text.clear(),
+                b"rPh" => in_phonetic = true,
+                b"t" => to_read = !in_phonetic,
+                _ => (),
+            },
+            Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"si" => shared_strings.push(String::new()),
+            Ok(Event::Text(e)) if to_read => text.push_str(&e.decode().unwrap_or_default()),
+            Ok(Event::GeneralRef(e)) if to_read => text.push_str(&resolve_general_ref(&e)),
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"si" => shared_strings.push(std::mem::take(&mut text)),
+                b"rPh" => in_phonetic = false,
+                b"t" => to_read = false,
+                _ => (),
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => (),
+        }
+    }
+    shared_strings
+}
+
+/// Columns of a worksheet, `A` to `XFD`.
+const MAX_COLUMNS: usize = 16384;
+
+/// The zero based column of a cell reference, e.g. 27 for `AB12`, None if it does not start with letters
+/// or is past the last column `XFD`, as in a corrupt sheet.
+fn column_index(cell_ref: &str) -> Option<usize> {
+    let letters: Vec<u8> = cell_ref.bytes().take_while(|x| x.is_ascii_alphabetic()).collect();
+    if letters.is_empty() {
+        return None;
+    }
+    let column = letters.iter().try_fold(0usize, |acc, x| {
+        acc.checked_mul(26)?.checked_add((x.to_ascii_uppercase() - b'A' + 1) as usize).filter(|x| *x <= MAX_COLUMNS)
+    })?;
+    Some(column - 1)
+}
+
+/// Reads the cells of a worksheet part as rows of text, each cell in its column. A shared string cell (`t="s"`)
+/// is looked up in `shared_strings`, an inline string is read from its `is` element, and any other cell is its stored value,
+/// e.g. the cached result of a formula, unformatted. Reading stops at the first malformed XML, keeping the cells read so far,
+/// and once a row starts past `max_rows` rows or `max_cells` cells with text, keeping the row that passed them.
+fn read_sheet_cells(xml_data: &str, shared_strings: &[String], max_rows: Option<usize>, max_cells: Option<usize>) -> Vec<Vec<String>> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let (mut text_row_count, mut text_cell_count) = (0usize, 0usize);
+    // the cell being read: its column, type and text
+    let mut cell: Option<(usize, String, String)> = None;
+    let mut to_read = false;
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"row" => {
+                    if let Some(row) = rows.last() {
+                        let row_cells = row.iter().filter(|x| !x.is_empty()).count();
+                        text_row_count += (row_cells > 0) as usize;
+                        text_cell_count += row_cells;
+                    }
+                    if max_rows.is_some_and(|x| text_row_count > x) || max_cells.is_some_and(|x| text_cell_count > x) {
+                        break;
+                    }
+                    rows.push(Vec::new());
+                }
+                b"c" => {
+                    let mut column = rows.last().map(|x| x.len()).unwrap_or_default();
+                    let mut typ = String::new();
+                    for attr in e.attributes().flatten() {
+                        match attr.key.as_ref() {
+                            b"r" => column = column_index(&attribute_value(&attr, xml_reader.decoder())).unwrap_or(column),
+                            b"t" => typ = attribute_value(&attr, xml_reader.decoder()),
+                            _ => (),
+                        }
+                    }
+                    cell = Some((column, typ, String::new()));
+                }
+                b"v" | b"t" => to_read = cell.is_some(),
+                _ => (),
+            },
+            Ok(Event::Text(e)) if to_read && let Some((_, _, text)) = cell.as_mut() => {
+                text.push_str(&e.decode().unwrap_or_default());
+            }
+            Ok(Event::GeneralRef(e)) if to_read && let Some((_, _, text)) = cell.as_mut() => {
+                text.push_str(&resolve_general_ref(&e));
+            }
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"v" | b"t" => to_read = false,
+                b"c" => {
+                    if let Some((column, typ, text)) = cell.take() {
+                        let text = match typ.as_str() {
+                            "s" => text.trim().parse::<usize>().ok().and_then(|x| shared_strings.get(x)).cloned().unwrap_or_default(),
+                            _ => text,
+                        };
+                        if rows.is_empty() {
+                            rows.push(Vec::new());
+                        }
+                        let row = rows.last_mut().unwrap();
+                        if row.len() <= column {
+                            row.resize(column + 1, String::new());
+                        }
+                        row[column] = text;
+                    }
+                }
+                _ => (),
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => (),
+        }
+    }
+    rows
+}
+
+/// Reads the cell text of every worksheet of an OOXML workbook straight from its XML parts, as a fallback for a workbook
+/// that calamine cannot open, e.g. one with a damaged styles part. The sheets are those named by the workbook, or if it
+/// cannot be read the `xl/worksheets/*.xml` parts, named by their file stem. Values are not formatted.
+/// Each sheet is read up to `max_rows` rows or `max_cells` cells with text, and the row that passes them.
+///
+/// # Returns
+///
+/// * A list of (sheet name, rows of cell text)
+pub fn read_raw_cells<P: AsRef<Path>>(path: P, max_rows: Option<usize>, max_cells: Option<usize>) -> io::Result<Vec<(String, Vec<Vec<String>>)>> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+
+    let sheet_parts = match read_sheet_parts(&mut archive) {
+        Ok(sheet_parts) if !sheet_parts.is_empty() => sheet_parts,
+        _ => {
+            let mut sheet_parts: Vec<(String, String)> = archive
+                .file_names()
+                .filter(|x| x.starts_with("xl/worksheets/") && x.ends_with(".xml") && x.matches('/').count() == 2)
+                .map(|x| (x["xl/worksheets/".len()..x.len() - 4].to_string(), x.to_string()))
+                .collect();
+            // sheet2 before sheet10
+            sheet_parts.sort_by_key(|(name, _)| (name.len(), name.clone()));
+            sheet_parts
+        }
+    };
+    let shared_strings = read_shared_strings(&read_zip_part(&mut archive, "xl/sharedStrings.xml").unwrap_or_default());
+
+    let mut sheets = Vec::new();
+    for (sheet_name, sheet_part) in sheet_parts {
+        let sheet_xml = read_zip_part(&mut archive, &sheet_part)?;
+        sheets.push((sheet_name, read_sheet_cells(&sheet_xml, &shared_strings, max_rows, max_cells)));
+    }
+    Ok(sheets)
+}
@@ -0,0 +1,82 @@
+use zip::ZipArchive;
+
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use super::xlsx::{attribute_value, read_relationships, read_zip_part, resolve_target, xml_error};
+
+/// Reads the `Source` attributes of the elements named `element` of an XPS part, resolved to part names.
+fn read_sources(archive: &mut ZipArchive<File>, part: &str, element: &[u8]) -> io::Result<Vec<String>> {
+    let xml_data = read_zip_part(archive, part)?;
+    let base_dir = part.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+    let mut xml_reader = Reader::from_str(&xml_data);
+    let mut sources = Vec::new();
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.local_name().as_ref() == element => {
+                if let Ok(Some(source)) = e.try_get_attribute("Source") {
+                    sources.push(resolve_target(base_dir, &attribute_value(&source, xml_reader.decoder())));
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+    Ok(sources)
+}
+
+/// Reads the text of a FixedPage part, a line per `Glyphs` element.
+fn read_page_text(xml_data: &str) -> io::Result<String> {
+    let mut xml_reader = Reader::from_str(xml_data);
+    let mut text = String::new();
+    loop {
+        match xml_reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"Glyphs" => {
+                if let Ok(Some(unicode_string)) = e.try_get_attribute("UnicodeString") {
+                    let unicode_string = attribute_value(&unicode_string, xml_reader.decoder());
+                    // a leading {} escapes a string that starts with {, see the XPS specification 5.1.4
+                    let unicode_string = unicode_string.strip_prefix("{}").unwrap_or(&unicode_string);
+                    if !unicode_string.trim().is_empty() {
+                        text.push_str(unicode_string);
+                        text.push('\n');
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return xml_error(&xml_reader, e),
+            _ => (),
+        }
+    }
+    Ok(text)
+}
+
+/// Reads the text of the pages of an XPS or OpenXPS document, in document order:
+/// the FixedDocumentSequence names the FixedDocuments, which name their FixedPages.
+///
+/// # Returns
+///
+/// * The text of each page
+pub fn read_pages<P: AsRef<Path>>(path: P) -> io::Result<Vec<String>> {
+    let file = File::open(path.as_ref())?;
+    let mut archive = ZipArchive::new(file)?;
+
+    // the package relationship to the FixedDocumentSequence, of either the XPS or the OpenXPS type
+    let sequence_part = read_relationships(&read_zip_part(&mut archive, "_rels/.rels")?)?
+        .into_iter()
+        .find(|(_, typ, _)| typ.ends_with("/fixedrepresentation"))
+        .map(|(_, _, target)| resolve_target("", &target))
+        .ok_or_else(|| io::Error::other("No FixedDocumentSequence in XPS package"))?;
+
+    let mut pages = Vec::new();
+    for document_part in read_sources(&mut archive, &sequence_part, b"DocumentReference")? {
+        for page_part in read_sources(&mut archive, &document_part, b"PageContent")? {
+            pages.push(read_page_text(&read_zip_part(&mut archive, &page_part)?)?);
+        }
+    }
+    Ok(pages)
+}
@@ -0,0 +1,258 @@
+use std::fmt;
+
+/// The kinds of image that are read by OCR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageKind {
+    Jpeg,
+    Pgm,
+    Png,
+    Ppm,
+}
+
+/// A file type, as detected from a file's extension and magic bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FileType {
+    /// Binary data that is not extracted, e.g. a .docx that is not a zip
+    Bin,
+    /// Compound File Binary (OLE) container
+    Cfb,
+    /// DjVu scanned document
+    Djvu,
+    Doc,
+    Docm,
+    Docx,
+    /// AutoCAD drawing exchange format, ASCII
+    Dxf,
+    Eml,
+    /// Password protected office document. Only detected, there is no such extension.
+    Encrypted,
+    Gzip,
+    /// Hancom Office (Hangul) document, a cfb container, only detected
+    Hwp,
+    /// Hancom Office OWPML document, zip based
+    Hwpx,
+    Image(ImageKind),
+    /// Apple Keynote presentation
+    Key,
+    Markdown,
+    /// MHTML web page archive
+    Mht,
+    Msg,
+    /// Apple Numbers spreadsheet
+    Numbers,
+    Ods,
+    Odt,
+    /// OneNote section, only detected, its revision store format is not read
+    One,
+    /// Apple Pages document
+    Pages,
+    Pdf,
+    Ppsx,
+    Pptm,
+    Pptx,
+    Rtf,
+    SevenZip,
+    Tar,
+    Text,
+    Xlam,
+    Xls,
+    Xlsb,
+    Xlsm,
+    Xlsx,
+    /// XPS or OpenXPS fixed layout document
+    Xps,
+    Zip,
+    /// Any other extension, lowercase
+    Other(String),
+}
+
+impl FileType {
+    /// Every file type except `Other`.
+    pub const KNOWN: [FileType; 40] = [
+        FileType::Bin,
+        FileType::Cfb,
+        FileType::Djvu,
+        FileType::Doc,
+        FileType::Docm,
+        FileType::Docx,
+        FileType::Dxf,
+        FileType::Eml,
+        FileType::Encrypted,
+        FileType::Gzip,
+        FileType::Hwp,
+        FileType::Hwpx,
+        FileType::Image(ImageKind::Jpeg),
+        FileType::Image(ImageKind::Pgm),
+        FileType::Image(ImageKind::Png),
+        FileType::Image(ImageKind::Ppm),
+        FileType::Key,
+        FileType::Markdown,
+        FileType::Mht,
+        FileType::Msg,
+        FileType::Numbers,
+        FileType::Ods,
+        FileType::Odt,
+        FileType::One,
+        FileType::Pages,
+        FileType::Pdf,
+        FileType::Ppsx,
+        FileType::Pptm,
+        FileType::Pptx,
+        FileType::Rtf,
+        FileType::SevenZip,
+        FileType::Tar,
+        FileType::Text,
+        FileType::Xlam,
+        FileType::Xls,
+        FileType::Xlsb,
+        FileType::Xlsm,
+        FileType::Xlsx,
+        FileType::Xps,
+        FileType::Zip,
+    ];
+
+    /// Maps a file extension (without the dot, any case) to its file type.
+    pub fn from_extension(extension: &str) -> FileType {
+        match extension.to_lowercase().as_str() {
+            "bin" => FileType::Bin,
+            "cfb" => FileType::Cfb,
+            "djv" | "djvu" => FileType::Djvu,
+            "doc" => FileType::Doc,
+            "docm" => FileType::Docm,
+            "docx" => FileType::Docx,
+            "dxf" => FileType::Dxf,
+            "eml" => FileType::Eml,
+            "encrypted" => FileType::Encrypted,
+            "gz" | "gzip" | "tgz" => FileType::Gzip,
+            "hwp" => FileType::Hwp,
+            "hwpx" => FileType::Hwpx,
+            "jpeg" | "jpg" => FileType::Image(ImageKind::Jpeg),
+            "pgm" => FileType::Image(ImageKind::Pgm),
+            "png" => FileType::Image(ImageKind::Png),
+            "ppm" => FileType::Image(ImageKind::Ppm),
+            "key" => FileType::Key,
+            "markdown" | "md" => FileType::Markdown,
+            "mht" | "mhtml" => FileType::Mht,
+            "msg" => FileType::Msg,
+            "numbers" => FileType::Numbers,
+            "ods" => FileType::Ods,
+            "odt" => FileType::Odt,
+            "one" => FileType::One,
+            "pages" => FileType::Pages,
+            "pdf" => FileType::Pdf,
+            "ppsx" => FileType::Ppsx,
+            "pptm" => FileType::Pptm,
+            "pptx" => FileType::Pptx,
+            "rtf" => FileType::Rtf,
+            "7z" => FileType::SevenZip,
+            "tar" => FileType::Tar,
+            "txt" => FileType::Text,
+            "xlam" => FileType::Xlam,
+            "xls" => FileType::Xls,
+            "xlsb" => FileType::Xlsb,
+            "xlsm" => FileType::Xlsm,
+            "xlsx" => FileType::Xlsx,
+            "oxps" | "xps" => FileType::Xps,
+            "zip" => FileType::Zip,
+            other => FileType::Other(other.to_string()),
+        }
+    }
+
+    /// The usual extension of the file type, without the dot.
+    pub fn extension(&self) -> &str {
+        match self {
+            FileType::Bin => "bin",
+            FileType::Cfb => "cfb",
+            FileType::Djvu => "djvu",
+            FileType::Doc => "doc",
+            FileType::Docm => "docm",
+            FileType::Docx => "docx",
+            FileType::Dxf => "dxf",
+            FileType::Eml => "eml",
+            FileType::Encrypted => "encrypted",
+            FileType::Gzip => "gzip",
+            FileType::Hwp => "hwp",
+            FileType::Hwpx => "hwpx",
+            FileType::Image(ImageKind::Jpeg) => "jpg",
+            FileType::Image(ImageKind::Pgm) => "pgm",
+            FileType::Image(ImageKind::Png) => "png",
+            FileType::Image(ImageKind::Ppm) => "ppm",
+            FileType::Key => "key",
+            FileType::Markdown => "md",
+            FileType::Mht => "mht",
+            FileType::Msg => "msg",
+            FileType::Numbers => "numbers",
+            FileType::Ods => "ods",
+            FileType::Odt => "odt",
+            FileType::One => "one",
+            FileType::Pages => "pages",
+            FileType::Pdf => "pdf",
+            FileType::Ppsx => "ppsx",
+            FileType::Pptm => "pptm",
+            FileType::Pptx => "pptx",
+            FileType::Rtf => "rtf",
+            FileType::SevenZip => "7z",
+            FileType::Tar => "tar",
+            FileType::Text => "txt",
+            FileType::Xlam => "xlam",
+            FileType::Xls => "xls",
+            FileType::Xlsb => "xlsb",
+            FileType::Xlsm => "xlsm",
+            FileType::Xlsx => "xlsx",
+            FileType::Xps => "xps",
+            FileType::Zip => "zip",
+            FileType::Other(extension) => extension,
+        }
+    }
+
+    /// The MIME type, `application/octet-stream` if there is no specific one.
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            FileType::Bin | FileType::Encrypted | FileType::Other(_) => "application/octet-stream",
+            FileType::Cfb => "application/x-ole-storage",
+            FileType::Djvu => "image/vnd.djvu",
+            FileType::Doc => "application/msword",
+            FileType::Docm => "application/vnd.ms-word.document.macroEnabled.12",
+            FileType::Docx => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            FileType::Dxf => "image/vnd.dxf",
+            FileType::Eml => "message/rfc822",
+            FileType::Gzip => "application/gzip",
+            FileType::Hwp => "application/x-hwp",
+            FileType::Hwpx => "application/hwp+zip",
+            FileType::Image(ImageKind::Jpeg) => "image/jpeg",
+            FileType::Image(ImageKind::Pgm) => "image/x-portable-graymap",
+            FileType::Image(ImageKind::Png) => "image/png",
+            FileType::Image(ImageKind::Ppm) => "image/x-portable-pixmap",
+            FileType::Key => "application/vnd.apple.keynote",
+            FileType::Markdown => "text/markdown",
+            FileType::Mht => "multipart/related",
+            FileType::Msg => "application/vnd.ms-outlook",
+            FileType::Numbers => "application/vnd.apple.numbers",
+            FileType::Ods => "application/vnd.oasis.opendocument.spreadsheet",
+            FileType::Odt => "application/vnd.oasis.opendocument.text",
+            FileType::One => "application/onenote",
+            FileType::Pages => "application/vnd.apple.pages",
+            FileType::Pdf => "application/pdf",
+            FileType::Ppsx => "application/vnd.openxmlformats-officedocument.presentationml.slideshow",
+            FileType::Pptm => "application/vnd.ms-powerpoint.presentation.macroEnabled.12",
+            FileType::Pptx => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+            FileType::Rtf => "application/rtf",
+            FileType::SevenZip => "application/x-7z-compressed",
+            FileType::Tar => "application/x-tar",
+            FileType::Text => "text/plain",
+            FileType::Xlam => "application/vnd.ms-excel.addin.macroEnabled.12",
+            FileType::Xls => "application/vnd.ms-excel",
+            FileType::Xlsb => "application/vnd.ms-excel.sheet.binary.macroEnabled.12",
+            FileType::Xlsm => "application/vnd.ms-excel.sheet.macroEnabled.12",
+            FileType::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            FileType::Xps => "application/vnd.ms-xpsdocument",
+            FileType::Zip => "application/zip",
+        }
+    }
+}
+
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
@@ -6,56 +6,117 @@
 //! logging and walks a directory recursively.
 
 
-use calamine::{open_workbook_auto, DataType, Reader};
+use calamine::{open_workbook_auto, Data, DataType, Reader, Sheets, Xlsx, XlsxError};
 use cfb::CompoundFile;
-use crc_fast::{checksum_file, CrcAlgorithm::Crc64Nvme};
+use crc_fast::{checksum, checksum_file, CrcAlgorithm::Crc64Nvme};
 use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
 use encoding_rs_io::DecodeReaderBytesBuilder;
+use flate2::read::MultiGzDecoder;
 use log::*;
-use mail_parser::{MessageParser, MimeHeaders};
+use mail_parser::{
+	decoders::{base64::base64_decode, html::html_to_text, quoted_printable::quoted_printable_decode},
+	Message, MessageParser, MessagePartId, MimeHeaders, PartType,
+};
 use serde::{Serialize, Deserialize};
-use sevenz_rust::decompress_file_with_password;
+use sevenz_rust::{decompress_with_extract_fn_and_password, default_entry_extract_fn, SevenZArchiveEntry};
 use std::{
-	collections::HashSet,
+	collections::{BTreeMap, HashMap, HashSet, VecDeque},
 	error::Error,
 	fs::{self, File},
-	io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+	io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
 	path::{Path, PathBuf},
-	process::Command,
+	process::{Command, Output},
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, OnceLock,
     },
+	thread,
+	time::{Duration, Instant},
 };
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo, Level};
+use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
 use walkdir::WalkDir;
 use zip::{result::ZipError, ZipArchive};
 
 mod ancillary;
-use ancillary::tempfiles_location;
+use ancillary::{remove_stale_temp_dirs, tempfiles_location};
 
 mod dotext;
+mod file_type;
+pub use file_type::{FileType, ImageKind};
 use dotext::doc::{MsDoc, OpenOfficeDoc};
+use dotext::docprops;
 use dotext::docx::Docx;
-use dotext::odt::Odt;
+use dotext::dxf;
+use dotext::hwpx;
+use dotext::odt::{self, Odt};
+use dotext::markdown;
+use dotext::pptx;
+use dotext::rtf;
+use dotext::xls;
+use dotext::xlsb;
+use dotext::xlsx;
+use dotext::xps;
 
 const DELETE_TEMP_FILES:bool = true;
+/// Temp folders untouched for this long are taken to be left by a run that crashed, see ExtractConfig::stale_temp_dir_age
+const STALE_TEMP_DIR_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Texts with fewer characters than this, not counting whitespace, are never taken as garbled, see is_garbled_text
+const GARBLED_TEXT_MIN_LEN: usize = 20;
+/// Note of a PDF page whose text layer looks like glyph codes rather than text
+const GARBLED_TEXT_LAYER_NOTE: &str = "likely unextractable text layer";
+
 
 struct MagicBytes {
-	extension: &'static str,
+	file_type: FileType,
 	bytes: &'static [u8],
 }
 
 // https://en.wikipedia.org/wiki/List_of_file_signatures
-const MAGIC_BYTES: [MagicBytes; 8] = [
-	MagicBytes { extension: "cfb", bytes: &[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1] },
-	MagicBytes { extension: "7z", bytes: &[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C] },
-	MagicBytes { extension: "pdf", bytes: &[0x25, 0x50, 0x44, 0x46, 0x2D] },
-	MagicBytes { extension: "zip", bytes: &[0x50, 0x4B, 0x03, 0x04] },
-	MagicBytes { extension: "txt", bytes: &[0xEF, 0xBB, 0xBF] },
-	MagicBytes { extension: "gzip", bytes: &[0x1F, 0x8B] },
-	MagicBytes { extension: "txt", bytes: &[0xFE, 0xFF] },
-	MagicBytes { extension: "txt", bytes: &[0xFF, 0xFE] },
+const MAGIC_BYTES: [MagicBytes; 11] = [
+	MagicBytes { file_type: FileType::Cfb, bytes: &[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1] },
+	MagicBytes { file_type: FileType::SevenZip, bytes: &[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C] },
+	MagicBytes { file_type: FileType::Pdf, bytes: &[0x25, 0x50, 0x44, 0x46, 0x2D] },
+	MagicBytes { file_type: FileType::Zip, bytes: &[0x50, 0x4B, 0x03, 0x04] },
+	MagicBytes { file_type: FileType::Djvu, bytes: &[0x41, 0x54, 0x26, 0x54] },
+	// {\rtf
+	MagicBytes { file_type: FileType::Rtf, bytes: &[0x7B, 0x5C, 0x72, 0x74, 0x66] },
+	// guidFileType of a OneNote section, {7B5C52E4-D88C-4DA7-AEB1-5378D02996D3}
+	MagicBytes { file_type: FileType::One, bytes: &[0xE4, 0x52, 0x5C, 0x7B, 0x8C, 0xD8, 0xA7, 0x4D, 0xAE, 0xB1, 0x53, 0x78, 0xD0, 0x29, 0x96, 0xD3] },
+	MagicBytes { file_type: FileType::Text, bytes: &[0xEF, 0xBB, 0xBF] },
+	MagicBytes { file_type: FileType::Gzip, bytes: &[0x1F, 0x8B] },
+	MagicBytes { file_type: FileType::Text, bytes: &[0xFE, 0xFF] },
+	MagicBytes { file_type: FileType::Text, bytes: &[0xFF, 0xFE] },
+];
+// ustar tar archives have their magic in the first header block, not at the start of the file
+const TAR_MAGIC_OFFSET: usize = 257;
+const TAR_MAGIC: &[u8] = b"ustar";
+/// Bytes and lines of a file read to tell whether it is delimited text
+const DELIMITED_TEXT_SAMPLE_LEN: usize = 8192;
+const DELIMITED_TEXT_SAMPLE_LINES: usize = 5;
+/// Archives, whose members are listed as files of their own.
+const ARCHIVE_FILE_TYPES: [FileType; 4] = [FileType::Gzip, FileType::SevenZip, FileType::Tar, FileType::Zip];
+/// Types stored in a cfb (OLE) container.
+const CFB_FILE_TYPES: [FileType; 4] = [FileType::Msg, FileType::Doc, FileType::Hwp, FileType::Xls];
+/// Types stored in a zip container.
+const ZIP_FILE_TYPES: [FileType; 16] = [
+	FileType::Docx,
+	FileType::Docm,
+	FileType::Hwpx,
+	FileType::Key,
+	FileType::Numbers,
+	FileType::Pages,
+	FileType::Ods,
+	FileType::Odt,
+	FileType::Ppsx,
+	FileType::Pptm,
+	FileType::Pptx,
+	FileType::Xlam,
+	FileType::Xlsx,
+	FileType::Xlsm,
+	FileType::Xlsb,
+	FileType::Xps,
 ];
 // const IMAGE_MAGIC_BYTES: [MagicBytes; 1] = [
 // 	MagicBytes { extension: "jpg", bytes: &[0xFF, 0xD8, 0xFF] },
@@ -71,96 +132,329 @@ const FILENAME_ILLEGAL_CHARS: [char; 9] = ['/' , '?' , '<' , '>' , '\\' , ':' ,
 // ];
 
 pub const MAX_FILE_SIZE: u64 = 1_000_000_000; // 1GB in bytes
+pub const OCR_MAX_IMAGE_PIXELS: u64 = 40_000_000; // a letter page scanned at 600dpi is about 34 megapixels
+const DEFAULT_PARAGRAPH_SEPARATOR: &str = "\n\n";
+/// Bytes of text kept either side of a search term that is found, see ExtractConfig::search_terms.
+const SEARCH_CONTEXT_LEN: usize = 80;
+/// Words ending in a full stop that do not end a sentence, see Segmentation::Sentence. Compared ignoring case.
+const SENTENCE_ABBREVIATIONS: [&str; 16] = [
+	"approx.", "dept.", "dr.", "e.g.", "etc.", "fig.", "i.e.", "inc.", "jr.", "ltd.", "mr.", "mrs.", "ms.", "no.", "prof.", "st.",
+];
+/// Preview renderings stored in iWork documents, whose own content is in the IWA protobuf format. The pdf preview holds the text.
+const IWORK_PREVIEW_PDFS: [&str; 2] = ["preview.pdf", "QuickLook/Preview.pdf"];
+/// Read by OCR when an iWork document has no pdf preview.
+const IWORK_PREVIEW_IMAGES: [&str; 2] = ["preview.jpg", "QuickLook/Thumbnail.jpg"];
+const DEFAULT_SKIP_TEXT_EXTENSIONS: &[&str] = &[
+	"avi", "dll", "exe", "flac", "iso", "m4a", "mkv", "mov", "mp3", "mp4", "msi", "ogg", "wav", "webm", "wmv",
+];
 
-fn get_effective_file_extension(filepath: &Path) -> String {
+fn get_effective_file_type(filepath: &Path) -> FileType {
 	//handled extensions
-	let file_extension = filepath.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+	let extension_file_type = FileType::from_extension(&filepath.extension().unwrap_or_default().to_string_lossy());
 
 	//cfb DOCFILE magic bytes file types
-	if [
-		String::from("msg"),
-		String::from("doc"),
-		String::from("xls"),
-	].contains(&file_extension) {
-		let cfb_bytes = MAGIC_BYTES.iter().find(|x| x.extension=="cfb").unwrap().bytes;
+	if CFB_FILE_TYPES.contains(&extension_file_type) {
+		let cfb_bytes = MAGIC_BYTES.iter().find(|x| x.file_type == FileType::Cfb).unwrap().bytes;
 		// println!("cfb_bytes: {:?}", cfb_bytes);
 		if let Ok(mut file) = File::open(filepath) {
 			let mut header = [0u8; 8];
 			if file.read_exact(&mut header).is_ok() {
 				// println!("header: {:?}", header);
 				if header == cfb_bytes {
-					return file_extension;
+					return extension_file_type;
 				}
 			}
 		}
-		return "bin".to_string();
+		//Word saves RTF and Excel saves CSV under the .doc and .xls names they were opened with
+		if magic_file_type(filepath) == Some(FileType::Rtf) {
+			return FileType::Rtf;
+		}
+		if extension_file_type == FileType::Xls && is_delimited_text(filepath, &[',', ';', '\t']) {
+			return FileType::Other(String::from("csv"));
+		}
+		return FileType::Bin;
 	}
 
 	//zip file types
-	if [
-		String::from("docx"),
-		String::from("docm"),
-		String::from("ods"),
-		String::from("odt"),
-		String::from("xlam"),
-		String::from("xlsx"),
-		String::from("xlsm"),
-		String::from("xlsb"),
-	].contains(&file_extension) {
-		let zip_bytes = MAGIC_BYTES.iter().find(|x| x.extension=="zip").unwrap().bytes;
+	if ZIP_FILE_TYPES.contains(&extension_file_type) {
+		let zip_bytes = MAGIC_BYTES.iter().find(|x| x.file_type == FileType::Zip).unwrap().bytes;
+		let cfb_bytes = MAGIC_BYTES.iter().find(|x| x.file_type == FileType::Cfb).unwrap().bytes;
 		// println!("zip_bytes: {:?}", zip_bytes);
 		if let Ok(mut file) = File::open(filepath) {
-			let mut header = [0u8; 4];
+			let mut header = [0u8; 8];
 			if file.read_exact(&mut header).is_ok() {
 				// println!("header: {:?}", header);
-				if header == zip_bytes {
-					return file_extension;
+				if header[0..4] == *zip_bytes {
+					return extension_file_type;
+				}
+				//password protected OOXML is a cfb holding an EncryptedPackage stream
+				if header == cfb_bytes && is_encrypted_ooxml(filepath) {
+					return FileType::Encrypted;
 				}
 			}
 		}
-		return "bin".to_string();
+		return FileType::Bin;
 	}
 	
 	//magic bytes
+	magic_file_type(filepath).unwrap_or(extension_file_type)
+}
+
+/// Detects the type of a file from its magic bytes, see MAGIC_BYTES. None if no magic bytes match or the file can't be read.
+fn magic_file_type(filepath: &Path) -> Option<FileType> {
 	match filepath.metadata() {
 		Ok(metadata) => {
-			if metadata.len() < 16 {
-				return file_extension;
-			}
 			match File::open(filepath) {
-				Ok(mut file) => {
-					let mut header = [0u8; 8];
-					file.read_exact(&mut header).unwrap();
+				Ok(file) => {
+					let mut header = Vec::with_capacity(TAR_MAGIC_OFFSET + TAR_MAGIC.len());
+					if let Err(e) = file.take((TAR_MAGIC_OFFSET + TAR_MAGIC.len()) as u64).read_to_end(&mut header) {
+						error!("Error reading header bytes from file {:?}. {:?}", filepath, e);
+						return None;
+					}
 					for magic_bytes in MAGIC_BYTES {
-						if *magic_bytes.bytes == header[0..magic_bytes.bytes.len()] {
-							return String::from(magic_bytes.extension);
+						//files under 16 bytes are only checked for the text byte order marks, anything else that small is not a real container
+						if metadata.len() < 16 && magic_bytes.file_type != FileType::Text {
+							continue;
 						}
+						if header.starts_with(magic_bytes.bytes) {
+							return Some(magic_bytes.file_type);
+						}
+					}
+					//a tar is at least one 512 byte header block
+					if metadata.len() >= 512 && header.get(TAR_MAGIC_OFFSET..) == Some(TAR_MAGIC) {
+						return Some(FileType::Tar);
 					}
 				}
 				Err(e) => {
 					error!("Error reading header bytes from file {:?}. {:?}", filepath, e);
-					return file_extension;
 				}
 			}
 		}
 		Err(e) => {
 			error!("Error getting file metadata {:?}. {:?}", filepath, e);
-			return file_extension;
 		}
 	}
+	None
+}
+
+/// Whether a file's magic bytes disagree with its extension, e.g. an `invoice.pdf` that is a zip or a `.jpg` that is a PDF,
+/// as renamed files are. Only extensions of the known file types are checked, and a text byte order mark agrees with any of them.
+fn has_type_mismatch(filepath: &Path) -> bool {
+	let extension_file_type = FileType::from_extension(&filepath.extension().unwrap_or_default().to_string_lossy());
+	if let FileType::Other(_) = extension_file_type {
+		return false;
+	}
+	let detected_file_type = magic_file_type(filepath).filter(|x| *x != FileType::Text);
+	let expected_file_type = if CFB_FILE_TYPES.contains(&extension_file_type) {
+		Some(FileType::Cfb)
+	} else if ZIP_FILE_TYPES.contains(&extension_file_type) {
+		//password protected OOXML is a cfb
+		if detected_file_type == Some(FileType::Cfb) {
+			return false;
+		}
+		Some(FileType::Zip)
+	} else if extension_file_type == FileType::Tar {
+		//tar archives older than POSIX have no magic
+		if detected_file_type.is_none() {
+			return false;
+		}
+		Some(FileType::Tar)
+	} else {
+		MAGIC_BYTES.iter().find(|x| x.file_type == extension_file_type && x.file_type != FileType::Text).map(|x| x.file_type.clone())
+	};
+	detected_file_type != expected_file_type
+}
+
+/// Whether a file looks like text delimited by one of `delimiters`, e.g. CSV: its first lines have the same number, at least one,
+/// of that delimiter.
+fn is_delimited_text(filepath: &Path, delimiters: &[char]) -> bool {
+	let mut header = Vec::new();
+	if let Err(e) = File::open(filepath).and_then(|file| file.take(DELIMITED_TEXT_SAMPLE_LEN as u64).read_to_end(&mut header)) {
+		error!("Error reading header bytes from file {:?}. {:?}", filepath, e);
+		return false;
+	}
+	//Excel's "Unicode Text" is UTF-16 with a BOM, anything else is read byte by byte, which keeps the delimiters of UTF-8 too
+	let (sample, _) = Encoding::for_bom(&header).unwrap_or((WINDOWS_1252, 0)).0.decode_with_bom_removal(&header);
+	if sample.contains('\0') {
+		return false;
+	}
+	let mut lines: Vec<&str> = sample.lines().filter(|x| !x.trim().is_empty()).collect();
+	//the sample may end part way through a line
+	if header.len() == DELIMITED_TEXT_SAMPLE_LEN && lines.len() > 1 {
+		lines.pop();
+	}
+	lines.truncate(DELIMITED_TEXT_SAMPLE_LINES);
+	!lines.is_empty() && delimiters.iter().any(|delimiter| {
+		let count = lines[0].matches(*delimiter).count();
+		count > 0 && lines.iter().all(|x| x.matches(*delimiter).count() == count)
+	})
+}
+
+/// Tells the type of a zip or cfb container whose name gives no extension, e.g. input read by extract_text_from_reader,
+/// from the main part or stream of the types it can hold. None if it is none of them.
+fn container_file_type(filepath: &Path, container_file_type: &FileType) -> Option<FileType> {
+	match container_file_type {
+		FileType::Zip => {
+			let archive = ZipArchive::new(File::open(filepath).ok()?).ok()?;
+			[
+				("word/document.xml", FileType::Docx),
+				("xl/workbook.xml", FileType::Xlsx),
+				("xl/workbook.bin", FileType::Xlsb),
+				("ppt/presentation.xml", FileType::Pptx),
+				("FixedDocumentSequence.fdseq", FileType::Xps),
+			].into_iter().find(|(part, _)| archive.index_for_name(part).is_some()).map(|(_, file_type)| file_type)
+		}
+		FileType::Cfb => {
+			let cfbf = cfb::open(filepath).ok()?;
+			[
+				("/__substg1.0_0037001F", FileType::Msg),
+				("/__substg1.0_0037001E", FileType::Msg),
+				("/WordDocument", FileType::Doc),
+				("/Workbook", FileType::Xls),
+				("/Book", FileType::Xls),
+				("/EncryptedPackage", FileType::Encrypted),
+			].into_iter().find(|(stream, _)| cfbf.is_stream(stream)).map(|(_, file_type)| file_type)
+		}
+		_ => None,
+	}
+}
 
-	return file_extension;
+fn is_encrypted_ooxml(filepath: &Path) -> bool {
+	match cfb::open(filepath) {
+		Ok(cfbf) => cfbf.is_stream("/EncryptedPackage"),
+		Err(_) => false,
+	}
 }
 
 fn read_file_with_encoding(filepath: &Path, encoding: &'static Encoding) -> Result<String, Box<dyn Error>> {
     let file = File::open(filepath)?;
+    Ok(decode_with_encoding(file, encoding)?)
+}
+
+/// Decodes everything a reader returns as `encoding`. The incremental decoder keeps the bytes of a character split between
+/// two reads until the next one, so the text is the same whatever size of chunks the reader returns.
+fn decode_with_encoding<R: Read>(reader: R, encoding: &'static Encoding) -> io::Result<String> {
 	let mut decoder = DecodeReaderBytesBuilder::new()
-        .encoding(Some(encoding)) // Specify the source encoding
-        .build(file);
-    let mut contents = String::new();
-    decoder.read_to_string(&mut contents)?;
+		.encoding(Some(encoding)) // Specify the source encoding
+		.build(reader);
+	let mut contents = String::new();
+	decoder.read_to_string(&mut contents)?;
+	Ok(contents)
+}
+
+/// Decodes the start of what a reader returns as `encoding`, at most `max_bytes` bytes of text (as UTF-8) cut back to a whole character.
+/// The reader is not read further than needed.
+///
+/// # Returns
+///
+/// * The text, and whether there was more
+fn decode_with_encoding_limit<R: Read>(reader: R, encoding: &'static Encoding, max_bytes: u64) -> io::Result<(String, bool)> {
+	let decoder = DecodeReaderBytesBuilder::new()
+		.encoding(Some(encoding))
+		.build(reader);
+	//one byte more tells if there is more
+	let mut bytes = Vec::new();
+	decoder.take(max_bytes.saturating_add(1)).read_to_end(&mut bytes)?;
+	let is_truncated = bytes.len() as u64 > max_bytes;
+	bytes.truncate(max_bytes as usize);
+	let text = match String::from_utf8(bytes) {
+		Ok(text) => text,
+		Err(e) => {
+			let valid_len = e.utf8_error().valid_up_to();
+			let mut bytes = e.into_bytes();
+			bytes.truncate(valid_len);
+			String::from_utf8(bytes).unwrap_or_default()
+		}
+	};
+	Ok((text, is_truncated))
+}
+
+/// Decodes what a reader returns as `encoding` a line at a time, keeping only the last `max_lines` lines,
+/// so a log of any size is read in memory for just those lines.
+///
+/// # Returns
+///
+/// * The text of the last lines, and whether there were more
+fn decode_tail_lines<R: Read>(reader: R, encoding: &'static Encoding, max_lines: usize) -> io::Result<(String, bool)> {
+	let mut decoder = BufReader::new(DecodeReaderBytesBuilder::new()
+		.encoding(Some(encoding))
+		.build(reader));
+	let mut lines = VecDeque::with_capacity(max_lines.min(1024));
+	let mut is_truncated = false;
+	loop {
+		let mut line = String::new();
+		if decoder.read_line(&mut line)? == 0 {
+			break;
+		}
+		if lines.len() == max_lines {
+			lines.pop_front();
+			is_truncated = true;
+		}
+		if max_lines > 0 {
+			lines.push_back(line);
+		} else {
+			is_truncated = true;
+		}
+	}
+	Ok((lines.into_iter().collect(), is_truncated))
+}
+
+/// Decodes what a reader returns as `encoding` a line at a time, cleaning each line as it is read, so a log is never held in
+/// memory both as read and as cleaned. Reading stops at `max_bytes` bytes of text (as UTF-8) if set, cut back to a whole character.
+///
+/// # Returns
+///
+/// * The cleaned text, the start of the text before cleaning as raw_text_prefix keeps it, and whether there was more
+fn decode_clean_lines<R: Read>(reader: R, encoding: &'static Encoding, max_bytes: Option<u64>, config: &ExtractConfig) -> io::Result<(String, Option<String>, bool)> {
+	let decoder = DecodeReaderBytesBuilder::new()
+		.encoding(Some(encoding))
+		.build(reader);
+	//one byte more tells if there is more
+	let mut decoder = BufReader::new(decoder.take(max_bytes.map_or(u64::MAX, |x| x.saturating_add(1))));
+	let mut text = String::new();
+	let mut raw_text = String::new();
+	let mut read_bytes = 0u64;
+	let mut is_truncated = false;
+	let mut line_bytes = Vec::new();
+	loop {
+		line_bytes.clear();
+		if decoder.read_until(b'\n', &mut line_bytes)? == 0 {
+			break;
+		}
+		if let Some(max_bytes) = max_bytes
+			&& read_bytes + line_bytes.len() as u64 > max_bytes
+		{
+			line_bytes.truncate((max_bytes - read_bytes) as usize);
+			is_truncated = true;
+		}
+		read_bytes += line_bytes.len() as u64;
+		//only a line cut short can end inside a character
+		let line = match std::str::from_utf8(&line_bytes) {
+			Ok(line) => line,
+			Err(e) => std::str::from_utf8(&line_bytes[..e.valid_up_to()]).unwrap_or_default(),
+		};
+		if config.raw_text_max_bytes.is_some_and(|x| raw_text.len() < x) {
+			raw_text.push_str(line);
+		}
+		text.push_str(&clean_text(line.to_string(), config));
+		if is_truncated {
+			break;
+		}
+	}
+	Ok((text, raw_text_prefix(&raw_text, config), is_truncated))
+}
 
-    Ok(contents)
+/// Whether a file is a log, by its name: `.log`, or a log rotated to `.log.1`, `.log.2`...
+fn is_log_file(filepath: &Path) -> bool {
+	let Some(extension) = filepath.extension().map(|x| x.to_string_lossy().to_lowercase()) else {
+		return false;
+	};
+	if extension == "log" {
+		return true;
+	}
+	!extension.is_empty()
+		&& extension.bytes().all(|x| x.is_ascii_digit())
+		&& Path::new(filepath.file_stem().unwrap_or_default()).extension().is_some_and(|x| x.eq_ignore_ascii_case("log"))
 }
 
 /// Detects the encoding of a file based on its header bytes and content.
@@ -237,32 +531,30 @@ fn detect_encoding(filepath: &Path, assume_utf8: bool) -> &'static Encoding {
 // }
 
 fn msg_get_contents(cfbf: &mut CompoundFile<File>, path: PathBuf) -> Result<(String, String, Vec<PathBuf>), Box<dyn Error>> {
-	let mut subject = String::new();
 	let mut body = String::new();
 	let mut sub_paths: Vec<PathBuf> = Vec::new();
 
-	//subject 0x0037 Subject, 0x001F UTF_16LE
-	if let Ok(mut stream) = cfbf.open_stream(path.join("__substg1.0_0037001F")) {
-		let mut data = Vec::new();
-		if let Ok(_) = stream.read_to_end(&mut data) {
-			let data = UTF_16LE.decode(&data);
-			// println!("{:?}", data);
-			subject = data.0.to_string();
-		}
-	} else {
-		return Err(format!("Subject stream not found in {:?}", path).into())
-	}
+	//subject 0x0037 Subject, 0x001F UTF_16LE or 0x001E in an ANSI msg
+	let subject = msg_string_stream(cfbf, &path, 0x0037).ok_or_else(|| format!("Subject stream not found in {:?}", path))?;
 
-	//body 0x1000 Body, 0x001F UTF_16LE
-	if let Ok(mut stream) = cfbf.open_stream(path.join("__substg1.0_1000001F")) {
-		let mut data = Vec::new();
-		if let Ok(_) = stream.read_to_end(&mut data) {
-			let data = UTF_16LE.decode(&data);
-			// println!("{:?}", data);
-			body = data.0.to_string();
+	//body 0x1000 Body, 0x001F UTF_16LE or 0x001E in an ANSI msg
+	let body_stream = msg_string_stream(cfbf, &path, 0x1000);
+	let has_body = body_stream.is_some();
+	if let Some(data) = body_stream {
+		body = data;
+	}
+	//messages saved without a plain text body only have the RTF one, 0x1009 RtfCompressed, 0x0102 PT_BINARY
+	if body.trim_end_matches('\0').trim().is_empty() {
+		if let Ok(mut stream) = cfbf.open_stream(path.join("__substg1.0_10090102")) {
+			let mut data = Vec::new();
+			stream.read_to_end(&mut data)?;
+			match rtf::decompress(&data) {
+				Ok(rtf_data) => body = rtf::to_text(&rtf_data),
+				Err(e) => warn!("Error decompressing RTF body in {:?}: {}", path, e),
+			}
+		} else if !has_body {
+			return Err(format!("Body stream not found in {:?}", path).into())
 		}
-	} else {
-		return Err(format!("Body stream not found in {:?}", path).into())
 	}
 
 	//attachments
@@ -281,1146 +573,7949 @@ fn msg_get_contents(cfbf: &mut CompoundFile<File>, path: PathBuf) -> Result<(Str
 	return Ok((subject, body, sub_paths))
 }
 
-/// Produces a list of files held within files (if any), recursive, and extracts individual files within archives to a temp folder.
-/// 
-/// # Arguments
-/// 
-/// * `filepath` - A path to the top-level file to search for subfiles within
-/// 
-/// # Returns
-/// 
-/// * A heirarchal list of filepaths of any extracted files, includes the top-level file
-fn extract_archive(filepath: &Path, depth:u8, parent_files: Vec<String>, list_of_files_in_archive: &mut Vec<SubFileItem>) -> Result<(), Box<dyn Error>> {
-
+/// A named property of a msg file, by the name or the numeric id it has in its property set.
+enum MsgPropertyName {
+	Name(&'static str),
+	Id(u32),
+}
 
-	debug!("filepath: {:?}", filepath);
-	if filepath.metadata()?.len() == 0 {
-		list_of_files_in_archive.push(SubFileItem {
-			filepath: filepath.to_path_buf(),
-			depth,
-			parent_files: parent_files.clone(),
-			ok_to_extract_text: true,
-		});
-		return Ok(())
-	}
+/// PS_PUBLIC_STRINGS {00020329-0000-0000-C000-000000000046}, as stored in the `__nameid_version1.0` GUID stream
+const PS_PUBLIC_STRINGS: [u8; 16] = [0x29, 0x03, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46];
+/// PSETID_Common {00062008-0000-0000-C000-000000000046}
+const PSETID_COMMON: [u8; 16] = [0x08, 0x20, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46];
+/// Named properties of msg files listed with ExtractConfig::msg_headers, with their label, see MS-OXPROPS.
+const MSG_NAMED_PROPERTIES: [(&str, [u8; 16], MsgPropertyName); 3] = [
+	("Categories", PS_PUBLIC_STRINGS, MsgPropertyName::Name("Keywords")),
+	("Companies", PSETID_COMMON, MsgPropertyName::Id(0x8539)),
+	("Billing information", PSETID_COMMON, MsgPropertyName::Id(0x8535)),
+];
 
-	let achive_uuid_subdir: &str = &Uuid::new_v4().simple().to_string();
+/// Reads a whole stream of a msg file, None if there is no such stream.
+fn msg_read_stream(cfbf: &mut CompoundFile<File>, path: &Path) -> Option<Vec<u8>> {
+	let mut stream = cfbf.open_stream(path).ok()?;
+	let mut data = Vec::new();
+	stream.read_to_end(&mut data).ok()?;
+	Some(data)
+}
 
-	//switch filepath extension
-	let effective_file_extension = get_effective_file_extension(filepath);
-	debug!("extract_archive: effective_file_extension: {:?}", effective_file_extension);
+/// Reads the stream of a string property of a msg file, `0x001F` UTF-16LE as Unicode msg files store it, or `0x001E` 8 bit as
+/// ANSI ones do, including the messages embedded in them. A trailing null is dropped. None if there is no stream for the property.
+fn msg_string_stream(cfbf: &mut CompoundFile<File>, path: &Path, property_id: u32) -> Option<String> {
+	let value = if let Some(data) = msg_read_stream(cfbf, &path.join(format!("__substg1.0_{:04X}001F", property_id))) {
+		UTF_16LE.decode(&data).0.to_string()
+	} else {
+		let data = msg_read_stream(cfbf, &path.join(format!("__substg1.0_{:04X}001E", property_id)))?;
+		WINDOWS_1252.decode(&data).0.to_string()
+	};
+	Some(value.trim_end_matches('\0').to_string())
+}
 
-	
-	match effective_file_extension.as_str() {
-		"7z" => {
-			list_of_files_in_archive.push(SubFileItem {
-				filepath: filepath.to_path_buf(),
-				depth,
-				parent_files: parent_files.clone(),
-				ok_to_extract_text: false,
-			});
+/// Reads a string property of a msg file, see msg_string_stream, or the values of a `0x101F` multiple string property
+/// joined with `; `. None if the property is not set.
+fn msg_string_property(cfbf: &mut CompoundFile<File>, path: &Path, property_id: u32) -> Option<String> {
+	let value = if let Some(value) = msg_string_stream(cfbf, path, property_id) {
+		value
+	} else {
+		//each value of a multiple valued property is a stream of its own, numbered from 0
+		let mut values = Vec::new();
+		while let Some(data) = msg_read_stream(cfbf, &path.join(format!("__substg1.0_{:04X}101F-{:08X}", property_id, values.len()))) {
+			values.push(UTF_16LE.decode(&data).0.trim_end_matches('\0').to_string());
+		}
+		values.join("; ")
+	};
+	let value = value.trim_end_matches('\0').trim().to_string();
+	(!value.is_empty()).then_some(value)
+}
 
-			let outpath = tempfiles_location().join(&achive_uuid_subdir);
-			// ignore returns and errors, if bad archive just skip
-			match decompress_file_with_password(filepath, &outpath, "a4".into()) {
-				Ok(()) => {
-					debug!("Extracted 7z to: {:?}", outpath);
-
-					// Walk through all files and directories recursively
-					for entry in WalkDir::new(outpath)
-						.into_iter()
-						.filter_map(|e| e.ok()) // Skip errors
-					{
-						let path = entry.path();
-						if path.is_file() {
-							let mut new_parent_files = parent_files.clone();
-							new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-							// new_parent_files passes ownership instead of reference, because we no longer need it after passing into this function
-							extract_archive(path, depth+1, new_parent_files, list_of_files_in_archive)?;
-						}
-					}
-				}
-				Err(err) => {
-					match err {
-						sevenz_rust::Error::MaybeBadPassword(msg) => {
-							warn!("sevenz_rust::Error::MaybeBadPassword: {}", msg);
-						}
-						_ => return Err(Box::new(err))
-					}
-				}
+/// Finds the property id a named property is mapped to in a msg file, from the `__nameid_version1.0` storage, see MS-OXMSG 2.2.3.
+/// None if the msg does not use the property.
+fn msg_named_property_id(cfbf: &mut CompoundFile<File>, guid: &[u8; 16], name: &MsgPropertyName) -> Option<u32> {
+	let nameid = Path::new("/__nameid_version1.0");
+	let guids = msg_read_stream(cfbf, &nameid.join("__substg1.0_00020102")).unwrap_or_default();
+	let entries = msg_read_stream(cfbf, &nameid.join("__substg1.0_00030102"))?;
+	let strings = msg_read_stream(cfbf, &nameid.join("__substg1.0_00040102")).unwrap_or_default();
+	for entry in entries.chunks_exact(8) {
+		let name_or_offset = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+		let index_and_kind = u32::from_le_bytes(entry[4..8].try_into().unwrap());
+		let is_string_name = index_and_kind & 1 == 1;
+		//1 is PS_MAPI, 2 PS_PUBLIC_STRINGS, from 3 on an index into the GUID stream
+		let entry_guid = match (index_and_kind >> 1) & 0x7FFF {
+			2 => Some(PS_PUBLIC_STRINGS),
+			guid_index @ 3.. => guids.get((guid_index as usize - 3) * 16..(guid_index as usize - 2) * 16).and_then(|x| x.try_into().ok()),
+			_ => None,
+		};
+		if entry_guid.as_ref() != Some(guid) {
+			continue;
+		}
+		let is_match = match name {
+			MsgPropertyName::Id(id) => !is_string_name && name_or_offset == *id,
+			MsgPropertyName::Name(name) => {
+				let offset = name_or_offset as usize;
+				is_string_name && strings.get(offset..offset + 4)
+					.map(|x| u32::from_le_bytes(x.try_into().unwrap()) as usize)
+					.and_then(|len| strings.get(offset + 4..offset + 4 + len))
+					.is_some_and(|x| UTF_16LE.decode(x).0 == *name)
 			}
+		};
+		if is_match {
+			return Some(0x8000 + (index_and_kind >> 16));
 		}
-		"docx" | "docm" => {
-			list_of_files_in_archive.push(SubFileItem {
-				filepath: filepath.to_path_buf(),
-				depth,
-				parent_files: parent_files.clone(),
-				ok_to_extract_text: true,
-			});
+	}
+	None
+}
 
-			let file = File::open(filepath)?;
-			let mut archive = zip::ZipArchive::new(file)?;
+/// Lists the conversation topic, the named properties of MSG_NAMED_PROPERTIES and the internet headers of a msg file as a `[Headers]`
+/// subfile, a `Label: value` line per property that is set then `Internet headers:` and the headers as they were received.
+fn push_msg_headers_subfile(cfbf: &mut CompoundFile<File>, filepath: &Path, outdir: &Path, depth: u8, parent_files: &[String], list_of_files_in_archive: &mut Vec<SubFileItem>) {
+	let root = Path::new("/");
+	let mut text = String::new();
+	//0x0070 ConversationTopic
+	if let Some(topic) = msg_string_property(cfbf, root, 0x0070) {
+		text.push_str(&format!("Conversation topic: {}\n", topic));
+	}
+	for (label, guid, name) in MSG_NAMED_PROPERTIES {
+		if let Some(property_id) = msg_named_property_id(cfbf, &guid, &name)
+			&& let Some(value) = msg_string_property(cfbf, root, property_id)
+		{
+			text.push_str(&format!("{}: {}\n", label, value));
+		}
+	}
+	//0x007D TransportMessageHeaders
+	if let Some(headers) = msg_string_property(cfbf, root, 0x007D) {
+		text.push_str("Internet headers:\n");
+		text.push_str(&headers);
+		text.push('\n');
+	}
+	if text.is_empty() {
+		return;
+	}
+	let mut new_parent_files = parent_files.to_vec();
+	new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+	push_text_subfile(outdir.join("[Headers]"), format!("Headers:\n{}", text), "msg", depth+1, new_parent_files, list_of_files_in_archive);
+}
 
-			for i in 0..archive.len() {
-				let mut file = archive.by_index(i)?;
-				let zipoutpath = match file.enclosed_name() {
-					Some(path) => path.to_owned(),
-					None => continue,
-				};
+/// Writes the PNG and JPEG pictures of the RTF body of a msg file, e.g. pasted screenshots, to `outdir` as `image1.png`, `image2.jpg`...
+/// to be read by OCR as subfiles of the msg. None are written without ExtractConfig::ocr_embedded_images.
+fn extract_msg_rtf_pictures(cfbf: &mut CompoundFile<File>, filepath: &Path, outdir: &Path, config: &ExtractConfig) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+	if !config.ocr_embedded_images {
+		debug!("Not extracting the RTF body pictures of {:?}", filepath);
+		return Ok(Vec::new());
+	}
+	//0x1009 RtfCompressed, 0x0102 PT_BINARY
+	let Some(data) = msg_read_stream(cfbf, Path::new("/__substg1.0_10090102")) else {
+		return Ok(Vec::new());
+	};
+	let rtf_data = match rtf::decompress(&data) {
+		Ok(rtf_data) => rtf_data,
+		Err(e) => {
+			warn!("Error decompressing RTF body in {:?}: {}", filepath, e);
+			return Ok(Vec::new());
+		}
+	};
+	let mut outpaths = Vec::new();
+	for (ipicture, (extension, picture)) in rtf::read_pictures(&rtf_data).into_iter().enumerate() {
+		let outpath = outdir.join(format!("image{}.{}", ipicture+1, extension));
+		fs::create_dir_all(outdir)?;
+		fs::write(&outpath, picture)?;
+		outpaths.push(outpath);
+	}
+	Ok(outpaths)
+}
 
-				// Check if the file is in the 'word/media/' folder and has a typical image extension
-				if zipoutpath.starts_with("word/media/") && 
-				zipoutpath.extension().map_or(false, |ext| 
-					ext == "png" || ext == "jpeg" || ext == "jpg") {
+/// Content-Transfer-Encoding values that mail_parser decodes, or that need no decoding
+const KNOWN_TRANSFER_ENCODINGS: [&str; 5] = ["7bit", "8bit", "binary", "quoted-printable", "base64"];
 
-					let outpath = tempfiles_location().join(&achive_uuid_subdir).join(zipoutpath.file_name().unwrap());
-					fs::create_dir_all(outpath.parent().unwrap())?;
-					
-					let mut outfile = File::create(&outpath)?;
-					match io::copy(&mut file, &mut outfile) {
-						Ok(_) => {
-							let mut new_parent_files = parent_files.clone();
-							new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-							extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-						},
-						Err(e) => {
-							error!("Error writing word image to file {:?}: {}", outpath, e)
-						},
-					}
-				}
-			}
+/// Decodes an email body that is still quoted-printable or base64 encoded.
+/// mail_parser decodes transfer encodings itself, but leaves the body raw when the Content-Transfer-Encoding header is malformed or missing,
+/// so only call this for such a part.
+///
+/// # Returns
+///
+/// * The decoded text, or None if the body does not look transfer encoded or does not decode to UTF-8 text
+fn decode_leftover_transfer_encoding(body: &str) -> Option<String> {
+	let decoded_to_text = |decoded: Vec<u8>| -> Option<String> {
+		let text = String::from_utf8(decoded).ok()?;
+		//binary output means this was not an encoded text body after all
+		if text.chars().any(|c| c.is_control() && !c.is_whitespace()) {
+			return None;
 		}
-		"eml" => {
-			list_of_files_in_archive.push(SubFileItem {
-				filepath: filepath.to_path_buf(),
-				depth,
-				parent_files: parent_files.clone(),
-				ok_to_extract_text: false,
-			});
-			
-			let mut file = File::open(filepath)?;
-			let mut raw_email_data = Vec::new();
-			file.read_to_end(&mut raw_email_data)?;
+		Some(text)
+	};
 
-			let mut bodytext:String = String::new();
-			if let Some(message) = MessageParser::default().parse(&raw_email_data) {
-				if let Some(subject) = message.subject() {
-					bodytext.push_str(subject);
-				}
-				if let Some(body) = message.body_text(0) {
-					bodytext.push_str(&body);
-				}
-				let outpath = tempfiles_location().join(&achive_uuid_subdir).join("body.txt");
-				fs::create_dir_all(outpath.parent().unwrap())?;
-				match fs::write(&outpath, bodytext) {
-					Ok(_) => {
-						let mut new_parent_files = parent_files.clone();
-						new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-						extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-					},
-					Err(e) => {
-						error!("Error writing to file {:?}: {}", outpath, e)
-					},
-				}
-				
-				for attachment in message.attachments() {
-					let temp_filename = &Uuid::new_v4().simple().to_string();
-					let attachment_name = attachment.attachment_name().unwrap_or(temp_filename);
-					//println!("Attachment found: {}", attachment_name);
-					let outpath = tempfiles_location().join(&achive_uuid_subdir).join(attachment_name);
-					match fs::write(&outpath, attachment.contents()) {
-						Ok(_) => {
-							let mut new_parent_files = parent_files.clone();
-							new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-							extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-						},
-						Err(e) => {
-							error!("Error writing to file {:?}: {}", outpath, e)
-						},
-					}
+	//base64, lines of base64 characters without spaces, all as long as the first but the last
+	let lines: Vec<&str> = body.trim_end().lines().map(|line| line.trim_end_matches('\r')).collect();
+	let line_len = lines.first().map(|line| line.len()).unwrap_or_default();
+	let is_base64_layout = lines.iter().enumerate().all(|(i, line)| {
+		!line.contains([' ', '\t']) && if i + 1 < lines.len() { line.len() == line_len } else { line.len() <= line_len }
+	});
+	let compact: String = lines.concat();
+	if is_base64_layout
+		&& compact.len() >= 16
+		&& compact.len().is_multiple_of(4)
+		&& compact.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/' || b == b'=')
+		&& let Some(text) = base64_decode(compact.as_bytes()).and_then(decoded_to_text)
+	{
+		return Some(text);
+	}
+
+	//quoted-printable, soft line breaks or =XX escapes
+	let bytes = body.as_bytes();
+	let escape_count = bytes.windows(3)
+		.filter(|w| w[0] == b'=' && w[1].is_ascii_hexdigit() && w[2].is_ascii_hexdigit() && !w[1].is_ascii_lowercase() && !w[2].is_ascii_lowercase())
+		.count();
+	if (escape_count >= 2 || body.contains("=\r\n") || body.contains("=\n"))
+		&& let Some(text) = quoted_printable_decode(bytes).and_then(decoded_to_text)
+	{
+		return Some(text);
+	}
+
+	None
+}
+
+/// Lists text decoded in memory, such as an email body, as a subfile without writing it to a temp file.
+/// `filepath` only names the subfile, `method` says how the text was obtained.
+fn push_text_subfile(filepath: PathBuf, text: String, method: &str, depth: u8, parent_files: Vec<String>, list_of_files_in_archive: &mut Vec<SubFileItem>) {
+	list_of_files_in_archive.push(SubFileItem {
+		filepath,
+		depth,
+		parent_files,
+		ok_to_extract_text: true,
+		text: Some(text),
+		method: Some(method.to_string()),
+		..Default::default()
+	});
+}
+
+/// Lists a text file written by a built-in extraction step, such as a PDF page from pdftotext, recording `method` as how its text was obtained.
+fn extract_generated_file(filepath: &Path, method: &str, depth: u8, parent_files: Vec<String>, list_of_files_in_archive: &mut Vec<SubFileItem>, context: &UnpackContext, config: &ExtractConfig) -> Result<(), Box<dyn Error>> {
+	let index = list_of_files_in_archive.len();
+	extract_archive(filepath, depth, parent_files, list_of_files_in_archive, context, config)?;
+	if let Some(item) = list_of_files_in_archive.get_mut(index) {
+		item.method.get_or_insert_with(|| method.to_string());
+	}
+	Ok(())
+}
 
+/// Lists the document properties of an OOXML file as a `[Properties]` subfile, a `Label: value` line per property that is set.
+fn push_properties_subfile(filepath: &Path, outdir: &Path, depth: u8, parent_files: &[String], list_of_files_in_archive: &mut Vec<SubFileItem>) {
+	match docprops::read_properties(filepath) {
+		Ok(properties) => {
+			if !properties.is_empty() {
+				let mut text = String::from("Properties:\n");
+				for (label, value) in properties {
+					text.push_str(&format!("{}: {}\n", label, value));
 				}
+				let mut new_parent_files = parent_files.to_vec();
+				new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+				push_text_subfile(outdir.join("[Properties]"), text, "docprops xml", depth+1, new_parent_files, list_of_files_in_archive);
 			}
 		}
-		"msg" => {
-			list_of_files_in_archive.push(SubFileItem {
-				filepath: filepath.to_path_buf(),
-				depth,
-				parent_files: parent_files.clone(),
-				ok_to_extract_text: false,
-			});
+		Err(e) => {
+			warn!("Error reading document properties from {:?}: {}", filepath, e);
+		}
+	}
+}
 
-			let mut cfbf = cfb::open(filepath)?;
+/// An archive split into numbered parts, e.g. `backup.zip.001`, `backup.zip.002`, ... as written by 7-Zip or `split`,
+/// which are put back together by concatenating them.
+struct SplitArchive {
+	/// Name of the whole archive, e.g. `backup.zip`
+	name: String,
+	/// Number of this part, from 1
+	part: u32,
+	/// The parts found next to this one, by number
+	parts: Vec<(u32, PathBuf)>,
+}
 
-			let (subject, body, sub_paths) = msg_get_contents(&mut cfbf, PathBuf::from("/"))?;
-			// debug!("{:?}", subject);
-			// debug!("{:?}", body);
-			// debug!("{:?}", sub_paths);
+impl SplitArchive {
+	/// Every part from 1 to the highest found is there. A missing last part is only noticed when the archive is opened.
+	fn is_complete(&self) -> bool {
+		self.parts.iter().enumerate().all(|(i, (number, _))| *number == i as u32 + 1)
+	}
+}
 
-			let outpath = tempfiles_location().join(&achive_uuid_subdir).join("body.txt");
-			fs::create_dir_all(outpath.parent().unwrap())?;
-			let outtext = subject + "\n\n" + &body;
-			match fs::write(&outpath, outtext) {
-				Ok(_) => {
-					let mut new_parent_files = parent_files.clone();
-					new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-					extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-				},
-				Err(e) => {
-					error!("Error writing to file {:?}: {}", outpath, e)
-				},
-			}
+/// The number of a split archive part from its extension, at least 3 digits, e.g. `001`.
+fn split_part_number(extension: &str) -> Option<u32> {
+	if extension.len() < 3 || !extension.bytes().all(|x| x.is_ascii_digit()) {
+		return None;
+	}
+	extension.parse().ok().filter(|x| *x > 0)
+}
 
-			//stores the file subpath to write the output to and a list of cfbf subpaths
-			let mut msg_attachments_to_traverse: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
-			if !sub_paths.is_empty() {
-				msg_attachments_to_traverse.push((PathBuf::new(), sub_paths.clone()));
-			}
+/// Detects a part of a split archive by its name, an archive extension then a part number, and finds the other parts in its directory.
+fn split_archive(filepath: &Path) -> Option<SplitArchive> {
+	let file_name = filepath.file_name()?.to_string_lossy().to_string();
+	let (name, extension) = file_name.rsplit_once('.')?;
+	let part = split_part_number(extension)?;
+	let archive_type = FileType::from_extension(&Path::new(name).extension()?.to_string_lossy());
+	if !ARCHIVE_FILE_TYPES.contains(&archive_type) {
+		return None;
+	}
+	let dir = filepath.parent().filter(|x| !x.as_os_str().is_empty()).unwrap_or(Path::new("."));
+	let mut parts: Vec<(u32, PathBuf)> = fs::read_dir(dir).ok()?
+		.filter_map(|entry| entry.ok())
+		.filter_map(|entry| {
+			let entry_name = entry.file_name().to_string_lossy().to_string();
+			let number = split_part_number(entry_name.strip_prefix(name)?.strip_prefix('.')?)?;
+			Some((number, entry.path()))
+		})
+		.collect();
+	parts.sort();
+	Some(SplitArchive {
+		name: name.to_string(),
+		part,
+		parts,
+	})
+}
 
-			while !msg_attachments_to_traverse.is_empty() {
-				if let Some((filesubpath, sub_paths)) = msg_attachments_to_traverse.pop() {
-					let achive_uuid_msg_subdir: &str = &Uuid::new_v4().simple().to_string();
-					debug!("sub_paths: {:?}", sub_paths);
-					for sub_path in sub_paths {
-						debug!("depth: {}, path: {:?}", sub_path.components().count()-1, sub_path);
-						// attachment binary, 0x3701 AttachDataObject, 0x0102 PT_BINARY
-						if cfbf.exists(sub_path.join("__substg1.0_37010102")) {
-							// println!("Binary attachment");
-							//attachment filename, 0x3707 AttachLongFilename, 0x001F UTF_16LE
-							let filename: String;
-							if let Ok(mut stream) = cfbf.open_stream(sub_path.join("__substg1.0_3707001F")) {
-								let mut data = Vec::new();
-								stream.read_to_end(&mut data)?;
-								let data = UTF_16LE.decode(&data);
-								filename = data.0.to_string();
-							} else {
-								return Err(format!("Body stream not found in {:?}", filepath).into())
-							}
-							//download binary attachment
-							let mut stream = cfbf.open_stream(sub_path.join("__substg1.0_37010102"))?;
-							let mut data = Vec::new();
-							stream.read_to_end(&mut data)?;
-							let outpath = tempfiles_location().join(&achive_uuid_subdir).join(achive_uuid_msg_subdir).join(sub_path.components().last().unwrap()).join(filename);
-							fs::create_dir_all(outpath.parent().unwrap())?;
-							match fs::write(&outpath, data) {
-								Ok(_) => {
-									let mut new_parent_files = parent_files.clone();
-									new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-									let parent_files_subpaths: Vec<String> = filesubpath.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
-									new_parent_files.extend(parent_files_subpaths);
-									extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-								},
-								Err(e) => {
-									error!("Error writing to file {:?}: {}", outpath, e)
-								},
-							}
+/// A part of a zip spanned by PKZIP or `zip -s`, `backup.z01`, `backup.z02`, ... then `backup.zip`.
+/// Its offsets are relative to each part, so the parts do not make a zip when concatenated.
+fn is_spanned_zip_part(filepath: &Path) -> bool {
+	let Some(extension) = filepath.extension().map(|x| x.to_string_lossy().to_lowercase()) else {
+		return false;
+	};
+	match extension.strip_prefix('z') {
+		Some("ip") => filepath.with_extension("z01").exists(),
+		Some(number) => number.len() >= 2 && number.bytes().all(|x| x.is_ascii_digit()),
+		None => false,
+	}
+}
 
-						}
-						//attachment msg path, 0x3701 AttachDataObject, 0x0102 PT_BINARY, 0x000D PT_OBJECT
-						else if cfbf.exists(sub_path.join("__substg1.0_3701000D")) {
-							// println!("MSG attachment");
-							//attachment displayname, 0x3001 DisplayName, 0x001F UTF_16LE
-							let mut displayname: String;
-							if let Ok(mut stream) = cfbf.open_stream(sub_path.join("__substg1.0_3001001F")) {
-								let mut data = Vec::new();
-								stream.read_to_end(&mut data)?;
-								let data = UTF_16LE.decode(&data);
-								displayname = data.0.to_string();
-							} else {
-								return Err(format!("Body stream not found in {:?}", filepath).into())
-							}
-							displayname.retain(|c| !FILENAME_ILLEGAL_CHARS.contains(&c));
-							//empty file placeholder as embedded msg
-							let msg_placeholder_filename = displayname.clone() + ".msg";
-							let outpath = tempfiles_location().join(&achive_uuid_subdir).join(achive_uuid_msg_subdir).join(sub_path.components().last().unwrap()).join(&msg_placeholder_filename);
-							fs::create_dir_all(outpath.parent().unwrap())?;
-							match fs::write(&outpath, "") {
-								Ok(_) => {
-									let mut new_parent_files = parent_files.clone();
-									new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-									let parent_files_subpaths: Vec<String> = filesubpath.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
-									new_parent_files.extend(parent_files_subpaths);
-									list_of_files_in_archive.push(SubFileItem {
-										filepath: outpath,
-										depth,
-										parent_files: new_parent_files.clone(),
-										ok_to_extract_text: false,
-									});
-								},
-								Err(e) => {
-									error!("Error writing to file {:?}: {}", outpath, e)
-								},
-							}
-							let filesubpath2 = filesubpath.clone().join(&msg_placeholder_filename);
-							//recurse into path
-							let (subject, body, sub_paths2) = msg_get_contents(&mut cfbf, sub_path.join("__substg1.0_3701000D"))?;
-							// println!("{:?}", sub_path.components().last().unwrap());
-							// println!("{:?}", subject);
-							// println!("{:?}", body);
-							let outpath = tempfiles_location().join(&achive_uuid_subdir).join(achive_uuid_msg_subdir).join(sub_path.components().last().unwrap()).join("body.txt");
-							fs::create_dir_all(outpath.parent().unwrap())?;
-							let outtext = subject + "\n\n" + &body;
-							match fs::write(&outpath, outtext) {
-								Ok(_) => {
-									let mut new_parent_files = parent_files.clone();
-									new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-									let parent_files_subpaths: Vec<String> = filesubpath2.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
-									new_parent_files.extend(parent_files_subpaths);
-									extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-								},
-								Err(e) => {
-									error!("Error writing to file {:?}: {}", outpath, e)
-								},
-							}
-							if !sub_paths2.is_empty() {
-								msg_attachments_to_traverse.push((filesubpath2.clone(), sub_paths2.clone()));
-							}
-						}
-						else {
-							return Err(format!("Unknown attachment type. Path: {:?}, file: {:?}", sub_path, filepath).into())
-						}
-					}
-				}
-			}
+/// The name of a zip entry. Names without the UTF-8 flag are decoded as CP437 by the zip crate, the zip default,
+/// but many tools write UTF-8 names without setting the flag, so a name that is valid UTF-8 is read as UTF-8.
+fn zip_entry_name<R: Read>(zipfile: &zip::read::ZipFile<'_, R>) -> String {
+	let name_raw = zipfile.name_raw();
+	if !name_raw.is_ascii()
+		&& let Ok(name) = std::str::from_utf8(name_raw)
+	{
+		return name.to_string();
+	}
+	zipfile.name().to_string()
+}
+
+/// The relative path a zip entry is written to, its name split into directories on either slash,
+/// without the parts that could leave the output directory: a root, a drive, `.` and `..`.
+fn zip_entry_path(name: &str) -> PathBuf {
+	name.split(['/', '\\'])
+		.filter(|component| !component.is_empty() && *component != "." && *component != ".." && !component.ends_with(':'))
+		.collect()
+}
+
+/// Writes one zip entry to `outdir` and lists it (recursively). Directories are only created.
+#[allow(clippy::too_many_arguments)]
+fn extract_zip_entry<R: Read>(zipfile: &mut zip::read::ZipFile<'_, R>, filepath: &Path, outdir: &Path, depth:u8, parent_files: &[String], list_of_files_in_archive: &mut Vec<SubFileItem>, context: &UnpackContext, config: &ExtractConfig) -> Result<(), Box<dyn Error>> {
+	// debug!("  {}: {} ({} bytes)", i, zipfile.name(), zipfile.size());
+	let entry_path = zip_entry_path(&zip_entry_name(zipfile));
+	if entry_path.as_os_str().is_empty() {
+		warn!("Skipping zip entry {:?} of {:?}, its name is not a file path", zipfile.name(), filepath);
+		return Ok(());
+	}
+	let outpath = outdir.join(&entry_path);
+	if zipfile.is_dir() {
+		fs::create_dir_all(&outpath)?;
+		// debug!("Created directory: {:?}", outpath);
+		return Ok(());
+	}
+
+	// Handle files
+	if let Some(parent) = outpath.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	// Extract the file
+	if !outpath.exists() { // if file already exists, as it duplicate filenames can appear in some archives (e.g. if archive created in linux with different case, and Windows does not care about case), just skip it.
+		let mut outfile = File::create(&outpath)?;
+		if let Err(e) = io::copy(zipfile, &mut outfile) {
+			//don't leave a partial file behind
+			drop(outfile);
+			_ = fs::remove_file(&outpath);
+			return Err(Box::new(e));
 		}
-		"odt" => {
-			list_of_files_in_archive.push(SubFileItem {
-				filepath: filepath.to_path_buf(),
-				depth,
-				parent_files: parent_files.clone(),
-				ok_to_extract_text: true,
-			});
+		debug!("Extracted: {:?}", outpath);
+		let entry_item_index = list_of_files_in_archive.len();
+		// new_parent_files passes ownership instead of reference, because we no longer need it after passing into this function
+		extract_archive(outpath.as_path(), depth+1, zip_entry_parent_files(filepath, &entry_path, parent_files, config), list_of_files_in_archive, context, config)?;
+		set_entry_modified(list_of_files_in_archive, entry_item_index, zip_entry_modified(zipfile));
+		//filepath.file_name().unwrap_or_default().to_string_lossy().to_string()
+	}
+	Ok(())
+}
 
-			let file = File::open(filepath)?;
-			let mut archive = zip::ZipArchive::new(file)?;
+/// When a zip entry was last modified, in seconds since the Unix epoch. The extended timestamp extra field is in UTC,
+/// the DOS date and time every entry has is local time of an unknown zone, so it is only used without one, taken as UTC.
+fn zip_entry_modified<R: Read>(zipfile: &zip::read::ZipFile<'_, R>) -> Option<i64> {
+	zipfile.extra_data_fields()
+		.find_map(|x| match x {
+			zip::ExtraField::ExtendedTimestamp(timestamp) => timestamp.mod_time(),
+			_ => None,
+		})
+		.map(i64::from)
+		.or_else(|| zipfile.last_modified()
+			.and_then(|x| time::OffsetDateTime::try_from(x).ok())
+			.map(|x| x.unix_timestamp()))
+}
 
-			for i in 0..archive.len() {
-				let mut file = archive.by_index(i)?;
-				let zipoutpath = match file.enclosed_name() {
-					Some(path) => path.to_owned(),
-					None => continue,
-				};
+/// Records the modification time of an archive entry on the items listed from it, from `entry_item_index` on.
+/// Items that already have one, the entries of a nested archive, keep their own.
+fn set_entry_modified(list_of_files_in_archive: &mut [SubFileItem], entry_item_index: usize, modified: Option<i64>) {
+	if modified.is_none() {
+		return;
+	}
+	for item in list_of_files_in_archive.iter_mut().skip(entry_item_index) {
+		item.modified = item.modified.or(modified);
+	}
+}
 
-				// Check if the file is in the 'word/media/' folder and has a typical image extension
-				if zipoutpath.starts_with("Pictures/") && 
-				zipoutpath.extension().map_or(false, |ext| 
-					ext == "png" || ext == "jpeg" || ext == "jpg") {
+/// The parent_files of a zip entry: the zip's own lineage and name, then the entry's directories with archive_paths_in_lineage.
+fn zip_entry_parent_files(filepath: &Path, entry_path: &Path, parent_files: &[String], config: &ExtractConfig) -> Vec<String> {
+	let mut new_parent_files = parent_files.to_vec();
+	new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+	if config.archive_paths_in_lineage
+		&& let Some(internal_dir) = entry_path.parent()
+	{
+		new_parent_files.extend(internal_dir.components().map(|c| c.as_os_str().to_string_lossy().into_owned()));
+	}
+	new_parent_files
+}
 
-					let outpath = tempfiles_location().join(&achive_uuid_subdir).join(zipoutpath.file_name().unwrap());
-					fs::create_dir_all(outpath.parent().unwrap())?;
-					
-					let mut outfile = File::create(&outpath)?;
-					match io::copy(&mut file, &mut outfile) {
-						Ok(_) => {
-							let mut new_parent_files = parent_files.clone();
-							new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-							extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-						},
-						Err(e) => {
-							error!("Error writing word image to file {:?}: {}", outpath, e)
-						},
-					}
-				}
+/// Lists an encrypted zip entry that no password decrypts, with the note `encrypted, not extracted`.
+/// An empty file stands in for it, as its content is never written, so its size and crc are those of the zip's directory.
+fn push_encrypted_zip_entry<R: Read>(zipfile: &zip::read::ZipFile<'_, R>, filepath: &Path, outdir: &Path, depth:u8, parent_files: &[String], list_of_files_in_archive: &mut Vec<SubFileItem>, config: &ExtractConfig) -> Result<(), Box<dyn Error>> {
+	let name = zip_entry_name(zipfile);
+	info!("Zip entry {} is encrypted, no text extracted {:?}", name, filepath);
+	let entry_path = zip_entry_path(&name);
+	if entry_path.as_os_str().is_empty() {
+		return Ok(());
+	}
+	let outpath = outdir.join(&entry_path);
+	if let Some(parent) = outpath.parent() {
+		fs::create_dir_all(parent)?;
+	}
+	File::create(&outpath)?;
+	list_of_files_in_archive.push(SubFileItem {
+		filepath: outpath,
+		depth: depth+1,
+		parent_files: zip_entry_parent_files(filepath, &entry_path, parent_files, config),
+		ok_to_extract_text: false,
+		note: Some(String::from("encrypted, not extracted")),
+		listed_size_crc: Some((zipfile.size(), zipfile.crc32() as i64)),
+		..Default::default()
+	});
+	Ok(())
+}
+
+/// The first of the passwords that decrypts an encrypted zip entry, ZipCrypto or AES.
+fn find_zip_password<'a>(archive: &mut ZipArchive<File>, index: usize, passwords: &'a [String]) -> Option<&'a String> {
+	//ZipCrypto checks a single byte of the password up front, so a wrong password can get past it and only fail the CRC check once read
+	passwords.iter().find(|password| {
+		archive.by_index_decrypt(index, password.as_bytes())
+			.is_ok_and(|mut zipfile| io::copy(&mut zipfile, &mut io::sink()).is_ok())
+	})
+}
+
+/// Writes the png and jpeg images in a folder of a zip based document, e.g. `word/media/`, to `outdir`.
+///
+/// # Returns
+///
+/// * The paths of the written images, in zip order. Images that fail to write are logged and left out.
+fn extract_zip_media(filepath: &Path, media_dir: &str, outdir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+	let file = File::open(filepath)?;
+	let mut archive = zip::ZipArchive::new(file)?;
+
+	let mut outpaths = Vec::new();
+	for i in 0..archive.len() {
+		// an entry that cannot be opened, e.g. encrypted or with an unsupported compression method, is skipped
+		// so the rest of the document is still read
+		let mut file = match archive.by_index(i) {
+			Ok(file) => file,
+			Err(e) => {
+				warn!("Skipping zip entry {} of {:?}: {}", i, filepath, e);
+				continue;
+			},
+		};
+		let zipoutpath = match file.enclosed_name() {
+			Some(path) => path.to_owned(),
+			None => continue,
+		};
+
+		// Check if the file is in the media folder and has a typical image extension
+		if zipoutpath.starts_with(media_dir)
+			&& zipoutpath.extension().is_some_and(|ext| ext == "png" || ext == "jpeg" || ext == "jpg")
+		{
+			let outpath = outdir.join(zipoutpath.file_name().unwrap());
+			fs::create_dir_all(outdir)?;
+
+			let mut outfile = File::create(&outpath)?;
+			match io::copy(&mut file, &mut outfile) {
+				Ok(_) => outpaths.push(outpath),
+				Err(e) => {
+					error!("Error writing image to file {:?}: {}", outpath, e);
+					drop(outfile);
+					_ = fs::remove_file(&outpath);
+				},
 			}
 		}
-		"pdf" => {
-			list_of_files_in_archive.push(SubFileItem {
-				filepath: filepath.to_path_buf(),
-				depth,
-				parent_files: parent_files.clone(),
-				ok_to_extract_text: false,
-			});
+	}
+	Ok(outpaths)
+}
 
-			fs::create_dir_all(tempfiles_location().join(&achive_uuid_subdir))?;
+/// The images of a zip based document written by extract_zip_media, to be read by OCR as subfiles of the document.
+/// None are written without ExtractConfig::ocr_embedded_images.
+fn extract_embedded_images(filepath: &Path, media_dir: &str, outdir: &Path, config: &ExtractConfig) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+	if !config.ocr_embedded_images {
+		debug!("Not extracting the images of {:?}", filepath);
+		return Ok(Vec::new());
+	}
+	extract_zip_media(filepath, media_dir, outdir)
+}
 
-			// get page count
-			let mut page_count: u32 = 0;
-			let mut command = Command::new("pdfinfo");
-			command.arg(format!("{}", filepath.to_string_lossy().to_string()));
+/// Extracts the embedded images of a docx, docm, odt, pptx, pptm, ppsx or pdf to a directory without reading them,
+/// e.g. to run another OCR or vision model on them. Pdf images are written by pdfimages, as ppm files.
+///
+/// # Returns
+///
+/// * The paths of the extracted images, empty for other file types
+pub fn extract_images(filepath: &Path, outdir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+	match get_effective_file_type(filepath) {
+		FileType::Docx | FileType::Docm => extract_zip_media(filepath, "word/media/", outdir),
+		FileType::Odt => extract_zip_media(filepath, "Pictures/", outdir),
+		FileType::Ppsx | FileType::Pptm | FileType::Pptx => extract_zip_media(filepath, "ppt/media/", outdir),
+		FileType::Pdf => {
+			// pdfimages writes image-000.ppm, image-001.ppm...
+			fs::create_dir_all(outdir)?;
+			let mut command = Command::new("pdfimages");
+			command
+				.arg(filepath)
+				.arg(outdir.join("image"));
 			debug!("{:#?}", command);
-			match command.output() {
+			let runner = LimitedCommandRunner { runner: &SystemCommandRunner, limit: &ProcessLimit::default() };
+			match runner.output(&mut command) {
 				Ok(output) => {
-					// println!("stdout: {}", String::from_utf8_lossy(&output.stdout));
-					// println!("stderr: {}", String::from_utf8_lossy(&output.stderr));
 					if !output.stderr.is_empty() {
-						debug!("{:#?}", command);
 						warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
-					} else {
-						let output = String::from_utf8_lossy(&output.stdout);
-						let output = output.lines();
-						for line in output {
-							if line.starts_with("Pages:") {
-								let pc = line.split_whitespace();
-								if let Some(pc) = pc.last() {
-									let pc: u32 = pc.parse()?;
-									page_count = pc;
-								} else {
-									println!("{:#?}", command);
-									return Err(format!("No page count found in PDF {}", filepath.to_string_lossy()).into())
-								}
-							}
-						}
 					}
 				}
 				Err(e) => {
-					println!("{:#?}", command);
 					return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
 				}
 			}
-			trace!("PDF page count {}", page_count);
-			for page_number in 1..=page_count {
-				// debug!("page number: {}", page_number)
+			let mut outpaths: Vec<PathBuf> = fs::read_dir(outdir)?
+				.filter_map(|x| x.ok())
+				.map(|x| x.path())
+				.filter(|x| x.file_name().is_some_and(|x| x.to_string_lossy().starts_with("image-")))
+				.collect();
+			outpaths.sort();
+			Ok(outpaths)
+		}
+		_ => Ok(Vec::new()),
+	}
+}
 
-				//page text
-				let mut is_text_extract_denied = false;
-				// pdftotext -f 1 -l 1 /home/ray/MEGA/Rays/Programming/python/file/test_text_extract/docs/sample2.pdf -
-				// pdftotext -f 1 -l 1 -enc UTF-8 "C:\Users\hrag\Sync\Programming\python\file\test_text_extract\docs\fiche d'evaluation du stagiaire - Loïc Vital.pdf" C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\pdftext.txt
-				// https://www.xpdfreader.com/pdftotext-man.html
-				let outpath = tempfiles_location().join(&achive_uuid_subdir).join(format!("page {}", page_number));
-				let mut command = Command::new("pdftotext");
-				command
-					.arg("-f").arg(format!("{}", page_number))
-					.arg("-l").arg(format!("{}", page_number))
-					.arg(format!("{}", filepath.to_string_lossy().to_string()))
-					.arg(format!("{}", outpath.to_string_lossy().to_string()));
-				debug!("{:#?}", command);
-				match command.output() {
-					Ok(output) => {
-						if !output.stderr.is_empty() {
-							let output_text = String::from_utf8_lossy(&output.stderr);
-							if output_text.contains("Copying of text from this document is not allowed") {
-								is_text_extract_denied = true;
-							} else {
-								debug!("{:#?}", command);
-								warn!("Error returned from {:?}: {}", command.get_program(), output_text);
-							}
-						}
-						if !is_text_extract_denied {
-							let mut new_parent_files = parent_files.clone();
-							new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-							extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-						}
-					}
-					Err(e) => {
-						println!("{:#?}", command);
-						return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
-					}
+/// Writes the rows of a sheet to `outpath` as they are made, a line per row with text, its cells joined with `config.cell_separator`,
+/// so the text of a sheet of a million rows is never held in memory. Stops before the row that would pass `config.max_sheet_rows`
+/// or `config.max_sheet_cells`. Nothing is left at `outpath` if no row has text.
+///
+/// # Returns
+///
+/// * Whether any row was written, and whether rows were left out
+fn write_sheet_rows(outpath: &Path, rows: impl Iterator<Item = Vec<String>>, config: &ExtractConfig) -> io::Result<(bool, bool)> {
+	let mut writer = BufWriter::new(File::create(outpath)?);
+	let (mut row_count, mut cell_count) = (0usize, 0usize);
+	let mut is_truncated = false;
+	for row in rows {
+		let line = row.join(&config.cell_separator);
+		if line.trim().is_empty() {
+			continue;
+		}
+		let row_cells = row.iter().filter(|x| !x.is_empty()).count();
+		if config.max_sheet_rows.is_some_and(|x| row_count >= x) || config.max_sheet_cells.is_some_and(|x| cell_count + row_cells > x) {
+			is_truncated = true;
+			break;
+		}
+		writer.write_all(line.as_bytes())?;
+		writer.write_all(config.row_separator.as_bytes())?;
+		row_count += 1;
+		cell_count += row_cells;
+	}
+	writer.flush()?;
+	drop(writer);
+	if row_count == 0 {
+		fs::remove_file(outpath)?;
+	}
+	Ok((row_count > 0, is_truncated))
+}
+
+/// Reads the cells of an xlsx sheet a cell at a time, as rows of text laid out as calamine's worksheet_range lays them out:
+/// from the first used row and column, each row as wide as the widest. Once a row starts past `config.max_sheet_rows` or
+/// `config.max_sheet_cells` the rest of the sheet is not read, so only the rows kept are held in memory. The rows read past
+/// the caps are still returned, for write_sheet_rows to mark the sheet truncated.
+fn read_xlsx_sheet_rows<RS: Read + Seek>(workbook: &mut Xlsx<RS>, sheet_name: &str, config: &ExtractConfig) -> Result<impl Iterator<Item = Vec<String>>, XlsxError> {
+	let mut cells_reader = workbook.worksheet_cells_reader(sheet_name)?;
+	let mut rows: BTreeMap<u32, Vec<(u32, String)>> = BTreeMap::new();
+	let (mut first_col, mut last_col) = (u32::MAX, 0);
+	let (mut text_row_count, mut text_cell_count) = (0usize, 0usize);
+	let mut current_row: Option<(u32, bool)> = None;
+	while let Some(cell) = cells_reader.next_cell()? {
+		let value = Data::from(cell.get_value().clone());
+		if value.is_empty() {
+			continue;
+		}
+		let (row, col) = cell.get_position();
+		if current_row.is_none_or(|(x, _)| x != row) {
+			if config.max_sheet_rows.is_some_and(|x| text_row_count > x) || config.max_sheet_cells.is_some_and(|x| text_cell_count > x) {
+				break;
+			}
+			current_row = Some((row, false));
+		}
+		let text = cell_text(&value);
+		if !text.is_empty() {
+			text_cell_count += 1;
+			if let Some((_, has_text)) = current_row.as_mut()
+				&& !*has_text
+			{
+				*has_text = true;
+				text_row_count += 1;
+			}
+		}
+		first_col = first_col.min(col);
+		last_col = last_col.max(col);
+		rows.entry(row).or_default().push((col, text));
+	}
+	Ok(rows.into_values().map(move |row_cells| {
+		let mut cells = vec![String::new(); (last_col + 1 - first_col) as usize];
+		for (col, text) in row_cells {
+			cells[(col - first_col) as usize] = text;
+		}
+		cells
+	}))
+}
+
+/// Lists a sheet of the workbook `filepath`, its rows written to `outpath` by write_sheet_rows, recording `method` as how its text was obtained.
+/// A sheet cut short by the caps is marked `truncated`.
+#[allow(clippy::too_many_arguments)]
+fn extract_sheet(outpath: &Path, rows: impl Iterator<Item = Vec<String>>, method: &str, filepath: &Path, depth: u8, parent_files: &[String], list_of_files_in_archive: &mut Vec<SubFileItem>, context: &UnpackContext, config: &ExtractConfig) -> Result<(), Box<dyn Error>> {
+	fs::create_dir_all(outpath.parent().unwrap())?;
+	match write_sheet_rows(outpath, rows, config) {
+		Ok((true, is_truncated)) => {
+			if is_truncated {
+				info!("Sheet {:?} of {:?} has more rows than max_sheet_rows or max_sheet_cells, the rest are left out", outpath.file_name().unwrap_or_default(), filepath);
+			}
+			let mut new_parent_files = parent_files.to_vec();
+			new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+			let sheet_item_index = list_of_files_in_archive.len();
+			extract_generated_file(outpath, method, depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+			if let Some(item) = list_of_files_in_archive.get_mut(sheet_item_index) {
+				item.truncated = is_truncated;
+			}
+		}
+		Ok((false, _)) => (),
+		Err(e) => {
+			error!("Error writing to file {:?}: {}", outpath, e)
+		}
+	}
+	Ok(())
+}
+
+/// The text of a spreadsheet cell. Booleans read as TRUE/FALSE and errors as Excel shows them, dates as ISO 8601.
+fn cell_text(cell: &Data) -> String {
+	match cell {
+		Data::Bool(value) => if *value { "TRUE".to_string() } else { "FALSE".to_string() },
+		Data::Error(e) => e.to_string(),
+		Data::DateTime(value) if value.is_datetime() => {
+			let (year, month, day, hour, minute, second, _) = value.to_ymd_hms_milli();
+			if (hour, minute, second) == (0, 0, 0) {
+				format!("{:04}-{:02}-{:02}", year, month, day)
+			} else {
+				format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", year, month, day, hour, minute, second)
+			}
+		}
+		Data::DateTime(value) => value.as_f64().to_string(),
+		Data::DateTimeIso(value) | Data::DurationIso(value) => value.clone(),
+		_ => cell.as_string().unwrap_or_default(),
+	}
+}
+
+/// Collects the text of the body parts of a MIME tree, in order, from `part_id` down.
+/// Of a multipart/alternative only the plain text alternative is read, or else the last, richest one. HTML is converted to text.
+/// Attachments are left out, they are extracted as files.
+fn mime_body_texts(message: &Message, part_id: MessagePartId, texts: &mut Vec<String>) {
+	let Some(part) = message.parts.get(part_id as usize) else {
+		return;
+	};
+	if message.attachments.contains(&part_id) {
+		return;
+	}
+	match &part.body {
+		PartType::Text(text) => {
+			let is_encoding_known = part.content_transfer_encoding()
+				.is_some_and(|x| KNOWN_TRANSFER_ENCODINGS.iter().any(|known| x.trim().eq_ignore_ascii_case(known)));
+			let decoded = if is_encoding_known { None } else { decode_leftover_transfer_encoding(text) };
+			texts.push(decoded.unwrap_or_else(|| text.to_string()));
+		}
+		PartType::Html(html) => texts.push(html_to_text(html)),
+		PartType::Multipart(children) => {
+			let is_alternative = part.content_type()
+				.and_then(|x| x.subtype())
+				.is_some_and(|x| x.eq_ignore_ascii_case("alternative"));
+			if is_alternative {
+				let plain_text = children.iter().find(|id| matches!(message.parts.get(**id as usize).map(|x| &x.body), Some(PartType::Text(_))));
+				if let Some(id) = plain_text.or(children.last()) {
+					mime_body_texts(message, *id, texts);
 				}
+			} else {
+				for id in children {
+					mime_body_texts(message, *id, texts);
+				}
+			}
+		}
+		_ => (),
+	}
+}
 
-				//page images
-				if is_text_extract_denied {
-					//OCR on the entire page
-					// pdftopng -f 1 -l 1 -gray "C:\Users\hrag\Sync\Programming\rust\rust-extract-text\tests\resources\files_to_scan\docs\ILEADER-V4 3-User Manual-Administration Module-1.0.0.pdf" C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\page
-					#[cfg(target_os = "windows")]
-					{
-						//appends -000001.png
-						let pdfimages_outpath = tempfiles_location().join(&achive_uuid_subdir).join("page");
-						let outpath = tempfiles_location().join(&achive_uuid_subdir).join(format!("page-{:06}.png", page_number));
-						let mut command = Command::new("pdftopng");
-						command
-							.arg("-f").arg(format!("{}", page_number))
-							.arg("-l").arg(format!("{}", page_number))
-							.arg("-gray")
-							.arg(format!("{}", filepath.to_string_lossy().to_string()))
-							.arg(format!("{}", pdfimages_outpath.to_string_lossy().to_string()));
-						debug!("{:#?}", command);
-						match command.output() {
-							Ok(output) => {
-								if !output.stderr.is_empty() {
-									let output_text = String::from_utf8_lossy(&output.stderr);
-									if output_text.contains("No display font") {
-										//don't worry about this error
-									} else {
-										debug!("{:#?}", command);
-										warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
-									}
-								}
-								let mut new_parent_files = parent_files.clone();
-								new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-								extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-							}
-							Err(e) => {
-								println!("{:#?}", command);
-								return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
-							}
-						}
-					}
-					#[cfg(target_os = "linux")]
-					{
-						panic!("TODO, page to png in linux");
-					}
-				} else {
-					// pdfimages -list /home/ray/MEGA/Rays/Programming/python/file/test_text_extract/docs/sample2.pdf /tmp/extract_text_from_file/870eabfb3dc44ae185b84f6056f73397/image
-					// pdfimages -list "C:\Users\hrag\Sync\Programming\python\file\test_text_extract\docs\fiche d'evaluation du stagiaire - Loïc Vital.pdf" C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\image
-					// https://www.xpdfreader.com/pdfimages-man.html
-					let pdfimages_outpath = tempfiles_location().join(&achive_uuid_subdir).join(format!("page {} image", page_number));
-					#[cfg(target_os = "windows")]
+/// Lists an eml file, its body as text and its attachments (recursively).
+fn extract_eml(filepath: &Path, depth:u8, parent_files: Vec<String>, list_of_files_in_archive: &mut Vec<SubFileItem>, context: &UnpackContext, config: &ExtractConfig) -> Result<(), Box<dyn Error>> {
+	let achive_uuid_subdir: &str = &Uuid::new_v4().simple().to_string();
+
+	list_of_files_in_archive.push(SubFileItem {
+		filepath: filepath.to_path_buf(),
+		depth,
+		parent_files: parent_files.clone(),
+		ok_to_extract_text: false,
+		..Default::default()
+	});
+	
+	let mut file = File::open(filepath)?;
+	let mut raw_email_data = Vec::new();
+	file.read_to_end(&mut raw_email_data)?;
+
+	let mut bodytext:String = String::new();
+	if let Some(message) = MessageParser::default().parse(&raw_email_data) {
+		if let Some(subject) = message.subject() {
+			bodytext.push_str(subject);
+		}
+		//every body part, not just the first: forwarded and multipart/mixed messages have their text spread over several
+		let mut body_texts = Vec::new();
+		mime_body_texts(&message, 0, &mut body_texts);
+		//the same text in another representation, e.g. in both branches of a forwarded message, is kept once
+		let mut seen_texts: HashSet<String> = HashSet::new();
+		for body in body_texts {
+			let normalized = body.split_whitespace().collect::<Vec<&str>>().join(" ");
+			if normalized.is_empty() || !seen_texts.insert(normalized) {
+				continue;
+			}
+			if !bodytext.is_empty() && !bodytext.ends_with('\n') {
+				bodytext.push('\n');
+			}
+			bodytext.push_str(&body);
+		}
+		let outpath = tempfiles_location().join(&achive_uuid_subdir).join("body.txt");
+		let mut new_parent_files = parent_files.clone();
+		new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+		push_text_subfile(outpath, bodytext, "mail-parser", depth+1, new_parent_files, list_of_files_in_archive);
+		
+		fs::create_dir_all(tempfiles_location().join(achive_uuid_subdir))?;
+		for attachment in message.attachments() {
+			let temp_filename = &Uuid::new_v4().simple().to_string();
+			let attachment_name = attachment.attachment_name().unwrap_or(temp_filename);
+			//println!("Attachment found: {}", attachment_name);
+			let outpath = tempfiles_location().join(&achive_uuid_subdir).join(attachment_name);
+			match fs::write(&outpath, attachment.contents()) {
+				Ok(_) => {
+					let mut new_parent_files = parent_files.clone();
+					new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+					extract_attachment(outpath.as_path(), false, depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+				},
+				Err(e) => {
+					error!("Error writing to file {:?}: {}", outpath, e)
+				},
+			}
+
+		}
+	}
+
+	Ok(())
+}
+
+/// Extracts an attachment of an eml or msg file written to `filepath`, unless its size or detected type is left out by
+/// ExtractConfig::max_attachment_size or attachment_extensions, when it is listed without text and nothing inside it is read.
+/// `is_eml` is an attached email, which is read as an eml whatever its name.
+fn extract_attachment(filepath: &Path, is_eml: bool, depth:u8, parent_files: Vec<String>, list_of_files_in_archive: &mut Vec<SubFileItem>, context: &UnpackContext, config: &ExtractConfig) -> Result<(), Box<dyn Error>> {
+	let file_type = if is_eml { FileType::Eml } else { get_effective_file_type(filepath) };
+	let note = if config.max_attachment_size.is_some_and(|x| filepath.metadata().is_ok_and(|m| m.len() > x)) {
+		Some("large attachment, not extracted")
+	} else if !config.is_attachment_wanted(file_type.extension()) {
+		Some("attachment type skipped, not extracted")
+	} else {
+		None
+	};
+	if let Some(note) = note {
+		debug!("Not extracting attachment {:?} of type {:?}: {}", filepath, file_type, note);
+		list_of_files_in_archive.push(SubFileItem {
+			filepath: filepath.to_path_buf(),
+			depth,
+			parent_files,
+			ok_to_extract_text: false,
+			note: Some(String::from(note)),
+			..Default::default()
+		});
+		return Ok(())
+	}
+	if is_eml {
+		extract_eml(filepath, depth, parent_files, list_of_files_in_archive, context, config)
+	} else {
+		extract_archive(filepath, depth, parent_files, list_of_files_in_archive, context, config)
+	}
+}
+
+/// Extracts an MHTML web page archive, a MIME multipart of the page and its resources.
+/// The main HTML part is converted to text, and the images are extracted for OCR. Other resources (stylesheets, scripts) are left out.
+fn extract_mht(filepath: &Path, depth:u8, parent_files: Vec<String>, list_of_files_in_archive: &mut Vec<SubFileItem>, context: &UnpackContext, config: &ExtractConfig) -> Result<(), Box<dyn Error>> {
+	let achive_uuid_subdir: &str = &Uuid::new_v4().simple().to_string();
+
+	list_of_files_in_archive.push(SubFileItem {
+		filepath: filepath.to_path_buf(),
+		depth,
+		parent_files: parent_files.clone(),
+		ok_to_extract_text: false,
+		..Default::default()
+	});
+
+	let raw_data = fs::read(filepath)?;
+	let Some(message) = MessageParser::default().parse(&raw_data) else {
+		return Err(format!("No MIME structure found in {:?}", filepath).into());
+	};
+	let mut new_parent_files = parent_files.clone();
+	new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+
+	//the main part is the first HTML part
+	let page_text = message.body_html(0)
+		.map(|html| html_to_text(&html))
+		.unwrap_or_default();
+	let outpath = tempfiles_location().join(achive_uuid_subdir).join("page.txt");
+	push_text_subfile(outpath, page_text, "mail-parser", depth+1, new_parent_files.clone(), list_of_files_in_archive);
+
+	for resource in message.attachments() {
+		if resource.content_type().is_none_or(|x| !x.ctype().eq_ignore_ascii_case("image")) {
+			continue;
+		}
+		//resources are named by their url, cut to its last component so it can't point outside the temp folder
+		let mut resource_name = resource.attachment_name()
+			.or_else(|| resource.content_location().and_then(|x| x.split(['?', '#']).next()))
+			.and_then(|x| x.rsplit(['/', '\\']).next())
+			.unwrap_or_default()
+			.to_string();
+		resource_name.retain(|c| !FILENAME_ILLEGAL_CHARS.contains(&c));
+		if resource_name.is_empty() || resource_name == "." || resource_name == ".." {
+			resource_name = Uuid::new_v4().simple().to_string();
+		}
+		//each in a folder of its own, resources of the same name from different urls don't overwrite each other
+		let outdir = tempfiles_location().join(achive_uuid_subdir).join(Uuid::new_v4().simple().to_string());
+		fs::create_dir_all(&outdir)?;
+		let outpath = outdir.join(&resource_name);
+		match fs::write(&outpath, resource.contents()) {
+			Ok(_) => {
+				extract_archive(outpath.as_path(), depth+1, new_parent_files.clone(), list_of_files_in_archive, context, config)?;
+			},
+			Err(e) => {
+				error!("Error writing to file {:?}: {}", outpath, e)
+			},
+		}
+	}
+
+	Ok(())
+}
+
+/// Checks whether bytes look like an RFC 822 message (an eml file), i.e. start with a well known header field.
+fn looks_like_eml(data: &[u8]) -> bool {
+	let first_line = data.split(|&b| b == b'\n').next().unwrap_or_default();
+	let Some(colon) = first_line.iter().position(|&b| b == b':') else {
+		return false;
+	};
+	let field_name = String::from_utf8_lossy(&first_line[..colon]).to_lowercase();
+	[
+		"return-path", "received", "delivered-to", "from", "to", "date", "subject", "message-id", "mime-version", "reply-to", "sender",
+	].contains(&field_name.as_str())
+}
+
+/// Produces a list of files held within files (if any), recursive, and extracts individual files within archives to a temp folder.
+/// 
+/// # Arguments
+/// 
+/// * `filepath` - A path to the top-level file to search for subfiles within
+/// * `config` - Extraction options
+/// 
+/// # Returns
+/// 
+/// * A heirarchal list of filepaths of any extracted files, includes the top-level file
+fn extract_archive(filepath: &Path, depth:u8, parent_files: Vec<String>, list_of_files_in_archive: &mut Vec<SubFileItem>, context: &UnpackContext, config: &ExtractConfig) -> Result<(), Box<dyn Error>> {
+
+
+	debug!("filepath: {:?}", filepath);
+	//an unchanged member is not opened, so no program such as pdftotext is run on it or on what it holds
+	if depth >= 1 && context.is_unchanged_member(filepath, &parent_files) {
+		debug!("Member not changed, not unpacked: {:?}", filepath);
+		list_of_files_in_archive.push(SubFileItem {
+			filepath: filepath.to_path_buf(),
+			depth,
+			parent_files,
+			unchanged: true,
+			..Default::default()
+		});
+		return Ok(())
+	}
+	if filepath.metadata()?.len() == 0 {
+		list_of_files_in_archive.push(SubFileItem {
+			filepath: filepath.to_path_buf(),
+			depth,
+			parent_files: parent_files.clone(),
+			ok_to_extract_text: true,
+			..Default::default()
+		});
+		return Ok(())
+	}
+
+	let achive_uuid_subdir: &str = &Uuid::new_v4().simple().to_string();
+
+	//switch filepath extension
+	let file_type = get_effective_file_type(filepath);
+	debug!("extract_archive: file_type: {:?}", file_type);
+
+	//listed with their size and crc only, whether by path extension or detected type
+	if config.is_text_extraction_skipped(&filepath.extension().unwrap_or_default().to_string_lossy())
+		|| config.is_text_extraction_skipped(file_type.extension())
+	{
+		debug!("Not extracting text from {:?} of skipped type {:?}", filepath, file_type);
+		list_of_files_in_archive.push(SubFileItem {
+			filepath: filepath.to_path_buf(),
+			depth,
+			parent_files: parent_files.clone(),
+			ok_to_extract_text: false,
+			note: Some(String::from("skipped type, not extracted")),
+			..Default::default()
+		});
+		return Ok(())
+	}
+
+	//user registered types are extracted as they are, even if the built-in handling would open them as archives
+	if config.extractors.is_custom(&file_type) {
+		list_of_files_in_archive.push(SubFileItem {
+			filepath: filepath.to_path_buf(),
+			depth,
+			parent_files: parent_files.clone(),
+			ok_to_extract_text: true,
+			..Default::default()
+		});
+		return Ok(())
+	}
+	
+	//the parts of a split archive are read together, as the first part
+	if depth == 0 && let Some(split) = split_archive(filepath) {
+		let note = if !split.is_complete() {
+			Some(String::from("incomplete split archive, not extracted"))
+		} else if split.part > 1 {
+			Some(format!("part {} of split archive {}, read with the first part", split.part, split.name))
+		} else {
+			None
+		};
+		list_of_files_in_archive.push(SubFileItem {
+			filepath: filepath.to_path_buf(),
+			depth,
+			parent_files: parent_files.clone(),
+			ok_to_extract_text: false,
+			note: note.clone(),
+			..Default::default()
+		});
+		if note.is_some() {
+			return Ok(())
+		}
+		let outpath = tempfiles_location().join(achive_uuid_subdir).join(&split.name);
+		fs::create_dir_all(outpath.parent().unwrap())?;
+		let mut outfile = File::create(&outpath)?;
+		for (_, part_path) in &split.parts {
+			io::copy(&mut File::open(part_path)?, &mut outfile)?;
+		}
+		drop(outfile);
+		let mut new_parent_files = parent_files.clone();
+		new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+		return extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config);
+	}
+	if is_spanned_zip_part(filepath) {
+		list_of_files_in_archive.push(SubFileItem {
+			filepath: filepath.to_path_buf(),
+			depth,
+			parent_files: parent_files.clone(),
+			ok_to_extract_text: false,
+			note: Some(String::from("spanned zip archive, not extracted")),
+			..Default::default()
+		});
+		return Ok(())
+	}
+
+	match &file_type {
+		FileType::SevenZip => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+
+			let outpath = tempfiles_location().join(&achive_uuid_subdir);
+			//members left out by archive_member_patterns are read past, not written
+			let mut modified_times: HashMap<PathBuf, i64> = HashMap::new();
+			let mut extract_fn = |entry: &SevenZArchiveEntry, reader: &mut dyn Read, dest: &PathBuf| {
+				if entry.is_directory() || config.is_archive_member_wanted(entry.name()) {
+					if entry.has_last_modified_date
+						&& let Ok(since_epoch) = std::time::SystemTime::from(entry.last_modified_date()).duration_since(std::time::UNIX_EPOCH)
 					{
-						let mut command = Command::new("pdfimages");
-						command
-							.arg("-f").arg(format!("{}", page_number))
-							.arg("-l").arg(format!("{}", page_number))
-							.arg("-list")
-							.arg(format!("{}", filepath.to_string_lossy().to_string()))
-							.arg(format!("{}", pdfimages_outpath.to_string_lossy().to_string()));
-						debug!("{:#?}", command);
-						match command.output() {
-							Ok(output) => {
-								if !output.stderr.is_empty() {
-									debug!("{:#?}", command);
-									warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
-								} else {
-									//println!("stdout: {}", String::from_utf8_lossy(&output.stdout));
-									let output = String::from_utf8_lossy(&output.stdout);
-									let output = output.lines();
-									for line in output {
-										if let Some((image_filename, _)) = line.split_once(": ") {
-											// println!(">>> {}", image_filename);
-											let outpath = PathBuf::from(image_filename);
-											let mut new_parent_files = parent_files.clone();
-											new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-											extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-										}
-									}
-								}
-							}
-							Err(e) => {
-								println!("{:#?}", command);
-								return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
-							}
-						}
+						modified_times.insert(dest.clone(), since_epoch.as_secs() as i64);
 					}
-					#[cfg(target_os = "linux")]
-					{
-						//linux, first get list of images in page, then extract
-						let mut command = Command::new("pdfimages");
-						command
-							.arg("-f").arg(format!("{}", page_number))
-							.arg("-l").arg(format!("{}", page_number))
-							.arg("-list")
-							.arg(format!("{}", filepath.to_string_lossy().to_string()));
-						debug!("{:#?}", command);
-						match command.output() {
-							Ok(output) => {
-								if !output.stderr.is_empty() {
-									debug!("{:#?}", command);
-									warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
-								} else {
-									let output = String::from_utf8_lossy(&output.stdout);
-									//println!("stdout: {}", output);
-									let image_output_lines:Vec<&str> = output.trim_end().lines().collect();
-									//println!("*** image_output_lines\n{:?}", image_output_lines);
-									let num_images = image_output_lines.len() - 2;
-									// println!(">>> num_images {}", num_images);
-									if num_images > 0 {
-										//export
-										let image_filename_prefix = pdfimages_outpath.to_string_lossy().to_string();
-										let mut command = Command::new("pdfimages");
-										command
-											.arg("-f").arg(format!("{}", page_number))
-											.arg("-l").arg(format!("{}", page_number))
-											.arg(format!("{}", filepath.to_string_lossy().to_string()))
-											.arg(format!("{}", image_filename_prefix));
-										debug!("{:#?}", command);
-										match command.output() {
-											Ok(output) => {
-												if !output.stderr.is_empty() {
-													debug!("{:#?}", command);
-													warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
-												}
-											}
-											Err(e) => {
-												println!("{:#?}", command);
-												return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
-											}
-										}
-										for iimg in 0..num_images {
-											// let image_info:Vec<&str> = image_output_lines[iimg+2].split_ascii_whitespace().collect();
-											//type image -> .ppm, type stencil -> .pbm
-											// let image_type = image_info[2];
-											// let image_color = image_info[5];
-											// let image_ext;
-											// if image_color == "index" {
-											// 	image_ext = "pbm";
-											// } else if image_color == "gray" {
-											// 	image_ext = "pbm";
-											// } else if image_type == "stencil" {
-											// 	image_ext = "pbm";
-											// } else if image_type == "image" {
-											// 	image_ext = "ppm";
-											// } else if image_type == "smask" {
-											// 	image_ext = "ppm";
-											// } else {
-											// 	return Err(format!("Unknown PDF embedded image type {}", image_type).into());
-											// }
-											// println!("image_info\n{:?}", image_info);
-											let image_filename_base = image_filename_prefix.clone();
-											let image_filename_ppm = image_filename_base.clone() + &format!("-{:03}.{}", iimg, "ppm");
-											// let image_filename_pbm = image_filename_base + &format!("-{:03}.{}", iimg, "pbm");
-											let outpath_ppm = PathBuf::from(image_filename_ppm);
-											// let outpath_pbm = PathBuf::from(&image_filename_pbm);
-											let outpath;
-											if outpath_ppm.exists() {
-												outpath = outpath_ppm;
-											// } else if outpath_pbm.exists() {
-											// 	outpath = outpath_pbm;
-											// } else {
-											// 	return Err(format!("Unknown PDF embedded image file extension: {}", image_filename_pbm).into());
-											// }
-												let mut new_parent_files = parent_files.clone();
-												new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-												extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-											} else {
-												debug!("No PDF embedded image found: {:?}", outpath_ppm);
-											}
-										}
-									}
-								}
-							}
-							Err(e) => {
-								println!("{:#?}", command);
-								return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
-							}
+					default_entry_extract_fn(entry, reader, dest)
+				} else {
+					io::copy(reader, &mut io::sink()).map_err(sevenz_rust::Error::io)?;
+					Ok(true)
+				}
+			};
+			//the passwords are tried in turn, an archive that is not encrypted ignores the password
+			let mut passwords = config.archive_passwords.iter();
+			let mut password = passwords.next().map(String::as_str).unwrap_or_default();
+			loop {
+				match decompress_with_extract_fn_and_password(File::open(filepath)?, &outpath, password.into(), &mut extract_fn) {
+					Ok(()) => {
+						debug!("Extracted 7z to: {:?}", outpath);
+					}
+					Err(sevenz_rust::Error::MaybeBadPassword(_) | sevenz_rust::Error::PasswordRequired) => {
+						//a wrong password leaves a garbled file behind
+						_ = fs::remove_dir_all(&outpath);
+						if let Some(next_password) = passwords.next() {
+							password = next_password;
+							continue;
 						}
+						info!("7z is encrypted, no text extracted {:?}", filepath);
+						list_of_files_in_archive.last_mut().unwrap().note = Some(String::from("encrypted, not extracted"));
+					}
+					Err(err) => {
+						//a damaged archive is listed with its error, along with any files extracted before the damage
+						warn!("Error extracting 7z {:?}: {}", filepath, err);
+						list_of_files_in_archive.last_mut().unwrap().error = Some(err.to_string());
+					}
+				}
+				break;
+			}
 
+			// Walk through all files and directories recursively
+			for entry in WalkDir::new(&outpath)
+				.into_iter()
+				.filter_map(|e| e.ok()) // Skip errors
+			{
+				let path = entry.path();
+				if path.is_file() {
+					let mut new_parent_files = parent_files.clone();
+					new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+					if config.archive_paths_in_lineage
+						&& let Some(internal_dir) = path.strip_prefix(&outpath).ok().and_then(|x| x.parent())
+					{
+						new_parent_files.extend(internal_dir.components().map(|c| c.as_os_str().to_string_lossy().into_owned()));
 					}
+					let entry_item_index = list_of_files_in_archive.len();
+					// new_parent_files passes ownership instead of reference, because we no longer need it after passing into this function
+					extract_archive(path, depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+					set_entry_modified(list_of_files_in_archive, entry_item_index, modified_times.get(path).copied());
 				}
 			}
+		}
+		FileType::Docx | FileType::Docm => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: true,
+				..Default::default()
+			});
+			push_properties_subfile(filepath, &tempfiles_location().join(achive_uuid_subdir), depth, &parent_files, list_of_files_in_archive);
 
+			for outpath in extract_embedded_images(filepath, "word/media/", &tempfiles_location().join(achive_uuid_subdir), config)? {
+				let mut new_parent_files = parent_files.clone();
+				new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+				extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+			}
 		}
-		"ods" | "xlam" | "xls" | "xlsb" | "xlsm" | "xlsx" => {
+		FileType::Encrypted => {
+			info!("Office document is password protected, no text extracted {:?}", filepath);
 			list_of_files_in_archive.push(SubFileItem {
 				filepath: filepath.to_path_buf(),
 				depth,
 				parent_files: parent_files.clone(),
 				ok_to_extract_text: false,
+				note: Some(String::from("encrypted, not extracted")),
+				..Default::default()
 			});
-			//let mut workbook = open_workbook_auto(filepath)?;
-			match open_workbook_auto(filepath) {
-				Ok(mut workbook) => {
-					if let Ok(vbaop) = workbook.vba_project() {
-						if let Some(vba) = vbaop {
-							let vba_modules = vba.get_module_names();
-							trace!("vba_modules: {:#?}", vba_modules);
-							for module_name in vba_modules {
-								let module = vba.get_module(module_name).unwrap();
-								let mut module_name_filename_safe = module_name.to_string();
-								module_name_filename_safe.retain(|c| !FILENAME_ILLEGAL_CHARS.contains(&c));
-								let outpath = tempfiles_location().join(&achive_uuid_subdir).join(format!("VBA_{}", module_name_filename_safe));
-								fs::create_dir_all(outpath.parent().unwrap())?;
-								match fs::write(&outpath, module) {
-									Ok(_) => {
-										let mut new_parent_files = parent_files.clone();
-										new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-										extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-									},
-									Err(e) => {
-										error!("Error writing to file {:?}: {}", outpath, e)
-									},
+		}
+		FileType::One | FileType::Hwp => {
+			info!("{} format is not supported, no text extracted {:?}", file_type, filepath);
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				note: Some(String::from("unsupported format, not extracted")),
+				..Default::default()
+			});
+		}
+		FileType::Eml => {
+			extract_eml(filepath, depth, parent_files, list_of_files_in_archive, context, config)?;
+		}
+		FileType::Mht => {
+			extract_mht(filepath, depth, parent_files, list_of_files_in_archive, context, config)?;
+		}
+		FileType::Msg => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+
+			let mut cfbf = cfb::open(filepath)?;
+
+			let (subject, body, sub_paths) = msg_get_contents(&mut cfbf, PathBuf::from("/"))?;
+			// debug!("{:?}", subject);
+			// debug!("{:?}", body);
+			// debug!("{:?}", sub_paths);
+
+			let outpath = tempfiles_location().join(&achive_uuid_subdir).join("body.txt");
+			let outtext = subject + "\n\n" + &body;
+			let mut new_parent_files = parent_files.clone();
+			new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+			push_text_subfile(outpath, outtext, "msg", depth+1, new_parent_files, list_of_files_in_archive);
+			if config.msg_headers {
+				push_msg_headers_subfile(&mut cfbf, filepath, &tempfiles_location().join(achive_uuid_subdir), depth, &parent_files, list_of_files_in_archive);
+			}
+			//pictures pasted into the body are only kept in its RTF
+			for outpath in extract_msg_rtf_pictures(&mut cfbf, filepath, &tempfiles_location().join(achive_uuid_subdir), config)? {
+				let mut new_parent_files = parent_files.clone();
+				new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+				extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+			}
+
+			//stores the file subpath to write the output to and a list of cfbf subpaths
+			let mut msg_attachments_to_traverse: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+			if !sub_paths.is_empty() {
+				msg_attachments_to_traverse.push((PathBuf::new(), sub_paths.clone()));
+			}
+
+			while !msg_attachments_to_traverse.is_empty() {
+				if let Some((filesubpath, sub_paths)) = msg_attachments_to_traverse.pop() {
+					let achive_uuid_msg_subdir: &str = &Uuid::new_v4().simple().to_string();
+					debug!("sub_paths: {:?}", sub_paths);
+					for sub_path in sub_paths {
+						debug!("depth: {}, path: {:?}", sub_path.components().count()-1, sub_path);
+						// attachment binary, 0x3701 AttachDataObject, 0x0102 PT_BINARY
+						if cfbf.exists(sub_path.join("__substg1.0_37010102")) {
+							// println!("Binary attachment");
+							//attachment filename, 0x3707 AttachLongFilename, 0x001F UTF_16LE or 0x001E in an ANSI msg
+							let filename = msg_string_stream(&mut cfbf, &sub_path, 0x3707)
+								.ok_or_else(|| format!("Attachment filename stream not found in {:?}", filepath))?;
+							//attachment mime type, 0x370E AttachMimeTag, 0x001F UTF_16LE or 0x001E
+							let mime_tag = msg_string_stream(&mut cfbf, &sub_path, 0x370E).unwrap_or_default();
+							//download binary attachment
+							let mut stream = cfbf.open_stream(sub_path.join("__substg1.0_37010102"))?;
+							let mut data = Vec::new();
+							stream.read_to_end(&mut data)?;
+							//an attached email is not always named .eml, its headers are only sniffed when the name gives no type
+							let has_known_extension = Path::new(&filename).extension()
+								.is_some_and(|x| !matches!(FileType::from_extension(&x.to_string_lossy()), FileType::Other(_)));
+							let is_eml = mime_tag.eq_ignore_ascii_case("message/rfc822") || (!has_known_extension && looks_like_eml(&data));
+							let outpath = tempfiles_location().join(&achive_uuid_subdir).join(achive_uuid_msg_subdir).join(sub_path.components().last().unwrap()).join(filename);
+							fs::create_dir_all(outpath.parent().unwrap())?;
+							match fs::write(&outpath, data) {
+								Ok(_) => {
+									let mut new_parent_files = parent_files.clone();
+									new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+									let parent_files_subpaths: Vec<String> = filesubpath.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+									new_parent_files.extend(parent_files_subpaths);
+									extract_attachment(outpath.as_path(), is_eml, depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+								},
+								Err(e) => {
+									error!("Error writing to file {:?}: {}", outpath, e)
+								},
+							}
+
+						}
+						//attachment msg path, 0x3701 AttachDataObject, 0x0102 PT_BINARY, 0x000D PT_OBJECT
+						else if cfbf.exists(sub_path.join("__substg1.0_3701000D")) {
+							// println!("MSG attachment");
+							//attachment displayname, 0x3001 DisplayName, 0x001F UTF_16LE or 0x001E in an ANSI msg
+							let mut displayname = msg_string_stream(&mut cfbf, &sub_path, 0x3001)
+								.ok_or_else(|| format!("Attachment display name stream not found in {:?}", filepath))?;
+							displayname.retain(|c| !FILENAME_ILLEGAL_CHARS.contains(&c));
+							//empty file placeholder as embedded msg
+							let msg_placeholder_filename = displayname.clone() + ".msg";
+							let outpath = tempfiles_location().join(&achive_uuid_subdir).join(achive_uuid_msg_subdir).join(sub_path.components().last().unwrap()).join(&msg_placeholder_filename);
+							fs::create_dir_all(outpath.parent().unwrap())?;
+							match fs::write(&outpath, "") {
+								Ok(_) => {
+									let mut new_parent_files = parent_files.clone();
+									new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+									let parent_files_subpaths: Vec<String> = filesubpath.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+									new_parent_files.extend(parent_files_subpaths);
+									list_of_files_in_archive.push(SubFileItem {
+										filepath: outpath,
+										depth,
+										parent_files: new_parent_files.clone(),
+										ok_to_extract_text: false,
+										..Default::default()
+									});
+								},
+								Err(e) => {
+									error!("Error writing to file {:?}: {}", outpath, e)
+								},
+							}
+							let filesubpath2 = filesubpath.clone().join(&msg_placeholder_filename);
+							//recurse into path
+							let (subject, body, sub_paths2) = msg_get_contents(&mut cfbf, sub_path.join("__substg1.0_3701000D"))?;
+							// println!("{:?}", sub_path.components().last().unwrap());
+							// println!("{:?}", subject);
+							// println!("{:?}", body);
+							let outpath = tempfiles_location().join(&achive_uuid_subdir).join(achive_uuid_msg_subdir).join(sub_path.components().last().unwrap()).join("body.txt");
+							let outtext = subject + "\n\n" + &body;
+							let mut new_parent_files = parent_files.clone();
+							new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+							let parent_files_subpaths: Vec<String> = filesubpath2.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+							new_parent_files.extend(parent_files_subpaths);
+							push_text_subfile(outpath, outtext, "msg", depth+1, new_parent_files, list_of_files_in_archive);
+							if !sub_paths2.is_empty() {
+								msg_attachments_to_traverse.push((filesubpath2.clone(), sub_paths2.clone()));
+							}
+						}
+						else {
+							return Err(format!("Unknown attachment type. Path: {:?}, file: {:?}", sub_path, filepath).into())
+						}
+					}
+				}
+			}
+		}
+		FileType::Odt => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: true,
+				..Default::default()
+			});
+
+			//comments and notes are left out of the body text, and listed after it. Square brackets can't clash with a picture's file.
+			for (label, heading, entries) in [
+				("[Comments]", "Comments:", odt::read_annotations(filepath)),
+				("[Notes]", "Notes:", odt::read_notes(filepath)),
+			] {
+				match entries {
+					Ok(entries) => {
+						if !entries.is_empty() {
+							let mut text = format!("{}{}", heading, config.row_separator);
+							for (name, entry_text) in entries {
+								text.push_str(&format!("{}{}{}{}", name, config.cell_separator, entry_text, config.row_separator));
+							}
+							let mut new_parent_files = parent_files.clone();
+							new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+							push_text_subfile(tempfiles_location().join(achive_uuid_subdir).join(label), text, "odt xml", depth+1, new_parent_files, list_of_files_in_archive);
+						}
+					}
+					Err(e) => {
+						warn!("Error reading {} from {:?}: {}", label, filepath, e);
+					}
+				}
+			}
+
+			for outpath in extract_embedded_images(filepath, "Pictures/", &tempfiles_location().join(achive_uuid_subdir), config)? {
+				let mut new_parent_files = parent_files.clone();
+				new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+				extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+			}
+		}
+		FileType::Pdf => {
+			let pdf_item_index = list_of_files_in_archive.len();
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+
+			fs::create_dir_all(tempfiles_location().join(&achive_uuid_subdir))?;
+
+			// get page count
+			let mut page_count: u32 = 0;
+			//encrypted with a user password, which is needed to read anything, as opposed to only restricting copying or printing
+			let mut is_password_required = false;
+			let mut command = Command::new("pdfinfo");
+			command.arg(format!("{}", filepath.to_string_lossy().to_string()));
+			debug!("{:#?}", command);
+			match config.runner().output(&mut command) {
+				Ok(output) => {
+					// println!("stdout: {}", String::from_utf8_lossy(&output.stdout));
+					// println!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+					if !output.stderr.is_empty() {
+						let output_text = String::from_utf8_lossy(&output.stderr);
+						if output_text.contains("Incorrect password") {
+							is_password_required = true;
+						} else {
+							debug!("{:#?}", command);
+							warn!("Error returned from {:?}: {}", command.get_program(), output_text);
+						}
+					} else {
+						let output = String::from_utf8_lossy(&output.stdout);
+						let mut is_encrypted = false;
+						let mut has_page_count = false;
+						for line in output.lines() {
+							if let Some(encrypted) = line.strip_prefix("Encrypted:") {
+								is_encrypted = encrypted.trim_start().starts_with("yes");
+							}
+							if line.starts_with("Pages:") {
+								has_page_count = true;
+								let pc = line.split_whitespace();
+								if let Some(pc) = pc.last() {
+									let pc: u32 = pc.parse()?;
+									page_count = pc;
+								} else {
+									println!("{:#?}", command);
+									return Err(format!("No page count found in PDF {}", filepath.to_string_lossy()).into())
 								}
 							}
 						}
+						is_password_required = is_encrypted && !has_page_count;
+					}
+				}
+				Err(e) => {
+					println!("{:#?}", command);
+					return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+				}
+			}
+			trace!("PDF page count {}", page_count);
+			if is_password_required {
+				//every page would fail the same way, so the whole file is marked instead
+				info!("PDF is password protected, no text extracted {:?}", filepath);
+				if let Some(pdf_item) = list_of_files_in_archive.last_mut() {
+					pdf_item.note = Some(String::from("encrypted, not extracted"));
+				}
+				return Ok(());
+			}
+
+			//the outline, scripts, portfolio files and annotations are read from the document structure, loaded once for all of them
+			let pdf_document = match lopdf::Document::load(filepath) {
+				Ok(document) => Some(document),
+				Err(e) => {
+					warn!("Error loading PDF {:?} for its outline, scripts, files and annotations: {}", filepath, e);
+					None
+				}
+			};
+
+			//outline (bookmarks) as a table of contents
+			if let Some(document) = &pdf_document {
+				match pdf_outline(document) {
+					Ok(outline) => {
+						if !outline.is_empty() {
+							let outpath = tempfiles_location().join(&achive_uuid_subdir).join("Outline");
+							match fs::write(&outpath, outline) {
+								Ok(_) => {
+									let mut new_parent_files = parent_files.clone();
+									new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+									extract_generated_file(outpath.as_path(), "pdf outline", depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+								},
+								Err(e) => {
+									error!("Error writing to file {:?}: {}", outpath, e)
+								},
+							}
+						}
+					}
+					Err(e) => {
+						warn!("Error reading PDF outline from {:?}: {}", filepath, e);
+					}
+				}
+			}
+
+			//scripts and launch actions, which are flagged for triage
+			if let Some(document) = &pdf_document {
+				let scripts = pdf_scripts(document);
+				if !scripts.is_empty() {
+					info!("PDF has {} script and launch actions {:?}", scripts.len(), filepath);
+					if let Some(pdf_item) = list_of_files_in_archive.get_mut(pdf_item_index) {
+						pdf_item.has_scripts = true;
+					}
+				}
+				if config.pdf_scripts && !scripts.is_empty() {
+					let mut text = String::from("Scripts:\n");
+					for (kind, script) in scripts {
+						text.push_str(&format!("{}: {}\n", kind, script));
+					}
+					let mut new_parent_files = parent_files.clone();
+					new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+					push_text_subfile(tempfiles_location().join(achive_uuid_subdir).join("[Scripts]"), text, "pdf scripts", depth+1, new_parent_files, list_of_files_in_archive);
+				}
+			}
+
+			//the files bundled in a PDF portfolio, its cover pages are read as usual after them
+			if let Some(document) = &pdf_document
+				&& is_pdf_portfolio(document)
+			{
+				let embedded_files = pdf_embedded_files(document, config.max_file_size);
+				debug!("PDF portfolio {:?} with {} files", filepath, embedded_files.len());
+				for (mut name, contents) in embedded_files {
+					//a folder of its own for each file, so files of the same name, or named like a page, can't overwrite each other
+					name.retain(|c| !FILENAME_ILLEGAL_CHARS.contains(&c));
+					if name.is_empty() || name == "." || name == ".." {
+						name = Uuid::new_v4().simple().to_string();
+					}
+					let outpath = tempfiles_location().join(achive_uuid_subdir).join(Uuid::new_v4().simple().to_string()).join(&name);
+					fs::create_dir_all(outpath.parent().unwrap())?;
+					let mut new_parent_files = parent_files.clone();
+					new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+					let Some(contents) = contents else {
+						info!("Skiping PDF portfolio file {} of {:?} larger than max_file_size", name, filepath);
+						File::create(&outpath)?;
+						list_of_files_in_archive.push(SubFileItem {
+							filepath: outpath,
+							depth: depth+1,
+							parent_files: new_parent_files,
+							ok_to_extract_text: false,
+							note: Some(format!("larger than max_file_size {}, not extracted", config.max_file_size)),
+							..Default::default()
+						});
+						continue;
+					};
+					match fs::write(&outpath, contents) {
+						Ok(_) => {
+							extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+						},
+						Err(e) => {
+							error!("Error writing to file {:?}: {}", outpath, e)
+						},
 					}
+				}
+			}
+
+			let mut annotations = match &pdf_document {
+				Some(document) if config.pdf_annotations => pdf_annotations(document),
+				_ => BTreeMap::new(),
+			};
+
+			//with a page marker, the page texts are merged into one subfile, listed before the page images
+			let mut merged_pages: Option<String> = config.pdf_page_marker.as_ref().map(|_| String::new());
+			let mut is_any_page_garbled = false;
+			let merged_pages_index = list_of_files_in_archive.len();
+			let page_count = if context.enumerate_only { 0 } else { page_count };
+
+			for page_number in 1..=page_count {
+				// debug!("page number: {}", page_number)
+
+				//page text
+				let mut is_text_extract_denied = false;
+				let mut is_text_layer_thin = false;
+				let mut is_text_layer_garbled = false;
+				let mut is_search_term_found = false;
+				// pdftotext -f 1 -l 1 /home/ray/MEGA/Rays/Programming/python/file/test_text_extract/docs/sample2.pdf -
+				// pdftotext -f 1 -l 1 -enc UTF-8 "C:\Users\hrag\Sync\Programming\python\file\test_text_extract\docs\fiche d'evaluation du stagiaire - Loïc Vital.pdf" C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\pdftext.txt
+				// https://www.xpdfreader.com/pdftotext-man.html
+				let outpath = tempfiles_location().join(&achive_uuid_subdir).join(format!("page {}", page_number));
+				let mut command = Command::new("pdftotext");
+				command
+					.arg("-f").arg(format!("{}", page_number))
+					.arg("-l").arg(format!("{}", page_number))
+					.arg(format!("{}", filepath.to_string_lossy().to_string()))
+					.arg(format!("{}", outpath.to_string_lossy().to_string()));
+				debug!("{:#?}", command);
+				match config.runner().output(&mut command) {
+					Ok(output) => {
+						if !output.stderr.is_empty() {
+							let output_text = String::from_utf8_lossy(&output.stderr);
+							if output_text.contains("Copying of text from this document is not allowed") {
+								is_text_extract_denied = true;
+							} else {
+								debug!("{:#?}", command);
+								warn!("Error returned from {:?}: {}", command.get_program(), output_text);
+							}
+						}
+						if !is_text_extract_denied {
+							if config.dehyphenate_pdf_text
+								&& let Ok(page_text) = fs::read_to_string(&outpath)
+							{
+								fs::write(&outpath, dehyphenate(&page_text))?;
+							}
+							//a near empty text layer, e.g. a scanned page with just a page number, is read by OCR as well
+							if config.ocr_fallback_min_text_len > 0 {
+								let page_text = fs::read(&outpath).unwrap_or_default();
+								let page_text_len = String::from_utf8_lossy(&page_text).chars().filter(|c| !c.is_whitespace()).count();
+								is_text_layer_thin = page_text_len < config.ocr_fallback_min_text_len;
+							}
+							//glyph codes instead of text, from a font without a ToUnicode map
+							is_text_layer_garbled = is_garbled_text(&String::from_utf8_lossy(&fs::read(&outpath).unwrap_or_default()));
+							if is_text_layer_garbled {
+								info!("PDF page {} text layer looks garbled {:?}", page_number, filepath);
+							}
+							//when searching, the rest of the pages are not read past a match, so they are not converted either
+							if !config.search_terms.is_empty() {
+								let page_text = String::from_utf8_lossy(&fs::read(&outpath).unwrap_or_default()).to_string();
+								is_search_term_found = find_search_term(&page_text, &config.search_terms).is_some();
+							}
+							if let Some(merged_pages) = &mut merged_pages
+								&& let Some(page_marker) = &config.pdf_page_marker
+							{
+								//the marker takes the place of the form feed pdftotext ends each page with
+								let page_text = String::from_utf8_lossy(&fs::read(&outpath).unwrap_or_default()).to_string();
+								merged_pages.push_str(&page_marker.replace("{page}", &page_number.to_string()));
+								merged_pages.push_str(page_text.trim_end_matches('\x0c'));
+								is_any_page_garbled |= is_text_layer_garbled;
+							} else {
+								let page_index = list_of_files_in_archive.len();
+								let mut new_parent_files = parent_files.clone();
+								new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+								extract_generated_file(outpath.as_path(), "pdftotext", depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+								if is_text_layer_garbled
+									&& let Some(page_item) = list_of_files_in_archive.get_mut(page_index)
+								{
+									page_item.note = Some(String::from(GARBLED_TEXT_LAYER_NOTE));
+								}
+							}
+						}
+					}
+					Err(e) => {
+						println!("{:#?}", command);
+						return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+					}
+				}
+				if is_search_term_found {
+					debug!("Search term found on page {} of {:?}, not converting the rest of the pages", page_number, filepath);
+					break;
+				}
+
+				//annotation comments, which pdftotext leaves out
+				if let Some(text) = annotations.remove(&page_number) {
+					let outpath = tempfiles_location().join(achive_uuid_subdir).join(format!("page {} annotations", page_number));
+					match fs::write(&outpath, text) {
+						Ok(_) => {
+							let mut new_parent_files = parent_files.clone();
+							new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+							extract_generated_file(outpath.as_path(), "pdf annotations", depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+						},
+						Err(e) => {
+							error!("Error writing to file {:?}: {}", outpath, e)
+						},
+					}
+				}
+
+				//page images
+				if is_text_extract_denied || is_text_layer_thin || (is_text_layer_garbled && config.ocr_garbled_text_layer) {
+					//OCR on the entire page
+					// pdftopng -f 1 -l 1 -gray "C:\Users\hrag\Sync\Programming\rust\rust-extract-text\tests\resources\files_to_scan\docs\ILEADER-V4 3-User Manual-Administration Module-1.0.0.pdf" C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\page
+					#[cfg(target_os = "windows")]
+					{
+						//appends -000001.png
+						let pdfimages_outpath = tempfiles_location().join(&achive_uuid_subdir).join("page");
+						let outpath = tempfiles_location().join(&achive_uuid_subdir).join(format!("page-{:06}.png", page_number));
+						let mut command = Command::new("pdftopng");
+						command
+							.arg("-f").arg(format!("{}", page_number))
+							.arg("-l").arg(format!("{}", page_number))
+							.arg("-gray")
+							.arg(format!("{}", filepath.to_string_lossy().to_string()))
+							.arg(format!("{}", pdfimages_outpath.to_string_lossy().to_string()));
+						debug!("{:#?}", command);
+						match config.runner().output(&mut command) {
+							Ok(output) => {
+								if !output.stderr.is_empty() {
+									let output_text = String::from_utf8_lossy(&output.stderr);
+									if output_text.contains("No display font") {
+										//don't worry about this error
+									} else {
+										debug!("{:#?}", command);
+										warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
+									}
+								}
+								let mut new_parent_files = parent_files.clone();
+								new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+								extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+							}
+							Err(e) => {
+								println!("{:#?}", command);
+								return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+							}
+						}
+					}
+					#[cfg(target_os = "linux")]
+					{
+						// pdftoppm -f 1 -l 1 -gray -png -singlefile /home/ray/MEGA/Rays/Programming/python/file/test_text_extract/docs/sample2.pdf "/tmp/extract_text_from_file/870eabfb3dc44ae185b84f6056f73397/page 1 render"
+						//-singlefile appends just the extension, .png by default
+						let (format_args, extension) = config.pdf_ocr_image_format.pdftoppm_args();
+						let pdftoppm_outpath = tempfiles_location().join(achive_uuid_subdir).join(format!("page {} render", page_number));
+						let outpath = tempfiles_location().join(achive_uuid_subdir).join(format!("page {} render.{}", page_number, extension));
+						let mut command = Command::new("pdftoppm");
+						command
+							.arg("-f").arg(page_number.to_string())
+							.arg("-l").arg(page_number.to_string())
+							.args(format_args)
+							.arg("-singlefile")
+							.arg(filepath)
+							.arg(&pdftoppm_outpath);
+						debug!("{:#?}", command);
+						match config.runner().output(&mut command) {
+							Ok(output) => {
+								if !output.stderr.is_empty() {
+									debug!("{:#?}", command);
+									warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
+								}
+								if outpath.exists() {
+									let mut new_parent_files = parent_files.clone();
+									new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+									extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+								}
+							}
+							Err(e) => {
+								error!("{:#?}", command);
+								return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+							}
+						}
+					}
+				} else {
+					// pdfimages -list /home/ray/MEGA/Rays/Programming/python/file/test_text_extract/docs/sample2.pdf /tmp/extract_text_from_file/870eabfb3dc44ae185b84f6056f73397/image
+					// pdfimages -list "C:\Users\hrag\Sync\Programming\python\file\test_text_extract\docs\fiche d'evaluation du stagiaire - Loïc Vital.pdf" C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\image
+					// https://www.xpdfreader.com/pdfimages-man.html
+					let pdfimages_outpath = tempfiles_location().join(&achive_uuid_subdir).join(format!("page {} image", page_number));
+					#[cfg(target_os = "windows")]
+					{
+						let mut command = Command::new("pdfimages");
+						command
+							.arg("-f").arg(format!("{}", page_number))
+							.arg("-l").arg(format!("{}", page_number))
+							.arg("-list")
+							.arg(format!("{}", filepath.to_string_lossy().to_string()))
+							.arg(format!("{}", pdfimages_outpath.to_string_lossy().to_string()));
+						debug!("{:#?}", command);
+						match config.runner().output(&mut command) {
+							Ok(output) => {
+								if !output.stderr.is_empty() {
+									debug!("{:#?}", command);
+									warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
+								} else {
+									//println!("stdout: {}", String::from_utf8_lossy(&output.stdout));
+									let output = String::from_utf8_lossy(&output.stdout);
+									let output = output.lines();
+									for line in output {
+										if let Some((image_filename, _)) = line.split_once(": ") {
+											// println!(">>> {}", image_filename);
+											let outpath = PathBuf::from(image_filename);
+											let mut new_parent_files = parent_files.clone();
+											new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+											extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+										}
+									}
+								}
+							}
+							Err(e) => {
+								println!("{:#?}", command);
+								return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+							}
+						}
+					}
+					#[cfg(target_os = "linux")]
+					{
+						//linux, first get list of images in page, then extract
+						let mut command = Command::new("pdfimages");
+						command
+							.arg("-f").arg(format!("{}", page_number))
+							.arg("-l").arg(format!("{}", page_number))
+							.arg("-list")
+							.arg(format!("{}", filepath.to_string_lossy().to_string()));
+						debug!("{:#?}", command);
+						match config.runner().output(&mut command) {
+							Ok(output) => {
+								if !output.stderr.is_empty() {
+									debug!("{:#?}", command);
+									warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
+								} else {
+									let output = String::from_utf8_lossy(&output.stdout);
+									//println!("stdout: {}", output);
+									let num_images = pdfimages_list_count(&output);
+									// println!(">>> num_images {}", num_images);
+									if num_images > 0 {
+										//export
+										let image_filename_prefix = pdfimages_outpath.to_string_lossy().to_string();
+										let (format_args, extensions) = config.pdf_ocr_image_format.pdfimages_args();
+										let mut command = Command::new("pdfimages");
+										command
+											.arg("-f").arg(format!("{}", page_number))
+											.arg("-l").arg(format!("{}", page_number))
+											.args(format_args)
+											.arg(format!("{}", filepath.to_string_lossy().to_string()))
+											.arg(format!("{}", image_filename_prefix));
+										debug!("{:#?}", command);
+										match config.runner().output(&mut command) {
+											Ok(output) => {
+												if !output.stderr.is_empty() {
+													debug!("{:#?}", command);
+													warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
+												}
+											}
+											Err(e) => {
+												println!("{:#?}", command);
+												return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+											}
+										}
+										for iimg in 0..num_images {
+											// let image_info:Vec<&str> = image_output_lines[iimg+2].split_ascii_whitespace().collect();
+											//type image -> .ppm, type stencil -> .pbm
+											// let image_type = image_info[2];
+											// let image_color = image_info[5];
+											// let image_ext;
+											// if image_color == "index" {
+											// 	image_ext = "pbm";
+											// } else if image_color == "gray" {
+											// 	image_ext = "pbm";
+											// } else if image_type == "stencil" {
+											// 	image_ext = "pbm";
+											// } else if image_type == "image" {
+											// 	image_ext = "ppm";
+											// } else if image_type == "smask" {
+											// 	image_ext = "ppm";
+											// } else {
+											// 	return Err(format!("Unknown PDF embedded image type {}", image_type).into());
+											// }
+											// println!("image_info\n{:?}", image_info);
+											let image_filename_base = format!("{}-{:03}", image_filename_prefix, iimg);
+											let outpath = extensions.iter()
+												.map(|extension| PathBuf::from(format!("{}.{}", image_filename_base, extension)))
+												.find(|x| x.exists());
+											if let Some(outpath) = outpath {
+												let mut new_parent_files = parent_files.clone();
+												new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+												extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+											} else {
+												debug!("No PDF embedded image found: {}.{}", image_filename_base, extensions.join(" or ."));
+											}
+										}
+									}
+								}
+							}
+							Err(e) => {
+								println!("{:#?}", command);
+								return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+							}
+						}
+
+					}
+				}
+			}
+
+			if let Some(merged_pages) = merged_pages.filter(|_| page_count > 0) {
+				let mut new_parent_files = parent_files.clone();
+				new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+				push_text_subfile(tempfiles_location().join(achive_uuid_subdir).join("pages"), merged_pages, "pdftotext", depth+1, new_parent_files, list_of_files_in_archive);
+				list_of_files_in_archive[merged_pages_index..].rotate_right(1);
+				if is_any_page_garbled {
+					list_of_files_in_archive[merged_pages_index].note = Some(String::from(GARBLED_TEXT_LAYER_NOTE));
+				}
+			}
+		}
+		FileType::Djvu => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+
+			fs::create_dir_all(tempfiles_location().join(achive_uuid_subdir))?;
+
+			// get page count
+			// djvused -e n /home/ray/docs/manual.djvu
+			let mut command = Command::new("djvused");
+			command.arg("-e").arg("n").arg(filepath);
+			debug!("{:#?}", command);
+			let page_count: u32 = match config.runner().output(&mut command) {
+				Ok(output) => {
+					if !output.stderr.is_empty() {
+						debug!("{:#?}", command);
+						warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
+					}
+					String::from_utf8_lossy(&output.stdout).trim().parse().unwrap_or_default()
+				}
+				Err(e) => {
+					//djvulibre is optional, the file is listed without text
+					warn!("Failed to execute {:?}: {}", command.get_program(), e);
+					if let Some(djvu_item) = list_of_files_in_archive.last_mut() {
+						djvu_item.error = Some(format!("Failed to execute {:?}: {}", command.get_program(), e));
+					}
+					return Ok(());
+				}
+			};
+			trace!("DjVu page count {}", page_count);
+			let page_count = if context.enumerate_only { 0 } else { page_count };
+
+			for page_number in 1..=page_count {
+				//page text layer
+				// djvutxt --page=1 /home/ray/docs/manual.djvu "/tmp/extract_text_from_file/870eabfb3dc44ae185b84f6056f73397/page 1"
+				let outpath = tempfiles_location().join(achive_uuid_subdir).join(format!("page {}", page_number));
+				let mut command = Command::new("djvutxt");
+				command
+					.arg(format!("--page={}", page_number))
+					.arg(filepath)
+					.arg(&outpath);
+				debug!("{:#?}", command);
+				match config.runner().output(&mut command) {
+					Ok(output) => {
+						if !output.stderr.is_empty() {
+							debug!("{:#?}", command);
+							warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
+						}
+					}
+					Err(e) => {
+						error!("{:#?}", command);
+						return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+					}
+				}
+				let page_text = fs::read(&outpath).unwrap_or_default();
+				if !String::from_utf8_lossy(&page_text).trim().is_empty() {
+					let mut new_parent_files = parent_files.clone();
+					new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+					extract_generated_file(outpath.as_path(), "djvutxt", depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+					continue;
+				}
+
+				//no text layer, OCR on the rendered page
+				// ddjvu -format=pgm -page=1 /home/ray/docs/manual.djvu "/tmp/extract_text_from_file/870eabfb3dc44ae185b84f6056f73397/page 1 render.pgm"
+				let outpath = tempfiles_location().join(achive_uuid_subdir).join(format!("page {} render.pgm", page_number));
+				let mut command = Command::new("ddjvu");
+				command
+					.arg("-format=pgm")
+					.arg(format!("-page={}", page_number))
+					.arg(filepath)
+					.arg(&outpath);
+				debug!("{:#?}", command);
+				match config.runner().output(&mut command) {
+					Ok(output) => {
+						if !output.stderr.is_empty() {
+							debug!("{:#?}", command);
+							warn!("Error returned from {:?}: {}", command.get_program(), String::from_utf8_lossy(&output.stderr));
+						}
+						if outpath.exists() {
+							let mut new_parent_files = parent_files.clone();
+							new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+							extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+						}
+					}
+					Err(e) => {
+						error!("{:#?}", command);
+						return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+					}
+				}
+			}
+		}
+		FileType::Ppsx | FileType::Pptm | FileType::Pptx => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+			push_properties_subfile(filepath, &tempfiles_location().join(achive_uuid_subdir), depth, &parent_files, list_of_files_in_archive);
+
+			//a subfile per slide, and per slide with speaker notes
+			let mut slide_blocks: Vec<(String, String)> = Vec::new();
+			match pptx::read_slides(filepath) {
+				Ok(slides) => {
+					for (islide, slide) in slides.into_iter().enumerate() {
+						let mut text = slide.text;
+						if !slide.alt_texts.is_empty() {
+							text.push_str("Alt text:\n");
+							for alt_text in slide.alt_texts {
+								text.push_str(&alt_text);
+								text.push('\n');
+							}
+						}
+						slide_blocks.push((format!("slide {}", islide+1), text));
+						if !slide.notes.trim().is_empty() {
+							slide_blocks.push((format!("slide {} notes", islide+1), format!("Notes:\n{}", slide.notes)));
+						}
+					}
+				}
+				Err(e) => {
+					warn!("Error reading slides from {:?}: {}", filepath, e);
+				}
+			}
+			for (label, text) in slide_blocks {
+				let outpath = tempfiles_location().join(achive_uuid_subdir).join(label);
+				fs::create_dir_all(outpath.parent().unwrap())?;
+				match fs::write(&outpath, text) {
+					Ok(_) => {
+						let mut new_parent_files = parent_files.clone();
+						new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+						extract_generated_file(outpath.as_path(), "pptx xml", depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+					},
+					Err(e) => {
+						error!("Error writing to file {:?}: {}", outpath, e)
+					},
+				}
+			}
+
+			for outpath in extract_embedded_images(filepath, "ppt/media/", &tempfiles_location().join(achive_uuid_subdir), config)? {
+				let mut new_parent_files = parent_files.clone();
+				new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+				extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+			}
+		}
+		FileType::Hwpx => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+
+			//a subfile per section, in section order
+			match hwpx::read_sections(filepath) {
+				Ok(sections) => {
+					for (isection, text) in sections.into_iter().enumerate() {
+						let outpath = tempfiles_location().join(achive_uuid_subdir).join(format!("section {}", isection+1));
+						fs::create_dir_all(outpath.parent().unwrap())?;
+						match fs::write(&outpath, text) {
+							Ok(_) => {
+								let mut new_parent_files = parent_files.clone();
+								new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+								extract_generated_file(outpath.as_path(), "hwpx xml", depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+							},
+							Err(e) => {
+								error!("Error writing to file {:?}: {}", outpath, e)
+							},
+						}
+					}
+				}
+				Err(e) => {
+					warn!("Error reading sections from {:?}: {}", filepath, e);
+					if let Some(hwpx_item) = list_of_files_in_archive.last_mut() {
+						hwpx_item.error = Some(format!("Error reading HWPX sections: {}", e));
+					}
+				}
+			}
+		}
+		FileType::Xps => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+
+			//a subfile per page, in document order
+			match xps::read_pages(filepath) {
+				Ok(pages) => {
+					for (ipage, text) in pages.into_iter().enumerate() {
+						let outpath = tempfiles_location().join(achive_uuid_subdir).join(format!("page {}", ipage+1));
+						fs::create_dir_all(outpath.parent().unwrap())?;
+						match fs::write(&outpath, text) {
+							Ok(_) => {
+								let mut new_parent_files = parent_files.clone();
+								new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+								extract_generated_file(outpath.as_path(), "xps xml", depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+							},
+							Err(e) => {
+								error!("Error writing to file {:?}: {}", outpath, e)
+							},
+						}
+					}
+				}
+				Err(e) => {
+					warn!("Error reading pages from {:?}: {}", filepath, e);
+					if let Some(xps_item) = list_of_files_in_archive.last_mut() {
+						xps_item.error = Some(format!("Error reading XPS pages: {}", e));
+					}
+				}
+			}
+		}
+		FileType::Ods | FileType::Xlam | FileType::Xls | FileType::Xlsb | FileType::Xlsm | FileType::Xlsx => {
+			let workbook_item_index = list_of_files_in_archive.len();
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+			if [FileType::Xlam, FileType::Xlsb, FileType::Xlsm, FileType::Xlsx].contains(&file_type) {
+				push_properties_subfile(filepath, &tempfiles_location().join(achive_uuid_subdir), depth, &parent_files, list_of_files_in_archive);
+			}
+			//let mut workbook = open_workbook_auto(filepath)?;
+			match open_workbook_auto(filepath) {
+				Ok(mut workbook) => {
+					if let Ok(vbaop) = workbook.vba_project() {
+						if let Some(vba) = vbaop {
+							let vba_modules = vba.get_module_names();
+							trace!("vba_modules: {:#?}", vba_modules);
+							for module_name in vba_modules {
+								let module = vba.get_module(module_name).unwrap();
+								let mut module_name_filename_safe = module_name.to_string();
+								module_name_filename_safe.retain(|c| !FILENAME_ILLEGAL_CHARS.contains(&c));
+								let outpath = tempfiles_location().join(&achive_uuid_subdir).join(format!("VBA_{}", module_name_filename_safe));
+								fs::create_dir_all(outpath.parent().unwrap())?;
+								match fs::write(&outpath, module) {
+									Ok(_) => {
+										let mut new_parent_files = parent_files.clone();
+										new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+										extract_generated_file(outpath.as_path(), "calamine vba", depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+									},
+									Err(e) => {
+										error!("Error writing to file {:?}: {}", outpath, e)
+									},
+								}
+							}
+						}
+					}
+
+					//calamine skips some xlsb cell records, they are read separately and added to the sheet
+					let mut skipped_cells = HashMap::new();
+					if file_type == FileType::Xlsb {
+						match xlsb::read_skipped_cells(filepath) {
+							Ok(cells) => skipped_cells = cells,
+							Err(e) => warn!("Error reading xlsb cells from {:?}: {}", filepath, e),
+						}
+					}
+
+					let sheets_metadata = workbook.sheets_metadata().to_owned();
+					for sheet in sheets_metadata {
+						// trace!("sheet_metadata: {:?}", sheet);
+						if sheet.typ == calamine::SheetType::WorkSheet {
+							trace!("Reading sheet: {}", sheet.name);
+							let mut sheet_name_filename_safe = sheet.name.clone();
+							sheet_name_filename_safe.retain(|c| !FILENAME_ILLEGAL_CHARS.contains(&c));
+							let outpath = tempfiles_location().join(&achive_uuid_subdir).join(sheet_name_filename_safe);
+							//an xlsx sheet is read a cell at a time up to the caps, not as a whole range
+							if let Sheets::Xlsx(xlsx_workbook) = &mut workbook {
+								match read_xlsx_sheet_rows(xlsx_workbook, &sheet.name, config) {
+									Ok(rows) => extract_sheet(&outpath, rows, "calamine", filepath, depth, &parent_files, list_of_files_in_archive, context, config)?,
+									Err(e) => warn!("Error reading sheet {} of {:?}: {}", sheet.name, filepath, e),
+								}
+							} else if let Ok(range) = workbook.worksheet_range(&sheet.name) {
+								let extra_cells = skipped_cells.remove(&sheet.name).unwrap_or_default();
+								//the sheet's cells, from the first used row and column of either the range or the extra cells
+								let (mut first_row, mut first_col) = range.start().unwrap_or((u32::MAX, u32::MAX));
+								for extra_cell in &extra_cells {
+									first_row = first_row.min(extra_cell.row);
+									first_col = first_col.min(extra_cell.col);
+								}
+								let mut last_row = range.end().map_or(0, |(row, _)| row);
+								//the extra cells of each row, as (column, text)
+								let mut extra_rows: BTreeMap<u32, Vec<(u32, String)>> = BTreeMap::new();
+								for extra_cell in extra_cells {
+									last_row = last_row.max(extra_cell.row);
+									extra_rows.entry(extra_cell.row).or_default().push((extra_cell.col, extra_cell.text));
+								}
+								//a row at a time, the cells of the range then the extra cells where the range has none
+								let (range_row, range_col) = range.start().unwrap_or_default();
+								let range_rows_end = range.end().map(|(row, _)| row);
+								let mut range_rows = range.rows();
+								let rows = (first_row..=last_row).map(|row| {
+									let mut cells: Vec<String> = Vec::new();
+									if range_rows_end.is_some_and(|x| row >= range_row && row <= x)
+										&& let Some(range_cells) = range_rows.next()
+									{
+										cells.resize((range_col - first_col) as usize, String::new());
+										cells.extend(range_cells.iter().map(cell_text));
+									}
+									for (col, text) in extra_rows.remove(&row).unwrap_or_default() {
+										let col = (col - first_col) as usize;
+										if cells.len() <= col {
+											cells.resize(col + 1, String::new());
+										}
+										if cells[col].is_empty() {
+											cells[col] = text;
+										}
+									}
+									cells
+								});
+								extract_sheet(&outpath, rows, "calamine", filepath, depth, &parent_files, list_of_files_in_archive, context, config)?;
+							}
+						} else {
+							trace!("Skipping sheet {} of type {:?}", sheet.name, sheet.typ);
+						}
+					}
+
+					//labeled blocks for defined names and cell comments. Square brackets are not allowed in sheet names, so these can't clash with a sheet's file.
+					let mut labeled_blocks: Vec<(&str, &str, String)> = Vec::new();
+					let defined_names = workbook.defined_names();
+					if !defined_names.is_empty() {
+						let mut text = format!("Defined Names:{}", config.row_separator);
+						for (name, formula) in defined_names {
+							text.push_str(&format!("{}{}{}{}", name, config.cell_separator, formula, config.row_separator));
+						}
+						labeled_blocks.push(("[Defined Names]", "calamine", text));
+					}
+					let comments = match file_type {
+						FileType::Xlam | FileType::Xlsm | FileType::Xlsx => Some((xlsx::read_comments(filepath), "xlsx xml")),
+						FileType::Xls => Some((xls::read_comments(filepath), "xls biff")),
+						_ => None,
+					};
+					match comments {
+						Some((Ok(comments), method)) if !comments.is_empty() => {
+							let mut text = format!("Comments:{}", config.row_separator);
+							for (location, author, comment) in comments {
+								text.push_str(&format!("{}{}{}{}{}{}", location, config.cell_separator, author, config.cell_separator, comment, config.row_separator));
+							}
+							labeled_blocks.push(("[Comments]", method, text));
+						}
+						Some((Err(e), _)) => {
+							warn!("Error reading comments from {:?}: {}", filepath, e);
+						}
+						_ => (),
+					}
+					if [FileType::Xlam, FileType::Xlsm, FileType::Xlsx].contains(&file_type) {
+						//text outside the cell grid: chart titles and labels, and the messages of data validations
+						for (label, heading, rows) in [
+							("[Charts]", "Charts:", xlsx::read_charts(filepath)),
+							("[Data Validation]", "Data Validation:", xlsx::read_data_validations(filepath)),
+						] {
+							match rows {
+								Ok(rows) => {
+									if !rows.is_empty() {
+										let mut text = format!("{}{}", heading, config.row_separator);
+										for (location, kind, row_text) in rows {
+											text.push_str(&format!("{}{}{}{}{}{}", location, config.cell_separator, kind, config.cell_separator, row_text, config.row_separator));
+										}
+										labeled_blocks.push((label, "xlsx xml", text));
+									}
+								}
+								Err(e) => {
+									warn!("Error reading {} from {:?}: {}", label, filepath, e);
+								}
+							}
+						}
+					}
+					for (label, method, text) in labeled_blocks {
+						let outpath = tempfiles_location().join(&achive_uuid_subdir).join(label);
+						fs::create_dir_all(outpath.parent().unwrap())?;
+						match fs::write(&outpath, text) {
+							Ok(_) => {
+								let mut new_parent_files = parent_files.clone();
+								new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+								extract_generated_file(outpath.as_path(), method, depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+							},
+							Err(e) => {
+								error!("Error writing to file {:?}: {}", outpath, e)
+							},
+						}
+					}
+
+				}
+				Err(err) => {
+					match err {
+						calamine::Error::Xls(calamine::XlsError::Cfb(ref msg)) => {
+							warn!("Xls Cfb error: {}, in file {:?}", msg, filepath);
+							list_of_files_in_archive[workbook_item_index].error = Some(format!("Error opening workbook: {}", err));
+						}
+						calamine::Error::Ods(calamine::OdsError::Password)
+						| calamine::Error::Xlsb(calamine::XlsbError::Password)
+						| calamine::Error::Xlsx(calamine::XlsxError::Password) => {
+							warn!("Cannot extract text from password protected file: {:?}", filepath);
+							list_of_files_in_archive[workbook_item_index].note = Some(String::from("encrypted, not extracted"));
+						}
+						_ => {
+							warn!("Error opening workbook {:?}: {}", filepath, err);
+							list_of_files_in_archive[workbook_item_index].error = Some(format!("Error opening workbook: {}", err));
+							//a damaged OOXML workbook may still have readable cells, they are read from the sheet parts as a best effort
+							if [FileType::Xlam, FileType::Xlsm, FileType::Xlsx].contains(&file_type) {
+								match xlsx::read_raw_cells(filepath, config.max_sheet_rows, config.max_sheet_cells) {
+									Ok(sheets) => {
+										for (sheet_name, rows) in sheets {
+											let mut sheet_name_filename_safe = sheet_name;
+											sheet_name_filename_safe.retain(|c| !FILENAME_ILLEGAL_CHARS.contains(&c));
+											let outpath = tempfiles_location().join(achive_uuid_subdir).join(sheet_name_filename_safe);
+											extract_sheet(&outpath, rows.into_iter(), "xlsx xml", filepath, depth, &parent_files, list_of_files_in_archive, context, config)?;
+										}
+									}
+									Err(e) => {
+										warn!("Error reading cells from {:?}: {}", filepath, e);
+									}
+								}
+							}
+						}
+					}
+				}
+			}
+		}
+		FileType::Gzip => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+
+			//the decompressed file is detected again, so a .tgz or .tar.gz goes on to the Tar branch
+			let mut decoder = MultiGzDecoder::new(BufReader::new(File::open(filepath)?));
+			//0 means the gzip has no time
+			let modified = decoder.header().map(|header| header.mtime()).filter(|x| *x > 0).map(i64::from);
+			let inner_name = decoder.header()
+				.and_then(|header| header.filename())
+				.map(|name| String::from_utf8_lossy(name).to_string())
+				.and_then(|name| Path::new(&name).file_name().map(|x| x.to_string_lossy().to_string()))
+				.unwrap_or_else(|| {
+					let stem = filepath.file_stem().unwrap_or_default().to_string_lossy().to_string();
+					if filepath.extension().is_some_and(|x| x.eq_ignore_ascii_case("tgz")) {
+						format!("{}.tar", stem)
+					} else {
+						stem
+					}
+				});
+			let outpath = tempfiles_location().join(achive_uuid_subdir).join(inner_name);
+			fs::create_dir_all(outpath.parent().unwrap())?;
+			let mut outfile = File::create(&outpath)?;
+			match io::copy(&mut decoder, &mut outfile) {
+				Ok(_) => {
+					let mut new_parent_files = parent_files.clone();
+					new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+					let entry_item_index = list_of_files_in_archive.len();
+					extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+					set_entry_modified(list_of_files_in_archive, entry_item_index, modified);
+				}
+				Err(e) => {
+					warn!("Error decompressing gzip {:?}: {}", filepath, e);
+					list_of_files_in_archive.last_mut().unwrap().error = Some(e.to_string());
+					drop(outfile);
+					_ = fs::remove_file(&outpath);
+				}
+			}
+		}
+		FileType::Tar => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+
+			let outdir = tempfiles_location().join(achive_uuid_subdir);
+			fs::create_dir_all(&outdir)?;
+			let mut archive = tar::Archive::new(BufReader::new(File::open(filepath)?));
+			let mut extracted: Vec<(PathBuf, Option<i64>)> = Vec::new();
+			let mut errors: Vec<String> = Vec::new();
+			match archive.entries() {
+				Ok(entries) => {
+					for entry in entries {
+						//a damaged archive is listed with its error, along with the files before the damage
+						let mut entry = match entry {
+							Ok(entry) => entry,
+							Err(e) => {
+								errors.push(e.to_string());
+								break;
+							}
+						};
+						//only regular files, links could point outside the temp dir
+						if entry.header().entry_type() != tar::EntryType::Regular {
+							continue;
+						}
+						let entry_path = match entry.path() {
+							Ok(entry_path) => entry_path.to_path_buf(),
+							Err(e) => {
+								errors.push(e.to_string());
+								continue;
+							}
+						};
+						if !config.is_archive_member_wanted(&entry_path.to_string_lossy()) {
+							continue;
+						}
+						let modified = entry.header().mtime().ok().map(|x| x as i64);
+						// unpack_in skips paths that would land outside outdir
+						match entry.unpack_in(&outdir) {
+							Ok(true) => extracted.push((entry_path, modified)),
+							Ok(false) => warn!("Skipping tar entry {:?} outside of the archive in {:?}", entry_path, filepath),
+							Err(e) => errors.push(e.to_string()),
+						}
+					}
+				}
+				Err(e) => errors.push(e.to_string()),
+			}
+			if !errors.is_empty() {
+				warn!("Error extracting tar {:?}: {}", filepath, errors.join("; "));
+				list_of_files_in_archive.last_mut().unwrap().error = Some(errors.join("; "));
+			}
+
+			for (entry_path, modified) in extracted {
+				let mut new_parent_files = parent_files.clone();
+				new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+				if config.archive_paths_in_lineage
+					&& let Some(internal_dir) = entry_path.parent()
+				{
+					new_parent_files.extend(internal_dir.components().map(|c| c.as_os_str().to_string_lossy().into_owned()));
+				}
+				let entry_item_index = list_of_files_in_archive.len();
+				extract_archive(outdir.join(&entry_path).as_path(), depth+1, new_parent_files, list_of_files_in_archive, context, config)?;
+				set_entry_modified(list_of_files_in_archive, entry_item_index, modified);
+			}
+		}
+		FileType::Zip => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+			
+			let archive_item_index = list_of_files_in_archive.len() - 1;
+			let outdir = tempfiles_location().join(&achive_uuid_subdir);
+			let mut errors: Vec<String> = Vec::new();
+			//archive comment, then (entry name, comment)
+			let mut archive_comment = String::new();
+			let mut entry_comments: Vec<(String, String)> = Vec::new();
+			let file = File::open(filepath)?;
+			match ZipArchive::new(file) {
+				Ok(mut archive) => {
+					debug!("Total entries: {}", archive.len());
+					archive_comment = String::from_utf8_lossy(archive.comment()).trim().to_string();
+					for i in 0..archive.len() {
+						//read without decrypting, an error is reported by by_index below
+						let (is_encrypted, is_wanted) = match archive.by_index_raw(i) {
+							Ok(zipfile) => {
+								if !zipfile.comment().trim().is_empty() {
+									entry_comments.push((zip_entry_name(&zipfile), zipfile.comment().trim().to_string()));
+								}
+								(zipfile.encrypted(), zipfile.is_dir() || config.is_archive_member_wanted(&zip_entry_name(&zipfile)))
+							}
+							Err(_) => (false, true),
+						};
+						if !is_wanted {
+							continue;
+						}
+						if is_encrypted {
+							let result = match find_zip_password(&mut archive, i, &config.archive_passwords) {
+								Some(password) => archive.by_index_decrypt(i, password.as_bytes())
+									.map_err(|e| e.into())
+									.and_then(|mut zipfile| extract_zip_entry(&mut zipfile, filepath, &outdir, depth, &parent_files, list_of_files_in_archive, context, config)),
+								None => archive.by_index_raw(i)
+									.map_err(|e| e.into())
+									.and_then(|zipfile| push_encrypted_zip_entry(&zipfile, filepath, &outdir, depth, &parent_files, list_of_files_in_archive, config)),
+							};
+							if let Err(e) = result {
+								warn!("Error extracting encrypted entry {} of zip {:?}: {}", i, filepath, e);
+								errors.push(e.to_string());
+							}
+							continue;
+						}
+						match archive.by_index(i) {
+							Ok(mut zipfile) => {
+								if let Err(e) = extract_zip_entry(&mut zipfile, filepath, &outdir, depth, &parent_files, list_of_files_in_archive, context, config) {
+									warn!("Error extracting {} from zip {:?}: {}", zipfile.name(), filepath, e);
+									errors.push(format!("{}: {}", zipfile.name(), e));
+								}
+							}
+							Err(ZipError::UnsupportedArchive(errtxt)) => {
+								info!("Zip file not supported: ({}) {:?}", errtxt, filepath);
+								break;
+							}
+							Err(err) => {
+								warn!("Error reading entry {} of zip {:?}: {}", i, filepath, err);
+								errors.push(err.to_string());
+							}
+						}
+					}
+				}
+				Err(err) => {
+					//no readable central directory, e.g. a truncated archive, so read the entries from the start of the file instead
+					warn!("Error opening zip {:?}: {}, reading its entries sequentially", filepath, err);
+					errors.push(err.to_string());
+					let mut reader = BufReader::new(File::open(filepath)?);
+					loop {
+						match zip::read::read_zipfile_from_stream(&mut reader) {
+							Ok(Some(mut zipfile)) => {
+								if !zipfile.is_dir() && !config.is_archive_member_wanted(&zip_entry_name(&zipfile)) {
+									continue;
+								}
+								//entries read from the stream can't be decrypted, the rest of the entry is skipped when it is dropped
+								if zipfile.encrypted() {
+									push_encrypted_zip_entry(&zipfile, filepath, &outdir, depth, &parent_files, list_of_files_in_archive, config)?;
+									continue;
+								}
+								if let Err(e) = extract_zip_entry(&mut zipfile, filepath, &outdir, depth, &parent_files, list_of_files_in_archive, context, config) {
+									warn!("Error extracting {} from zip {:?}: {}", zipfile.name(), filepath, e);
+									errors.push(format!("{}: {}", zipfile.name(), e));
+									break;
+								}
+							}
+							Ok(None) => break,
+							Err(err) => {
+								warn!("Error reading zip {:?} sequentially: {}", filepath, err);
+								errors.push(err.to_string());
+								break;
+							}
+						}
+					}
+				}
+			}
+			if !errors.is_empty() {
+				list_of_files_in_archive[archive_item_index].error = Some(errors.join("; "));
+			}
+			//the comments are the text of the archive's own item, so they can't clash with an entry's name
+			if config.archive_comments && (!archive_comment.is_empty() || !entry_comments.is_empty()) {
+				let mut text = String::new();
+				if !archive_comment.is_empty() {
+					text.push_str(&format!("Archive Comment:{}{}{}", config.row_separator, archive_comment, config.row_separator));
+				}
+				if !entry_comments.is_empty() {
+					text.push_str(&format!("Entry Comments:{}", config.row_separator));
+					for (name, comment) in entry_comments {
+						text.push_str(&format!("{}{}{}{}", name, config.cell_separator, comment, config.row_separator));
+					}
+				}
+				let archive_item = &mut list_of_files_in_archive[archive_item_index];
+				archive_item.ok_to_extract_text = true;
+				archive_item.method = Some(String::from("zip comment"));
+				archive_item.comments = Some(text);
+			}
+		}
+		FileType::Key | FileType::Numbers | FileType::Pages => {
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: false,
+				..Default::default()
+			});
+
+			let archive_item_index = list_of_files_in_archive.len() - 1;
+			let outdir = tempfiles_location().join(achive_uuid_subdir);
+			let mut archive = ZipArchive::new(File::open(filepath)?)?;
+			let preview_pdfs: Vec<&str> = IWORK_PREVIEW_PDFS.into_iter().filter(|x| archive.index_for_name(x).is_some()).collect();
+			let previews = if preview_pdfs.is_empty() {
+				IWORK_PREVIEW_IMAGES.into_iter().filter(|x| archive.index_for_name(x).is_some()).collect()
+			} else {
+				preview_pdfs
+			};
+			if previews.is_empty() {
+				list_of_files_in_archive[archive_item_index].note = Some(String::from("no preview, not extracted"));
+			}
+			for preview in previews {
+				let mut zipfile = archive.by_name(preview)?;
+				extract_zip_entry(&mut zipfile, filepath, &outdir, depth, &parent_files, list_of_files_in_archive, context, config)?;
+			}
+		}
+		_ => {
+			//binary data with no extractor is listed without text, rather than as fragments of ASCII, unless strings-style extraction is on
+			let is_binary = config.binary_strings_min_len.is_none()
+				&& config.extractors.get(&file_type).is_none()
+				&& is_binary_file(filepath);
+			list_of_files_in_archive.push(SubFileItem {
+				filepath: filepath.to_path_buf(),
+				depth,
+				parent_files: parent_files.clone(),
+				ok_to_extract_text: !is_binary,
+				note: is_binary.then(|| String::from("binary, not extracted")),
+				..Default::default()
+			});
+			
+		}
+	}
+
+
+	Ok(())
+}
+
+/// Finds the first occurrence of any of the terms in a text, ignoring the case of ASCII letters.
+///
+/// # Returns
+///
+/// * The term found and the text around it, up to SEARCH_CONTEXT_LEN bytes either side. None if no term is found.
+fn find_search_term(text: &str, search_terms: &[String]) -> Option<(String, String)> {
+	//ASCII case folding keeps the byte offsets of the text
+	let lowercase_text = text.to_ascii_lowercase();
+	let (position, term) = search_terms.iter()
+		.filter(|term| !term.is_empty())
+		.filter_map(|term| lowercase_text.find(&term.to_ascii_lowercase()).map(|position| (position, term)))
+		.min_by_key(|(position, _)| *position)?;
+	let mut start = position.saturating_sub(SEARCH_CONTEXT_LEN);
+	while !text.is_char_boundary(start) {
+		start -= 1;
+	}
+	let mut end = (position + term.len() + SEARCH_CONTEXT_LEN).min(text.len());
+	while !text.is_char_boundary(end) {
+		end += 1;
+	}
+	Some((term.clone(), text[start..end].to_string()))
+}
+
+/// Whether a sentence found by the Unicode rules ends with an abbreviation or an initial, e.g. `Dr.` or `J.`, rather than the end of a sentence.
+fn ends_with_abbreviation(sentence: &str) -> bool {
+	let Some(last_word) = sentence.split_whitespace().last() else {
+		return false;
+	};
+	let mut chars = last_word.chars();
+	let is_initial = matches!((chars.next(), chars.next(), chars.next()), (Some(letter), Some('.'), None) if letter.is_uppercase());
+	is_initial || SENTENCE_ABBREVIATIONS.iter().any(|x| last_word.eq_ignore_ascii_case(x))
+}
+
+/// Splits text into segments, see ExtractConfig::segmentation. Segments are trimmed, and empty ones left out.
+pub fn segment_text(text: &str, segmentation: Segmentation) -> Vec<String> {
+	match segmentation {
+		Segmentation::None => vec![text.to_string()],
+		Segmentation::Sentence => {
+			//the Unicode rules break at every line break, but extracted text is often wrapped, so only blank lines end a paragraph
+			let unwrapped = text.replace("\r\n", "\n").split("\n\n").map(|x| x.replace('\n', " ")).collect::<Vec<String>>().join("\n\n");
+			//they also break after any full stop followed by a capital, so sentences ending in an abbreviation are joined to the next
+			let mut sentences: Vec<String> = Vec::new();
+			let mut current = String::new();
+			for sentence in unwrapped.split_sentence_bounds() {
+				current.push_str(sentence);
+				if !ends_with_abbreviation(&current) {
+					sentences.push(std::mem::take(&mut current));
+				}
+			}
+			sentences.push(current);
+			sentences.into_iter()
+				.map(|x| x.split_whitespace().collect::<Vec<&str>>().join(" "))
+				.filter(|x| !x.is_empty())
+				.collect()
+		}
+		Segmentation::Line => text.lines().map(|x| x.trim()).filter(|x| !x.is_empty()).map(|x| x.to_string()).collect(),
+		Segmentation::Token => text.unicode_words().map(|x| x.to_string()).collect(),
+	}
+}
+
+/// Extensions of source code files, which are read as text.
+const SOURCE_CODE_EXTENSIONS: [&str; 24] = [
+	"bas", "bat", "c", "cc", "cpp", "cs", "go", "h", "hpp", "java", "js", "kt", "php", "pl", "ps1", "py", "r", "rb", "rs", "sh", "sql", "swift", "ts", "vb",
+];
+
+/// Words that start a line of code in many languages.
+const CODE_LINE_STARTS: [&str; 16] = [
+	"#include", "class ", "const ", "def ", "else", "fn ", "for ", "function ", "if ", "import ", "let ", "private ", "public ", "return", "var ", "while ",
+];
+
+/// Checks if a text looks like source code: at least 3 lines, and a third of its lines, end with `;`, `{` or `}`,
+/// or start with a keyword such as `def` or `return`.
+fn looks_like_source_code(text: &str) -> bool {
+	let lines: Vec<&str> = text.lines().map(|x| x.trim()).filter(|x| !x.is_empty()).collect();
+	let code_lines = lines.iter()
+		.filter(|x| x.ends_with([';', '{', '}']) || CODE_LINE_STARTS.iter().any(|start| x.starts_with(start)))
+		.count();
+	code_lines >= 3 && code_lines * 3 >= lines.len()
+}
+
+/// The content class of a file, see FileListItem::content_class, from its detected type, how its text was obtained, and the text.
+/// Text generated from a file, such as the body of an email or the sheets of a workbook, has the class of that file, `parent_class`,
+/// unless it came from OCR.
+fn content_class(file_type: &FileType, method: Option<&str>, text: &str, is_generated: bool, parent_class: Option<&'static str>) -> Option<&'static str> {
+	if method == Some("ocr") && !text.trim().is_empty() {
+		return Some("scanned image");
+	}
+	if is_generated && parent_class.is_some() {
+		return parent_class;
+	}
+	match file_type {
+		FileType::Eml | FileType::Msg => Some("email"),
+		FileType::Numbers | FileType::Ods | FileType::Xlam | FileType::Xls | FileType::Xlsb | FileType::Xlsm | FileType::Xlsx => Some("spreadsheet"),
+		FileType::Other(extension) if extension == "csv" || extension == "tsv" => Some("spreadsheet"),
+		FileType::Key | FileType::Ppsx | FileType::Pptm | FileType::Pptx => Some("presentation"),
+		FileType::Doc | FileType::Docm | FileType::Docx | FileType::Hwp | FileType::Hwpx | FileType::Markdown | FileType::Mht | FileType::Odt
+			| FileType::One | FileType::Pages | FileType::Pdf | FileType::Rtf | FileType::Xps => Some("document"),
+		FileType::Djvu => Some("scanned image"),
+		FileType::Image(_) => Some("image"),
+		FileType::Dxf => Some("drawing"),
+		FileType::Cfb | FileType::Gzip | FileType::SevenZip | FileType::Tar | FileType::Zip => Some("archive"),
+		FileType::Bin | FileType::Encrypted => None,
+		FileType::Other(extension) if SOURCE_CODE_EXTENSIONS.contains(&extension.as_str()) => Some("source code"),
+		FileType::Text | FileType::Other(_) if looks_like_source_code(text) => Some("source code"),
+		FileType::Text | FileType::Other(_) if !text.trim().is_empty() => Some("text"),
+		FileType::Text | FileType::Other(_) => None,
+	}
+}
+
+/// The page count of a PDF from pdfinfo, None if pdfinfo fails or does not give one, e.g. for a PDF that needs a password.
+fn pdf_page_count(filepath: &Path, runner: &dyn CommandRunner) -> Option<u32> {
+	let output = runner.output(Command::new("pdfinfo").arg(filepath)).ok()?;
+	String::from_utf8_lossy(&output.stdout)
+		.lines()
+		.find_map(|line| line.strip_prefix("Pages:"))
+		.and_then(|pages| pages.trim().parse().ok())
+}
+
+/// The number of images in a PDF from `pdfimages -list`, None if pdfimages fails.
+fn pdf_image_count(filepath: &Path, runner: &dyn CommandRunner) -> Option<usize> {
+	let output = runner.output(Command::new("pdfimages").arg("-list").arg(filepath)).ok()?;
+	Some(pdfimages_list_count(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The page count of a DjVu file from djvused, None if djvused fails or does not give one.
+fn djvu_page_count(filepath: &Path, runner: &dyn CommandRunner) -> Option<u32> {
+	let output = runner.output(Command::new("djvused").arg("-e").arg("n").arg(filepath)).ok()?;
+	String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Checks if a PDF text layer looks like garbage, as pdftotext writes for a font without a ToUnicode map: glyph codes read as
+/// control or private use characters, symbols rather than letters and digits, or letters that do not make words. Short texts are never taken as garbled.
+/// Runs of a repeated symbol, such as the dot leaders of a table of contents or the rules of a table, are not counted.
+fn is_garbled_text(text: &str) -> bool {
+	let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+	let is_symbol = |c: char| !c.is_alphanumeric() && !c.is_control() && !('\u{E000}'..='\u{F8FF}').contains(&c) && c != '\u{FFFD}';
+	let chars: Vec<char> = chars.chunk_by(|a, b| a == b)
+		.filter(|run| run.len() < 3 || !is_symbol(run[0]))
+		.flatten()
+		.copied()
+		.collect();
+	if chars.len() < GARBLED_TEXT_MIN_LEN {
+		return false;
+	}
+	let unmapped = chars.iter().filter(|c| c.is_control() || ('\u{E000}'..='\u{F8FF}').contains(*c) || **c == '\u{FFFD}').count();
+	let alphanumeric = chars.iter().filter(|c| c.is_alphanumeric()).count();
+	if unmapped * 10 > chars.len() || alphanumeric * 2 < chars.len() {
+		return true;
+	}
+	//nearly every word of Latin letters has a vowel, few made of shifted glyph codes do
+	let words: Vec<&str> = text
+		.split(|c: char| !c.is_alphabetic())
+		.filter(|x| x.len() >= 3 && x.is_ascii())
+		.collect();
+	if words.len() >= 5 {
+		let without_vowel = words.iter().filter(|x| !x.chars().any(|c| "aeiouyAEIOUY".contains(c))).count();
+		return without_vowel * 2 > words.len();
+	}
+	false
+}
+
+/// Counts the images in the output of `pdfimages -list`, the rows that start with a page number. The header lines do not.
+fn pdfimages_list_count(output: &str) -> usize {
+	output
+		.lines()
+		.filter(|line| line.split_whitespace().next().is_some_and(|x| x.parse::<u32>().is_ok()))
+		.count()
+}
+
+/// Reads the outline (bookmarks) of a PDF as a table of contents.
+///
+/// # Returns
+///
+/// * One title per line, indented with a tab per level and followed by a tab and its page number. Empty if the PDF has no outline.
+fn pdf_outline(document: &lopdf::Document) -> Result<String, Box<dyn Error>> {
+	let toc = match document.get_toc() {
+		Ok(toc) => toc,
+		Err(lopdf::Error::NoOutline) | Err(lopdf::Error::DictKey(_)) => return Ok(String::new()),
+		Err(e) => return Err(Box::new(e)),
+	};
+	for toc_error in toc.errors {
+		debug!("PDF outline error: {}", toc_error);
+	}
+	let mut text = String::new();
+	for entry in toc.toc {
+		text.push_str(&format!("{}{}\t{}\n", "\t".repeat(entry.level.saturating_sub(1)), entry.title, entry.page));
+	}
+	Ok(text)
+}
+
+/// Checks if a PDF is a portfolio, a container of embedded files shown as a collection, by the `/Collection` of its catalog.
+fn is_pdf_portfolio(document: &lopdf::Document) -> bool {
+	document.catalog().is_ok_and(|x| x.has(b"Collection"))
+}
+
+/// The decoded content of a PDF stream, None if it is larger than `max_size` bytes. A Flate stream, as embedded files usually are,
+/// is inflated only up to the limit, so a small stream that inflates to gigabytes is never held in memory.
+/// A stream that can't be decoded is its content as it is.
+fn pdf_stream_content(stream: &lopdf::Stream, max_size: u64) -> Option<Vec<u8>> {
+	if stream.content.len() as u64 > max_size {
+		return None;
+	}
+	let contents = match stream.filters() {
+		Ok(filters) if filters == [b"FlateDecode"] && stream.dict.get(b"DecodeParms").is_err() => {
+			let mut contents = Vec::new();
+			match flate2::read::ZlibDecoder::new(stream.content.as_slice()).take(max_size + 1).read_to_end(&mut contents) {
+				Ok(_) => contents,
+				Err(_) => stream.content.clone(),
+			}
+		}
+		Ok(filters) if !filters.is_empty() => stream.decompressed_content().unwrap_or_else(|_| stream.content.clone()),
+		_ => stream.content.clone(),
+	};
+	(contents.len() as u64 <= max_size).then_some(contents)
+}
+
+/// Reads the files embedded in a PDF, from the EmbeddedFiles name tree of its catalog.
+///
+/// # Returns
+///
+/// * The file name and contents of each file, in name tree order, None for a file larger than `max_size` bytes.
+///   Files without a name get a random one.
+fn pdf_embedded_files(document: &lopdf::Document, max_size: u64) -> Vec<(String, Option<Vec<u8>>)> {
+	let mut files = Vec::new();
+	let Some(root) = document.catalog().ok()
+		.and_then(|x| x.get_deref(b"Names", document).ok())
+		.and_then(|x| x.as_dict().ok())
+		.and_then(|x| x.get_deref(b"EmbeddedFiles", document).ok())
+		.and_then(|x| x.as_dict().ok())
+	else {
+		return files;
+	};
+	//the nodes of the name tree still to read, the leaves hold pairs of a name and a file specification
+	let mut nodes = vec![root];
+	let mut seen_kids = HashSet::new();
+	while let Some(node) = nodes.pop() {
+		if let Ok(kids) = node.get_deref(b"Kids", document).and_then(|x| x.as_array()) {
+			for kid in kids.iter().rev() {
+				//a kid referenced twice would make a loop
+				if let Ok(kid_id) = kid.as_reference()
+					&& !seen_kids.insert(kid_id)
+				{
+					continue;
+				}
+				if let Ok((_, lopdf::Object::Dictionary(kid))) = document.dereference(kid) {
+					nodes.push(kid);
+				}
+			}
+		}
+		let Ok(names) = node.get_deref(b"Names", document).and_then(|x| x.as_array()) else {
+			continue;
+		};
+		for pair in names.chunks(2) {
+			let [tree_name, file_spec] = pair else {
+				continue;
+			};
+			let Ok((_, lopdf::Object::Dictionary(file_spec))) = document.dereference(file_spec) else {
+				continue;
+			};
+			let Some(stream) = file_spec.get_deref(b"EF", document).ok()
+				.and_then(|x| x.as_dict().ok())
+				.and_then(|x| x.get_deref(b"F", document).ok())
+				.and_then(|x| x.as_stream().ok())
+			else {
+				continue;
+			};
+			//the Unicode file name, else the file name, else the name in the tree
+			let name = [file_spec.get_deref(b"UF", document), file_spec.get_deref(b"F", document), Ok(tree_name)]
+				.into_iter()
+				.filter_map(|x| x.ok().and_then(|x| lopdf::decode_text_string(x).ok()))
+				.map(|x| x.rsplit(['/', '\\']).next().unwrap_or_default().trim().to_string())
+				.find(|x| !x.is_empty())
+				.unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+			files.push((name, pdf_stream_content(stream, max_size)));
+		}
+	}
+	files
+}
+
+/// Finds the actions of a PDF that run code: JavaScript, e.g. an OpenAction script that runs when the PDF is opened, a document level
+/// script or a form field calculation, and Launch actions that start a program or open a file. Every object is checked, so actions
+/// are found wherever they are attached.
+///
+/// # Returns
+///
+/// * The kind of each action, `JavaScript` or `Launch`, and its script or the program it launches with its parameters, in object order
+fn pdf_scripts(document: &lopdf::Document) -> Vec<(&'static str, String)> {
+	let text_of = |object: &lopdf::Object| match document.dereference(object) {
+		Ok((_, lopdf::Object::Stream(stream))) => String::from_utf8_lossy(&stream.decompressed_content().unwrap_or_else(|_| stream.content.clone())).to_string(),
+		Ok((_, object)) => lopdf::decode_text_string(object).unwrap_or_default(),
+		Err(_) => String::new(),
+	};
+	//a file specification is a string, or a dictionary with the file name in its F or UF
+	let file_of = |object: &lopdf::Object| match document.dereference(object) {
+		Ok((_, lopdf::Object::Dictionary(file_spec))) => [b"UF".as_slice(), b"F"].into_iter()
+			.filter_map(|key| file_spec.get(key).ok().map(&text_of))
+			.find(|x| !x.is_empty())
+			.unwrap_or_default(),
+		Ok(_) => text_of(object),
+		Err(_) => String::new(),
+	};
+	let mut scripts = Vec::new();
+	for object in document.objects.values() {
+		let action = match object {
+			lopdf::Object::Dictionary(dict) => dict,
+			lopdf::Object::Stream(stream) => &stream.dict,
+			_ => continue,
+		};
+		match action.get(b"S").and_then(|x| x.as_name()) {
+			Ok(b"JavaScript") => {
+				let script = action.get(b"JS").map(&text_of).unwrap_or_default();
+				scripts.push(("JavaScript", script.trim().to_string()));
+			}
+			Ok(b"Launch") => {
+				//the Windows launch parameters, if any, name the program and its arguments
+				let windows = action.get_deref(b"Win", document).and_then(|x| x.as_dict()).ok();
+				let program = windows.and_then(|x| x.get(b"F").ok()).or_else(|| action.get(b"F").ok()).map(&file_of).unwrap_or_default();
+				let parameters = windows.and_then(|x| x.get(b"P").ok()).map(&text_of).unwrap_or_default();
+				scripts.push(("Launch", format!("{} {}", program, parameters).trim().to_string()));
+			}
+			_ => (),
+		}
+	}
+	scripts
+}
+
+/// Rejoins words that were split across lines with a hyphen, e.g. `inter-\nnational trade` to `international\ntrade`.
+/// Only a hyphen between letters with a lowercase letter starting the next line is removed, so `North-\nWest` and `COVID-\n19` are kept.
+fn dehyphenate(text: &str) -> String {
+	let mut lines: Vec<String> = text.split('\n').map(|x| x.to_string()).collect();
+	for i in 0..lines.len().saturating_sub(1) {
+		let line = lines[i].trim_end_matches('\r');
+		let Some(head) = line.strip_suffix('-') else {
+			continue;
+		};
+		let next_line = lines[i + 1].trim_start();
+		if !head.chars().last().is_some_and(|c| c.is_alphabetic())
+			|| !next_line.chars().next().is_some_and(|c| c.is_lowercase())
+		{
+			continue;
+		}
+		//the rest of the word moves up, the rest of the next line stays where it is
+		let (word_end, rest) = next_line.split_once(char::is_whitespace).unwrap_or((next_line, ""));
+		let joined = format!("{}{}{}", head, word_end, &lines[i][line.len()..]);
+		lines[i + 1] = rest.trim_start().to_string();
+		lines[i] = joined;
+	}
+	lines.join("\n")
+}
+
+/// Puts text that is mostly right-to-left (Hebrew, Arabic...) and stored in visual order, as some PDFs and OCR produce it,
+/// into logical (reading) order, by reordering each line as a right-to-left paragraph. Other text is returned as it is.
+fn to_logical_order(text: &str) -> String {
+	let (rtl_len, ltr_len) = text.chars().fold((0, 0), |(rtl_len, ltr_len), c| match bidi_class(c) {
+		BidiClass::R | BidiClass::AL => (rtl_len + 1, ltr_len),
+		BidiClass::L => (rtl_len, ltr_len + 1),
+		_ => (rtl_len, ltr_len),
+	});
+	if rtl_len <= ltr_len {
+		return text.to_string();
+	}
+	text.split('\n')
+		.map(|line| {
+			let bidi_info = BidiInfo::new(line, Some(Level::rtl()));
+			bidi_info.paragraphs.iter().map(|paragraph| bidi_info.reorder_line(paragraph, paragraph.range.clone())).collect::<String>()
+		})
+		.collect::<Vec<String>>()
+		.join("\n")
+}
+
+/// Reads the comments of a PDF's annotations (sticky notes, highlights and other markup), which pdftotext leaves out.
+/// Popups are skipped, they repeat the comment of the annotation they belong to.
+///
+/// # Returns
+///
+/// * By page number, `Annotations:` then a line per commented annotation: its subtype, the author in brackets if set, and the comment.
+///   Pages without comments are left out.
+fn pdf_annotations(document: &lopdf::Document) -> BTreeMap<u32, String> {
+	let mut annotations = BTreeMap::new();
+	for (page_number, page_id) in document.get_pages() {
+		let mut text = String::new();
+		for annotation in document.get_page_annotations(page_id).unwrap_or_default() {
+			let text_value = |key: &[u8]| {
+				annotation.get(key)
+					.and_then(|x| document.dereference(x))
+					.and_then(|(_, x)| lopdf::decode_text_string(x))
+					.map(|x| x.replace("\r\n", "\n").replace('\r', "\n").trim().to_string())
+					.unwrap_or_default()
+			};
+			let subtype = annotation.get(b"Subtype")
+				.and_then(|x| x.as_name())
+				.map(|x| String::from_utf8_lossy(x).to_string())
+				.unwrap_or_default();
+			let contents = text_value(b"Contents");
+			if subtype == "Popup" || contents.is_empty() {
+				continue;
+			}
+			let author = text_value(b"T");
+			if author.is_empty() {
+				text.push_str(&format!("{}: {}\n", subtype, contents));
+			} else {
+				text.push_str(&format!("{} ({}): {}\n", subtype, author, contents));
+			}
+		}
+		if !text.is_empty() {
+			annotations.insert(page_number, format!("Annotations:\n{}", text));
+		}
+	}
+	annotations
+}
+
+/// Downscales an image with more than `max_pixels` pixels to about that many, keeping its aspect ratio, as a grayscale png in the temp folder.
+/// Only the header is read for images within the limit.
+///
+/// # Returns
+///
+/// * The path of the downscaled image, None if the image is within the limit
+fn downscale_for_ocr(filepath: &Path, max_pixels: u64) -> Result<Option<PathBuf>, Box<dyn Error>> {
+	let (width, height) = image::ImageReader::open(filepath)?.with_guessed_format()?.into_dimensions()?;
+	let pixels = width as u64 * height as u64;
+	if pixels <= max_pixels {
+		return Ok(None);
+	}
+	let scale = (max_pixels as f64 / pixels as f64).sqrt();
+	let new_width = ((width as f64 * scale) as u32).max(1);
+	let new_height = ((height as f64 * scale) as u32).max(1);
+	debug!("Downscaling {:?} from {}x{} to {}x{} for OCR", filepath, width, height, new_width, new_height);
+	let downscaled = image::ImageReader::open(filepath)?.with_guessed_format()?.decode()?
+		.resize_exact(new_width, new_height, image::imageops::FilterType::Triangle)
+		.into_luma8();
+	let outpath = tempfiles_location().join(format!("{}.png", Uuid::new_v4().simple()));
+	downscaled.save(&outpath)?;
+	Ok(Some(outpath))
+}
+
+/// The difference hash (dHash) of an image, a perceptual hash that stays about the same when the image is recompressed or resized:
+/// the image is shrunk to 9x8 gray pixels and each bit says if a pixel is brighter than the one to its right.
+/// It is returned with the width and height of the image.
+fn image_dhash(filepath: &Path) -> Result<ImageHash, Box<dyn Error>> {
+	let image = image::ImageReader::open(filepath)?.with_guessed_format()?.decode()?;
+	let dimensions = (image.width(), image.height());
+	let small = image
+		.resize_exact(9, 8, image::imageops::FilterType::Triangle)
+		.into_luma8();
+	let mut hash = 0u64;
+	for y in 0..8 {
+		for x in 0..8 {
+			hash <<= 1;
+			if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+				hash |= 1;
+			}
+		}
+	}
+	Ok((hash, dimensions))
+}
+
+/// The dHash of an image, and its width and height.
+type ImageHash = (u64, (u32, u32));
+
+/// Checks whether an image is a page rendered for OCR, e.g. `page 3 render.png`, as written for the pages of PDF and djvu files.
+fn is_page_render(filepath: &Path) -> bool {
+	let file_stem = filepath.file_stem().unwrap_or_default().to_string_lossy();
+	file_stem.strip_prefix("page ")
+		.and_then(|x| x.strip_suffix(" render"))
+		.is_some_and(|x| !x.is_empty() && x.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The tesseract command that reads `filepath` into `outbase`.txt, with the page segmentation mode (`--psm`) and OCR engine mode (`--oem`) if set.
+fn tesseract_command(filepath: &Path, outbase: &str, psm: Option<u8>, oem: Option<u8>) -> Command {
+	let mut command = Command::new("tesseract");
+	command
+		.arg("-l").arg("eng");
+	if let Some(psm) = psm {
+		command.arg("--psm").arg(psm.to_string());
+	}
+	if let Some(oem) = oem {
+		command.arg("--oem").arg(oem.to_string());
+	}
+	command
+		.arg(format!("{}", filepath.to_string_lossy().to_string()))
+		.arg(outbase);
+	command
+}
+
+fn ocr(filepath: &Path, psm: Option<u8>, oem: Option<u8>, runner: &dyn CommandRunner) -> Result<String, Box<dyn Error>> {
+	// tesseract -l eng "C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\43766efc4742438884b0f109fd6a6bac\image-0001.ppm" C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\43766efc4742438884b0f109fd6a6bac\ocr
+	// https://tesseract-ocr.github.io/tessdoc/Command-Line-Usage.html
+	// https://github.com/tesseract-ocr/tessdata_fast
+	// get traineddata for eng (english) and osd (orientation and script detection)
+	let a_uuid: &str = &Uuid::new_v4().simple().to_string();
+	let outpath = tempfiles_location().join(a_uuid);
+	let mut outpath = format!("{}", outpath.to_string_lossy().to_string());
+	let mut command = tesseract_command(filepath, &outpath, psm, oem);
+	trace!("{:#?}", command);
+	match runner.output(&mut command) {
+		Ok(_output) => {
+			//println!("stdout: {}", String::from_utf8_lossy(&output.stdout));
+		}
+		Err(e) => {
+			println!("{:#?}", command);
+			return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
+		}
+	}
+	outpath.push_str(&".txt");
+	let outpath = PathBuf::from(outpath);
+	if outpath.exists() {
+		//tesseract writes UTF-8
+		let contents = read_file_with_encoding(&outpath, UTF_8)?;
+		_ = std::fs::remove_file(&outpath);
+		return Ok(contents);
+	}
+
+	return Ok(String::new());
+}
+
+/// OCR with the modes of the config. With `ocr_alternate_psm` the image is read twice and the pass with more text is kept.
+fn ocr_with_config(filepath: &Path, config: &ExtractConfig) -> Result<String, Box<dyn Error>> {
+	let text = ocr(filepath, config.ocr_psm, config.ocr_oem, &config.runner())?;
+	if let Some(alternate_psm) = config.ocr_alternate_psm {
+		let alternate_text = ocr(filepath, Some(alternate_psm), config.ocr_oem, &config.runner())?;
+		let text_len = |x: &str| x.chars().filter(|c| !c.is_whitespace()).count();
+		if text_len(&alternate_text) > text_len(&text) {
+			return Ok(alternate_text);
+		}
+	}
+	Ok(text)
+}
+
+fn convert_accented_manual(s: &str) -> String {
+	s.chars()
+		.map(|c| match c {
+			'á' | 'à' | 'ã' | 'â' => 'a',
+			'é' | 'è' | 'ê' => 'e',
+			'í' | 'ì' | 'î' | 'ï' => 'i',
+			'ó' | 'ò' | 'õ' | 'ô' => 'o',
+			'ú' | 'ù' | 'ũ' | 'û' => 'u',
+			'ñ' => 'n',
+			// Add more mappings as needed
+			_ => c, // Keep other characters as they are
+		})
+		.collect()
+}
+
+/// # Returns
+///
+/// * The text, the method used: `text` if decoded, `strings` if only the printable runs of a binary file were read,
+///   the encoding it was decoded from, None for `strings`, and whether the text was cut at `config.max_text_bytes`
+///   or to the last `config.log_tail_lines` lines
+fn read_text_from_file(filepath: &Path, config: &ExtractConfig) -> Result<SubfileText, Box<dyn Error>> {
+	let file_encoding = detect_encoding(filepath, false);
+	debug!("file_encoding: {:?}", file_encoding);
+	//NUL bytes outside of UTF-16 mean a binary file
+	if let Some(min_len) = config.binary_strings_min_len
+		&& file_encoding != UTF_16LE && file_encoding != UTF_16BE
+	{
+		let bytes = fs::read(filepath)?;
+		if bytes.contains(&0) {
+			let mut text = printable_runs(&bytes, min_len);
+			//the runs are ASCII, any byte is a character boundary
+			let is_truncated = config.max_text_bytes.is_some_and(|x| text.len() as u64 > x);
+			if let Some(max_text_bytes) = config.max_text_bytes.filter(|_| is_truncated) {
+				text.truncate(max_text_bytes as usize);
+			}
+			return Ok(SubfileText {
+				text,
+				method: Some(String::from("strings")),
+				truncated: is_truncated,
+				..Default::default()
+			});
+		}
+	}
+	let log_tail_lines = config.log_tail_lines.filter(|_| is_log_file(filepath));
+	//a whole log is read and cleaned a line at a time
+	if log_tail_lines.is_none() && is_log_file(filepath) {
+		let (text, raw_text, is_truncated) = decode_clean_lines(File::open(filepath)?, file_encoding, config.max_text_bytes, config)?;
+		if is_truncated {
+			debug!("Text of {:?} cut at {} bytes", filepath, config.max_text_bytes.unwrap_or_default());
+		}
+		return Ok(SubfileText {
+			raw_text,
+			text,
+			method: Some(String::from("text")),
+			encoding: Some(file_encoding),
+			truncated: is_truncated,
+			..Default::default()
+		});
+	}
+	let (contents, is_truncated) = match (log_tail_lines, config.max_text_bytes) {
+		(Some(log_tail_lines), _) => decode_tail_lines(File::open(filepath)?, file_encoding, log_tail_lines)?,
+		(None, Some(max_text_bytes)) => decode_with_encoding_limit(File::open(filepath)?, file_encoding, max_text_bytes)?,
+		(None, None) => (read_file_with_encoding(filepath, file_encoding)?, false),
+	};
+	if is_truncated {
+		match log_tail_lines {
+			Some(log_tail_lines) => debug!("Only the last {} lines of {:?} read", log_tail_lines, filepath),
+			None => debug!("Text of {:?} cut at {} bytes", filepath, config.max_text_bytes.unwrap_or_default()),
+		}
+	}
+	return Ok(SubfileText {
+		raw_text: raw_text_prefix(&contents, config),
+		text: clean_text(contents, config),
+		method: Some(String::from("text")),
+		encoding: Some(file_encoding),
+		truncated: is_truncated,
+		..Default::default()
+	});
+}
+
+/// Reads a text file, cleaned as extraction does, in the encoding detected from its BOM or else its content (UTF-8, or CP1252 if it is not valid UTF-8).
+///
+/// # Returns
+///
+/// * The text, and the encoding it was decoded from, e.g. `WINDOWS_1252`
+pub fn read_text_with_encoding(filepath: &Path, config: &ExtractConfig) -> Result<(String, &'static Encoding), Box<dyn Error>> {
+	let file_encoding = detect_encoding(filepath, false);
+	let contents = read_file_with_encoding(filepath, file_encoding)?;
+	Ok((clean_text(contents, config), file_encoding))
+}
+
+/// Checks a file for NUL bytes, which text only contains when it is UTF-16.
+fn is_binary_file(filepath: &Path) -> bool {
+	let file_encoding = detect_encoding(filepath, false);
+	if file_encoding == UTF_16LE || file_encoding == UTF_16BE {
+		return false;
+	}
+	let Ok(file) = File::open(filepath) else {
+		return false;
+	};
+	let mut reader = BufReader::new(file);
+	loop {
+		let buf = match reader.fill_buf() {
+			Ok(buf) => buf,
+			Err(_) => return false,
+		};
+		if buf.is_empty() {
+			return false;
+		}
+		if buf.contains(&0) {
+			return true;
+		}
+		let len = buf.len();
+		reader.consume(len);
+	}
+}
+
+/// Finds the runs of at least `min_len` printable ASCII characters in binary data, like the `strings` command.
+///
+/// # Returns
+///
+/// * The runs, one per line
+fn printable_runs(bytes: &[u8], min_len: usize) -> String {
+	let mut text = String::new();
+	for run in bytes.split(|b| !(b.is_ascii_graphic() || *b == b' ' || *b == b'\t')) {
+		if run.len() >= min_len.max(1) {
+			text.push_str(&String::from_utf8_lossy(run));
+			text.push('\n');
+		}
+	}
+	text
+}
+
+/// Normalizes tab delimited text as spreadsheet sheets are written: each line a row of cells, trimmed of the spaces that pad
+/// fixed width columns and joined with `config.cell_separator`, each row ended with `config.row_separator`, and blank rows dropped.
+fn tabular_text(text: &str, config: &ExtractConfig) -> String {
+	let mut table = String::new();
+	for line in text.lines() {
+		let row: Vec<&str> = line.split('\t').map(|x| x.trim()).collect();
+		let row = row.join(&config.cell_separator);
+		if !row.trim().is_empty() {
+			table.push_str(&row);
+			table.push_str(&config.row_separator);
+		}
+	}
+	table
+}
+
+/// Whether a character is kept by `ExtractConfig::preserve_unicode`. Every printable character, in any script, is kept,
+/// as are whitespace and format characters such as zero width joiners and direction marks. Dropped are:
+/// * control characters (Unicode category Cc) other than whitespace, e.g. NUL, backspace, escape and the C1 controls
+/// * U+FFFD, the replacement character a decoder writes for an invalid byte sequence
+/// * U+FEFF, a byte order mark left in the text
+/// * noncharacters, U+FDD0 to U+FDEF and the last two code points of each plane, e.g. U+FFFE and U+FFFF
+fn is_unicode_text_char(c: char) -> bool {
+	if c.is_control() {
+		return c.is_whitespace();
+	}
+	let code = c as u32;
+	!(c == char::REPLACEMENT_CHARACTER || c == '\u{FEFF}' || (0xFDD0..=0xFDEF).contains(&code) || code & 0xFFFE == 0xFFFE)
+}
+
+/// Reduces decoded text to ASCII, converting accented characters to their base letters, unless `config.preserve_unicode` is set,
+/// which keeps all printable Unicode and drops just control characters and invalid sequences, see is_unicode_text_char.
+/// A `config.allowed_chars` filter replaces this, keeping just the characters it allows.
+fn clean_text(mut contents: String, config: &ExtractConfig) -> String {
+	if let Some(allowed_chars) = &config.allowed_chars {
+		contents.retain(|c| allowed_chars.allows(c));
+		return contents;
+	}
+	if config.preserve_unicode {
+		contents.retain(is_unicode_text_char);
+		return contents;
+	}
+	// if file_encoding == WINDOWS_1252 {
+		//if no 0 or 255 bytes the in the contents, assume this is a text file and convert accented characters to base letters
+		if !(contents.as_bytes().contains(&0) || contents.as_bytes().contains(&255)) {
+			contents = convert_accented_manual(&contents);
+		}
+		//clean all but english letters
+		contents.retain(|c| c.is_ascii_graphic() || c.is_whitespace());
+	// }
+	// debug!("contents: {:?}", contents);
+	contents
+}
+
+/// The start of a text before clean_text, up to `config.raw_text_max_bytes` and cut at a character boundary.
+/// None unless that option is set.
+fn raw_text_prefix(contents: &str, config: &ExtractConfig) -> Option<String> {
+	let max_bytes = config.raw_text_max_bytes?;
+	let mut end = max_bytes.min(contents.len());
+	while !contents.is_char_boundary(end) {
+		end -= 1;
+	}
+	trace!("text before cleaning: {:?}", &contents[..end]);
+	Some(contents[..end].to_string())
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+struct SubFileItem {
+	filepath: PathBuf,
+	depth: u8,
+	parent_files: Vec<String>,
+	ok_to_extract_text: bool,
+	note: Option<String>,
+	error: Option<String>,
+	/// Text already decoded in memory, used instead of reading `filepath` (which is then never written).
+	text: Option<String>,
+	/// How the text was obtained, when that is decided before extract_text_from_subfile, e.g. `pdftotext` for a PDF page
+	method: Option<String>,
+	/// See FileListItem::has_scripts
+	has_scripts: bool,
+	/// See FileListItem::modified
+	modified: Option<i64>,
+	/// Only part of the text was generated, such as a sheet cut short by ExtractConfig::max_sheet_rows. See FileListItem::truncated
+	truncated: bool,
+	/// Matches a prescanned item and was not opened, its items are carried over from the prescan
+	unchanged: bool,
+	/// Size and crc32 of a member that is listed but not written, as recorded by the archive, e.g. an encrypted zip entry
+	listed_size_crc: Option<(u64, i64)>,
+	/// Comments of an archive, read as its text instead of the file, see ExtractConfig::archive_comments
+	comments: Option<String>,
+}
+
+impl SubFileItem {
+	/// The parent_files of the items extracted from this one: its own parent_files and its name.
+	fn lineage(&self) -> Vec<String> {
+		let mut lineage = self.parent_files.clone();
+		lineage.push(self.filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
+		lineage
+	}
+}
+
+/// (size, crc) of the prescanned items by (filename, parent_files)
+type PreScannedMembers = HashMap<(String, Vec<String>), Vec<(i64, i64)>>;
+
+/// What extract_archive needs to know of the extraction it is part of, besides the options, passed down to the files it recurses into.
+#[derive(Debug, Default)]
+struct UnpackContext {
+	/// See is_unchanged_member
+	pre_scanned_members: PreScannedMembers,
+	/// List the files without converting the pages of PDF and DjVu files, no pdftotext, pdfimages, pdftoppm, djvutxt or ddjvu
+	/// is run on them. See estimate_work, which counts the pages and their images itself.
+	enumerate_only: bool,
+}
+
+impl UnpackContext {
+	fn new(pre_scanned_items: &[FileListItem]) -> Self {
+		let mut pre_scanned_members: PreScannedMembers = HashMap::new();
+		for x in pre_scanned_items {
+			pre_scanned_members.entry((x.filename.clone(), x.parent_files.clone())).or_default().push((x.size, x.crc));
+		}
+		UnpackContext { pre_scanned_members, ..Default::default() }
+	}
+
+	/// Whether a member matches a prescanned item by name, parent_files, size and crc.
+	/// The crc is only computed when an item of that name and size was prescanned.
+	fn is_unchanged_member(&self, filepath: &Path, parent_files: &[String]) -> bool {
+		if self.pre_scanned_members.is_empty() {
+			return false;
+		}
+		let file_name = filepath.file_name().unwrap_or_default().to_string_lossy().to_string();
+		let Some(sizes_and_crcs) = self.pre_scanned_members.get(&(file_name, parent_files.to_vec())) else {
+			return false;
+		};
+		let Ok(size) = filepath.metadata().map(|metadata| metadata.len() as i64) else {
+			return false;
+		};
+		if size == 0 || !sizes_and_crcs.iter().any(|(x, _)| *x == size) {
+			return false;
+		}
+		match checksum_file(Crc64Nvme, &filepath.to_string_lossy(), None) {
+			Ok(crc) => sizes_and_crcs.contains(&(size, crc as i64)),
+			Err(_) => false,
+		}
+	}
+}
+
+/// Every lineage that some item was extracted from, each start of each item's parent_files.
+fn parent_lineages(list_of_files_in_archive: &[SubFileItem]) -> HashSet<Vec<String>> {
+	let mut parent_lineages = HashSet::new();
+	for sub_file_item in list_of_files_in_archive {
+		for len in 1..=sub_file_item.parent_files.len() {
+			parent_lineages.insert(sub_file_item.parent_files[..len].to_vec());
+		}
+	}
+	parent_lineages
+}
+
+/// Copies a subfile to `preserve_dir`, named after its crc and keeping its extension, see ExtractConfig::preserve_subfiles_dir.
+/// Subfiles with the same crc are stored once.
+///
+/// # Returns
+///
+/// * The path of the copy, None if it could not be made
+fn preserve_subfile(filepath: &Path, crc: i64, preserve_dir: &Path) -> Option<PathBuf> {
+	let mut filename = format!("{:016x}", crc as u64);
+	if let Some(extension) = filepath.extension() {
+		filename.push('.');
+		filename.push_str(&extension.to_string_lossy());
+	}
+	let preserved_path = preserve_dir.join(filename);
+	if !preserved_path.exists()
+		&& let Err(e) = fs::create_dir_all(preserve_dir).and_then(|_| fs::copy(filepath, &preserved_path))
+	{
+		warn!("Error preserving subfile {:?} to {:?}: {}", filepath, preserved_path, e);
+		return None;
+	}
+	Some(preserved_path)
+}
+
+/// Text read from a subfile, with how it was read, or why it could not be.
+#[derive(Default)]
+struct SubfileText {
+	text: String,
+	method: Option<String>,
+	/// Why the subfile was not read, e.g. the OCR budget is spent
+	note: Option<String>,
+	/// The extractor failed, e.g. the document has no content part, as opposed to the document being empty
+	error: Option<String>,
+	/// The encoding a text file was decoded from
+	encoding: Option<&'static Encoding>,
+	/// The text was cut at ExtractConfig::max_text_bytes, or to the last ExtractConfig::log_tail_lines lines
+	truncated: bool,
+	/// The start of the text before clean_text, see ExtractConfig::raw_text_max_bytes
+	raw_text: Option<String>,
+}
+
+/// # Returns
+///
+/// * The text, how it was obtained (see FileListItem::method) and any extractor error. A failed extractor gives empty text, not an Err.
+fn extract_text_from_subfile(file_list_item: &SubFileItem, config: &ExtractConfig) -> Result<SubfileText, Box<dyn Error>> {
+	debug!("subfile to extract text: {:?}", file_list_item.filepath);
+	
+	if !file_list_item.ok_to_extract_text {
+		return Ok(SubfileText::default())
+	}
+	if let Some(text) = file_list_item.text.as_ref().or(file_list_item.comments.as_ref()) {
+		return Ok(SubfileText {
+			text: clean_text(text.clone(), config),
+			raw_text: raw_text_prefix(text, config),
+			method: file_list_item.method.clone(),
+			..Default::default()
+		});
+	}
+	// let file_extension = file_list_item.filepath.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+	let file_type = get_effective_file_type(&file_list_item.filepath);
+	debug!("extract_text_from_subfile: file_type: {:?}", file_type);
+
+	if let Some(extractor) = config.extractors.get(&file_type) {
+		if extractor.method() == "ocr" && config.ocr_budget.as_ref().is_some_and(|x| x.is_exhausted()) {
+			debug!("OCR budget exhausted, not reading {:?}", file_list_item.filepath);
+			return Ok(SubfileText {
+				note: Some(String::from("OCR budget exhausted, not extracted")),
+				..Default::default()
+			});
+		}
+		match extractor.extract_with_config(file_list_item.filepath.as_path(), config) {
+			Ok(text) => return Ok(SubfileText {
+				text,
+				method: Some(extractor.method().to_string()),
+				..Default::default()
+			}),
+			Err(ExtractError::NotExtracted(note)) => {
+				info!("Not extracting text from {} {:?}: {}", file_type, file_list_item.filepath, note);
+				return Ok(SubfileText {
+					note: Some(note),
+					..Default::default()
+				});
+			}
+			Err(e) => {
+				warn!("Error extracting text from {} {:?}\n{:?}", file_type, file_list_item.filepath, e);
+				let e: &dyn Error = match &e {
+					ExtractError::Extractor(e) => e.as_ref(),
+					e => e,
+				};
+				return Ok(SubfileText {
+					error: Some(format!("Error extracting text from {}: {}", file_type, e)),
+					..Default::default()
+				});
+			}
+		}
+	}
+
+	if file_type == FileType::Markdown && config.markdown_to_text {
+		let file_encoding = detect_encoding(&file_list_item.filepath, false);
+		let contents = markdown::to_text(&read_file_with_encoding(&file_list_item.filepath, file_encoding)?, config.markdown_link_urls);
+		return Ok(SubfileText {
+			raw_text: raw_text_prefix(&contents, config),
+			text: clean_text(contents, config),
+			method: Some(String::from("markdown")),
+			encoding: Some(file_encoding),
+			..Default::default()
+		});
+	}
+
+	//text
+	let mut subfile_text = read_text_from_file(file_list_item.filepath.as_path(), config)?;
+	if config.tab_delimited_text
+		&& file_type == FileType::Text
+		&& subfile_text.method.as_deref() == Some("text")
+		&& is_delimited_text(&file_list_item.filepath, &['\t'])
+	{
+		subfile_text.text = tabular_text(&subfile_text.text, config);
+		subfile_text.method = Some(String::from("tab delimited"));
+	}
+	// debug!("contents: {:?}", subfile_text.text);
+	return Ok(SubfileText {
+		//text generated from another file is written as UTF-8 by this crate, its encoding says nothing of the original
+		encoding: subfile_text.encoding.filter(|_| file_list_item.method.is_none()),
+		method: file_list_item.method.clone().or(subfile_text.method),
+		..subfile_text
+	});
+}
+
+/// Extracts the text of one file type. Register implementations in `ExtractConfig::extractors`.
+pub trait TextExtractor: Send + Sync {
+	/// Failures are returned as ExtractError::Extractor, which an io::Error converts to with `?`.
+	fn extract(&self, path: &Path) -> Result<String, ExtractError>;
+
+	/// Used instead of `extract` during extraction, for extractors whose output depends on the options. Defaults to `extract`.
+	fn extract_with_config(&self, path: &Path, config: &ExtractConfig) -> Result<String, ExtractError> {
+		let _ = config;
+		self.extract(path)
+	}
+
+	/// Short name of how the text is obtained, reported in FileListItem::method. Defaults to `custom`.
+	fn method(&self) -> &str {
+		"custom"
+	}
+}
+
+struct DocxExtractor;
+
+impl DocxExtractor {
+	fn read(path: &Path, paragraph_separator: &str) -> Result<String, Box<dyn Error>> {
+		//dotext
+		let mut doc = <Docx as MsDoc<Docx>>::open(path)?;
+		doc.set_paragraph_separator(paragraph_separator);
+		let mut text = String::new();
+		let _ = doc.read_to_string(&mut text);
+		Ok(text)
+	}
+}
+
+impl TextExtractor for DocxExtractor {
+	fn extract(&self, path: &Path) -> Result<String, ExtractError> {
+		DocxExtractor::read(path, DEFAULT_PARAGRAPH_SEPARATOR).map_err(ExtractError::Extractor)
+	}
+
+	fn extract_with_config(&self, path: &Path, config: &ExtractConfig) -> Result<String, ExtractError> {
+		DocxExtractor::read(path, &config.paragraph_separator).map_err(ExtractError::Extractor)
+	}
+
+	fn method(&self) -> &str {
+		"docx xml"
+	}
+}
+
+struct OdtExtractor;
+
+impl OdtExtractor {
+	fn read(path: &Path, paragraph_separator: &str) -> Result<String, Box<dyn Error>> {
+		//dotext
+		let mut doc = <Odt as OpenOfficeDoc<Odt>>::open(path)?;
+		doc.set_paragraph_separator(paragraph_separator);
+		let mut text = String::new();
+		let _ = doc.read_to_string(&mut text);
+		Ok(text)
+	}
+}
+
+struct DxfExtractor;
+
+impl TextExtractor for DxfExtractor {
+	fn extract(&self, path: &Path) -> Result<String, ExtractError> {
+		let mut header = [0u8; 18];
+		if File::open(path)?.read_exact(&mut header).is_ok() && &header == b"AutoCAD Binary DXF" {
+			return Err(ExtractError::Extractor("binary DXF is not supported".into()));
+		}
+		Ok(dxf::to_text(&read_file_with_encoding(path, detect_encoding(path, false)).map_err(ExtractError::Extractor)?))
+	}
+
+	fn method(&self) -> &str {
+		"dxf"
+	}
+}
+
+struct RtfExtractor;
+
+impl TextExtractor for RtfExtractor {
+	fn extract(&self, path: &Path) -> Result<String, ExtractError> {
+		Ok(rtf::to_text(&fs::read(path)?))
+	}
+
+	fn method(&self) -> &str {
+		"rtf"
+	}
+}
+
+impl TextExtractor for OdtExtractor {
+	fn extract(&self, path: &Path) -> Result<String, ExtractError> {
+		OdtExtractor::read(path, DEFAULT_PARAGRAPH_SEPARATOR).map_err(ExtractError::Extractor)
+	}
+
+	fn extract_with_config(&self, path: &Path, config: &ExtractConfig) -> Result<String, ExtractError> {
+		OdtExtractor::read(path, &config.paragraph_separator).map_err(ExtractError::Extractor)
+	}
+
+	fn method(&self) -> &str {
+		"odt xml"
+	}
+}
+
+struct OcrExtractor;
+
+impl OcrExtractor {
+	fn read(path: &Path, config: &ExtractConfig) -> Result<String, ExtractError> {
+		if config.ocr_max_image_pixels == 0 {
+			return ocr_with_config(path, config).map_err(ExtractError::Extractor);
+		}
+		//a format the image crate can't read, e.g. JBIG2, is left to tesseract
+		let (width, height) = match image::ImageReader::open(path)?.with_guessed_format()?.into_dimensions() {
+			Ok(dimensions) => dimensions,
+			Err(e) => {
+				warn!("Error reading the size of image {:?}, OCR as it is: {}", path, e);
+				return ocr_with_config(path, config).map_err(ExtractError::Extractor);
+			}
+		};
+		if width as u64 * height as u64 <= config.ocr_max_image_pixels {
+			return ocr_with_config(path, config).map_err(ExtractError::Extractor);
+		}
+		//huge images are downscaled first, tesseract's memory use grows with the pixel count
+		//one too large to even decode, e.g. past the image crate's allocation limit, would exhaust tesseract's memory too
+		match downscale_for_ocr(path, config.ocr_max_image_pixels) {
+			Ok(Some(downscaled_path)) => {
+				let text = ocr_with_config(&downscaled_path, config);
+				_ = fs::remove_file(&downscaled_path);
+				text.map_err(ExtractError::Extractor)
+			}
+			Ok(None) => ocr_with_config(path, config).map_err(ExtractError::Extractor),
+			Err(e) => {
+				warn!("Error downscaling image {:?} of {}x{}, not read by OCR: {}", path, width, height, e);
+				Err(ExtractError::NotExtracted(String::from("image too large, not extracted")))
+			}
+		}
+	}
+}
+
+impl TextExtractor for OcrExtractor {
+	fn extract(&self, path: &Path) -> Result<String, ExtractError> {
+		//tesseract, within the process limit shared by the default configs
+		let runner = LimitedCommandRunner { runner: &SystemCommandRunner, limit: &ProcessLimit::default() };
+		ocr(path, None, None, &runner).map_err(ExtractError::Extractor)
+	}
+
+	fn extract_with_config(&self, path: &Path, config: &ExtractConfig) -> Result<String, ExtractError> {
+		//an image that looks like one already read, e.g. a logo on every page, reuses its text
+		//pages are left out, the hash of a page of text is much like that of any other
+		let image_hash = match &config.ocr_image_dedup {
+			Some(_) if !is_page_render(path) => match image_dhash(path) {
+				Ok(image_hash) => Some(image_hash),
+				Err(e) => {
+					warn!("Error hashing image {:?}, OCR without dedup: {}", path, e);
+					None
+				}
+			},
+			_ => None,
+		};
+		if let (Some(image_dedup), Some(image_hash)) = (&config.ocr_image_dedup, image_hash)
+			&& let Some(text) = image_dedup.find(image_hash)
+		{
+			debug!("Image {:?} looks like one already read, reusing its OCR text", path);
+			return Ok(text);
+		}
+		let start = Instant::now();
+		//cleaned like any text read from a file
+		let text = OcrExtractor::read(path, config).map(|text| clean_text(text, config));
+		if let Some(ocr_budget) = &config.ocr_budget {
+			ocr_budget.add(start.elapsed());
+		}
+		if let (Some(image_dedup), Some(image_hash), Ok(text)) = (&config.ocr_image_dedup, image_hash, &text) {
+			image_dedup.insert(image_hash, text);
+		}
+		text
+	}
+
+	fn method(&self) -> &str {
+		"ocr"
+	}
+}
+
+/// Text extractors by detected file type (as returned by get_effective_file_type), consulted before the built-in handling.
+/// The default registry holds the built-in extractors for docx, docm, odt and images.
+#[derive(Clone)]
+pub struct ExtractorRegistry {
+	extractors: HashMap<FileType, Arc<dyn TextExtractor>>,
+	/// Types registered through `register`, their files are extracted as they are instead of being opened as archives.
+	custom: HashSet<FileType>,
+}
+
+impl Default for ExtractorRegistry {
+	fn default() -> Self {
+		let mut registry = ExtractorRegistry::empty();
+		let docx: Arc<dyn TextExtractor> = Arc::new(DocxExtractor);
+		let odt: Arc<dyn TextExtractor> = Arc::new(OdtExtractor);
+		let ocr: Arc<dyn TextExtractor> = Arc::new(OcrExtractor);
+		let rtf: Arc<dyn TextExtractor> = Arc::new(RtfExtractor);
+		let dxf: Arc<dyn TextExtractor> = Arc::new(DxfExtractor);
+		for (file_type, extractor) in [
+			(FileType::Docx, &docx),
+			(FileType::Docm, &docx),
+			(FileType::Dxf, &dxf),
+			(FileType::Odt, &odt),
+			(FileType::Rtf, &rtf),
+			(FileType::Image(ImageKind::Jpeg), &ocr),
+			(FileType::Image(ImageKind::Pgm), &ocr),
+			(FileType::Image(ImageKind::Png), &ocr),
+			(FileType::Image(ImageKind::Ppm), &ocr),
+		] {
+			registry.extractors.insert(file_type, extractor.clone());
+		}
+		registry
+	}
+}
+
+impl std::fmt::Debug for ExtractorRegistry {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let mut file_types: Vec<&str> = self.extractors.keys().map(|x| x.extension()).collect();
+		file_types.sort();
+		f.debug_struct("ExtractorRegistry")
+			.field("file_types", &file_types)
+			.field("custom", &self.custom)
+			.finish()
+	}
+}
+
+impl ExtractorRegistry {
+	/// A registry without any extractors, not even the built-in ones.
+	pub fn empty() -> Self {
+		ExtractorRegistry {
+			extractors: HashMap::new(),
+			custom: HashSet::new(),
+		}
+	}
+
+	/// Adds an extractor for a file extension, replacing any extractor (built-in or not) already registered for its type.
+	pub fn register(&mut self, extension: &str, extractor: impl TextExtractor + 'static) -> &mut Self {
+		let file_type = FileType::from_extension(extension);
+		self.extractors.insert(file_type.clone(), Arc::new(extractor));
+		self.custom.insert(file_type);
+		self
+	}
+
+	pub fn get(&self, file_type: &FileType) -> Option<&Arc<dyn TextExtractor>> {
+		self.extractors.get(file_type)
+	}
+
+	fn is_custom(&self, file_type: &FileType) -> bool {
+		self.custom.contains(file_type)
+	}
+}
+
+/// How the text of each file is split up, see ExtractConfig::segmentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Segmentation {
+	/// The text is kept whole
+	#[default]
+	None,
+	/// Sentences by the Unicode sentence boundaries (UAX #29), not split after common abbreviations such as `Dr.` or `e.g.`, or initials.
+	/// A line break only ends a sentence when it is followed by a blank line. The whitespace within a sentence is collapsed to single spaces.
+	Sentence,
+	/// Lines that are not blank
+	Line,
+	/// Words by the Unicode word boundaries (UAX #29), without the punctuation and whitespace between them
+	Token,
+}
+
+/// Which symlinks a directory scan follows, see ExtractConfig::symlinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+	/// Symlinks to files are scanned wherever they lead, symlinks to directories are not walked
+	#[default]
+	Files,
+	/// Symlinks to files and directories are not scanned
+	Skip,
+	/// Symlinks are followed if their target is inside the scanned directory
+	FollowWithinRoot,
+	/// Every symlink is followed, wherever it leads
+	FollowAll,
+}
+
+/// The line ending extracted text is written with, see ExtractConfig::line_ending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+	/// `\n`, as most formats are read
+	#[default]
+	Lf,
+	/// `\r\n`, as Windows tools expect
+	CrLf,
+}
+
+/// Rewrites every line break of a text, `\r\n`, `\n` or a lone `\r`, as `line_ending`.
+fn normalize_line_endings(text: &str, line_ending: LineEnding) -> String {
+	let text = text.replace("\r\n", "\n").replace('\r', "\n");
+	match line_ending {
+		LineEnding::Lf => text,
+		LineEnding::CrLf => text.replace('\n', "\r\n"),
+	}
+}
+
+/// The format PDF pages are rendered and PDF images written in for OCR, see ExtractConfig::pdf_ocr_image_format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PdfOcrImageFormat {
+	/// Grayscale PNG pages (`pdftoppm -gray -png`) and PNG images (`pdfimages -png`), compressed so the smallest on disk
+	#[default]
+	Png,
+	/// Grayscale PGM pages (`pdftoppm -gray`), uncompressed so quicker to write and read but larger. Images are written as by Ppm.
+	Pgm,
+	/// Color PPM pages (`pdftoppm`), and images in the netpbm format of their colors (`pdfimages`), uncompressed and the largest on disk
+	Ppm,
+}
+
+impl PdfOcrImageFormat {
+	/// The pdftoppm options, and the extension of the page image it writes.
+	fn pdftoppm_args(&self) -> (&'static [&'static str], &'static str) {
+		match self {
+			PdfOcrImageFormat::Png => (&["-gray", "-png"], "png"),
+			PdfOcrImageFormat::Pgm => (&["-gray"], "pgm"),
+			PdfOcrImageFormat::Ppm => (&[], "ppm"),
+		}
+	}
+
+	/// The pdfimages options, and the extensions of the images it writes that OCR reads, in order of preference.
+	fn pdfimages_args(&self) -> (&'static [&'static str], &'static [&'static str]) {
+		match self {
+			PdfOcrImageFormat::Png => (&["-png"], &["png"]),
+			PdfOcrImageFormat::Pgm | PdfOcrImageFormat::Ppm => (&[], &["ppm", "pgm"]),
+		}
+	}
+}
+
+/// A policy of which characters are kept in extracted text, see ExtractConfig::allowed_chars.
+#[derive(Clone)]
+pub struct CharFilter(Arc<dyn Fn(char) -> bool + Send + Sync>);
+
+impl CharFilter {
+	pub fn new(predicate: impl Fn(char) -> bool + Send + Sync + 'static) -> Self {
+		CharFilter(Arc::new(predicate))
+	}
+
+	/// Checks if a character is kept.
+	pub fn allows(&self, c: char) -> bool {
+		(self.0)(c)
+	}
+}
+
+impl std::fmt::Debug for CharFilter {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("CharFilter")
+	}
+}
+
+/// A limit on the total time spent in OCR, see ExtractConfig::ocr_budget.
+/// Clones share the time spent, so one budget covers every file extracted with a config, from any number of threads.
+#[derive(Debug, Clone)]
+pub struct OcrBudget {
+	limit: Duration,
+	used_nanos: Arc<AtomicU64>,
+}
+
+impl OcrBudget {
+	pub fn new(limit: Duration) -> Self {
+		OcrBudget {
+			limit,
+			used_nanos: Arc::new(AtomicU64::new(0)),
+		}
+	}
+
+	/// The OCR time spent so far.
+	pub fn used(&self) -> Duration {
+		Duration::from_nanos(self.used_nanos.load(Ordering::Relaxed))
+	}
+
+	/// Checks if the OCR time spent has reached the limit. Images read when it has are not OCRed.
+	pub fn is_exhausted(&self) -> bool {
+		self.used() >= self.limit
+	}
+
+	fn add(&self, elapsed: Duration) {
+		self.used_nanos.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+	}
+}
+
+/// OCR text of the images read so far by their perceptual hash and size, so that images that look the same are read once, see ExtractConfig::ocr_image_dedup.
+/// Clones share the texts, so one cache covers every file extracted with a config, like OcrBudget.
+#[derive(Debug, Clone)]
+pub struct ImageDedup {
+	max_distance: u32,
+	texts: Arc<Mutex<Vec<(ImageHash, String)>>>,
+}
+
+impl ImageDedup {
+	/// Images of the same width and height whose hashes differ in at most `max_distance` of their 64 bits are taken as the same.
+	/// 0 matches only images that look exactly the same, about 5 also matches recompressed copies, much more than 10 matches different images.
+	pub fn new(max_distance: u32) -> Self {
+		ImageDedup {
+			max_distance,
+			texts: Arc::new(Mutex::new(Vec::new())),
+		}
+	}
+
+	/// The number of distinct images read so far.
+	pub fn len(&self) -> usize {
+		self.texts.lock().unwrap().len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	fn find(&self, (image_hash, dimensions): ImageHash) -> Option<String> {
+		self.texts.lock().unwrap().iter()
+			.find(|((x, x_dimensions), _)| *x_dimensions == dimensions && (x ^ image_hash).count_ones() <= self.max_distance)
+			.map(|(_, text)| text.clone())
+	}
+
+	fn insert(&self, image_hash: ImageHash, text: &str) {
+		self.texts.lock().unwrap().push((image_hash, text.to_string()));
+	}
+}
+
+/// A limit on the number of external programs running at once, see ExtractConfig::process_limit. A program waits to start until
+/// fewer than the limit are running. Clones share the count of running programs, so one limit covers every file extracted with a config,
+/// from any number of threads, like OcrBudget. The default limit is one for the whole process, of the number of CPUs.
+#[derive(Debug, Clone)]
+pub struct ProcessLimit {
+	max_running: usize,
+	running: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl ProcessLimit {
+	/// At most `max_running` programs run at once, at least 1.
+	pub fn new(max_running: usize) -> Self {
+		ProcessLimit {
+			max_running: max_running.max(1),
+			running: Arc::new((Mutex::new(0), Condvar::new())),
+		}
+	}
+
+	pub fn max_running(&self) -> usize {
+		self.max_running
+	}
+
+	/// Waits until fewer than `max_running` programs are running, and counts one more until the permit is dropped.
+	fn acquire(&self) -> ProcessPermit<'_> {
+		let (running, program_ended) = &*self.running;
+		let mut running = program_ended.wait_while(running.lock().unwrap(), |x| *x >= self.max_running).unwrap();
+		*running += 1;
+		ProcessPermit { limit: self }
+	}
+}
+
+impl Default for ProcessLimit {
+	fn default() -> Self {
+		static PROCESS_LIMIT: OnceLock<ProcessLimit> = OnceLock::new();
+		PROCESS_LIMIT
+			.get_or_init(|| ProcessLimit::new(thread::available_parallelism().map(|x| x.get()).unwrap_or(1)))
+			.clone()
+	}
+}
+
+/// A program counted by a ProcessLimit while it runs.
+struct ProcessPermit<'a> {
+	limit: &'a ProcessLimit,
+}
+
+impl Drop for ProcessPermit<'_> {
+	fn drop(&mut self) {
+		let (running, program_ended) = &*self.limit.running;
+		*running.lock().unwrap() -= 1;
+		program_ended.notify_one();
+	}
+}
+
+/// Runs the external programs of the extraction: pdfinfo, pdftotext, pdfimages, tesseract, the djvulibre tools...
+/// See ExtractConfig::command_runner, e.g. to run them in a sandbox or to test without them installed using MockCommandRunner.
+pub trait CommandRunner: Send + Sync + std::fmt::Debug {
+	/// Runs the command to completion and collects its output, like `Command::output`.
+	fn output(&self, command: &mut Command) -> io::Result<Output>;
+}
+
+/// Runs commands as child processes, the default CommandRunner.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemCommandRunner;
+
+impl CommandRunner for SystemCommandRunner {
+	fn output(&self, command: &mut Command) -> io::Result<Output> {
+		command.output()
+	}
+}
+
+/// A CommandRunner that runs each command within a ProcessLimit, see ExtractConfig::runner.
+#[derive(Debug)]
+struct LimitedCommandRunner<'a> {
+	runner: &'a dyn CommandRunner,
+	limit: &'a ProcessLimit,
+}
+
+impl CommandRunner for LimitedCommandRunner<'_> {
+	fn output(&self, command: &mut Command) -> io::Result<Output> {
+		let _permit = self.limit.acquire();
+		self.runner.output(command)
+	}
+}
+
+/// Answers a command of MockCommandRunner, given the program and its arguments.
+type MockResponse = dyn Fn(&str, &[String]) -> io::Result<Output> + Send + Sync;
+
+/// A CommandRunner that runs nothing, it answers each command by calling a function with the program and its arguments,
+/// and records the commands it is given. The function writes any output files the program would, e.g. the text file of pdftotext.
+pub struct MockCommandRunner {
+	respond: Box<MockResponse>,
+	calls: Mutex<Vec<(String, Vec<String>)>>,
+}
+
+impl MockCommandRunner {
+	pub fn new(respond: impl Fn(&str, &[String]) -> io::Result<Output> + Send + Sync + 'static) -> Self {
+		MockCommandRunner {
+			respond: Box::new(respond),
+			calls: Mutex::new(Vec::new()),
+		}
+	}
+
+	/// The output of a program that succeeded, writing `stdout` and nothing to stderr.
+	pub fn success(stdout: &str) -> Output {
+		Output {
+			status: Default::default(),
+			stdout: stdout.as_bytes().to_vec(),
+			stderr: Vec::new(),
+		}
+	}
+
+	/// The commands run so far, as the program and its arguments, in order.
+	pub fn calls(&self) -> Vec<(String, Vec<String>)> {
+		self.calls.lock().unwrap().clone()
+	}
+}
+
+impl std::fmt::Debug for MockCommandRunner {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("MockCommandRunner")
+			.field("calls", &self.calls)
+			.finish()
+	}
+}
+
+impl CommandRunner for MockCommandRunner {
+	fn output(&self, command: &mut Command) -> io::Result<Output> {
+		let program = command.get_program().to_string_lossy().to_string();
+		let args: Vec<String> = command.get_args().map(|x| x.to_string_lossy().to_string()).collect();
+		let output = (self.respond)(&program, &args);
+		self.calls.lock().unwrap().push((program, args));
+		output
+	}
+}
+
+/// The text of a FileListItem, or why it has none.
+///
+/// Serialized with a `status` tag, e.g. `{"status": "extracted", "text": "..."}` or `{"status": "unchanged"}`.
+/// The earlier form, a string for the text or null for an unchanged file, is still read.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", content = "text", rename_all = "snake_case")]
+pub enum TextContents {
+	/// Text read from the file, empty if the file has none
+	Extracted(String),
+	/// Not read again, the crc matches the pre-scanned item
+	Unchanged,
+	/// Not read, with the reason, e.g. `encrypted, not extracted`
+	Skipped(String),
+	/// Reading the file failed, with the error
+	Error(String),
+}
+
+impl Default for TextContents {
+	fn default() -> Self {
+		TextContents::Extracted(String::new())
+	}
+}
+
+impl<'de> Deserialize<'de> for TextContents {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		#[derive(Deserialize)]
+		#[serde(tag = "status", content = "text", rename_all = "snake_case")]
+		enum Tagged {
+			Extracted(String),
+			Unchanged,
+			Skipped(String),
+			Error(String),
+		}
+		#[derive(Deserialize)]
+		#[serde(untagged)]
+		enum Format {
+			Tagged(Tagged),
+			Legacy(Option<String>),
+		}
+		Ok(match Format::deserialize(deserializer)? {
+			Format::Tagged(Tagged::Extracted(text)) | Format::Legacy(Some(text)) => TextContents::Extracted(text),
+			Format::Tagged(Tagged::Unchanged) | Format::Legacy(None) => TextContents::Unchanged,
+			Format::Tagged(Tagged::Skipped(reason)) => TextContents::Skipped(reason),
+			Format::Tagged(Tagged::Error(error)) => TextContents::Error(error),
+		})
+	}
+}
+
+impl TextContents {
+	/// The extracted text, None for any other state.
+	pub fn text(&self) -> Option<&str> {
+		match self {
+			TextContents::Extracted(text) => Some(text),
+			_ => None,
+		}
+	}
+
+	/// Extracted text, or when there is none the reason it was not read, the error before the note.
+	fn from_extraction(text: String, note: &Option<String>, error: &Option<String>) -> TextContents {
+		match (error, note) {
+			(Some(error), _) if text.is_empty() => TextContents::Error(error.clone()),
+			(_, Some(note)) if text.is_empty() => TextContents::Skipped(note.clone()),
+			_ => TextContents::Extracted(text),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct FileListItem {
+	pub filename: String,
+	pub parent_files: Vec<String>,
+	pub crc: i64,
+	pub size: i64,
+	pub text_contents: TextContents,
+	/// Why no text was extracted from this file, e.g. `encrypted, not extracted`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub note: Option<String>,
+	/// Error hit while reading this file, e.g. a truncated archive. Any entries that could still be read are listed as usual.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+	/// Names of the subfiles merged into this item when flattened, empty otherwise
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub contained_files: Vec<String>,
+	/// How the text was obtained, e.g. `pdftotext` for a PDF text layer, `ocr`, `docx xml`, `calamine` or `text` for a file read as it is.
+	/// None if no text was extracted.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub method: Option<String>,
+	/// Name of the encoding a text file was decoded from, e.g. `UTF-8` or `windows-1252`. None for files that are not read as text.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub encoding: Option<String>,
+	/// Only part of this file's text was read, see ExtractConfig::max_text_bytes and ExtractConfig::log_tail_lines
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub truncated: bool,
+	/// The file's magic bytes disagree with its extension, see ExtractConfig::detect_type_mismatch. Only set with that option.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub type_mismatch: bool,
+	/// The search term found in this file's text, see ExtractConfig::search_terms. The text is then just the context of the match.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub matched_term: Option<String>,
+	/// Where a copy of this file's original bytes was stored, see ExtractConfig::preserve_subfiles_dir
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub preserved_path: Option<PathBuf>,
+	/// The text split into segments, see ExtractConfig::segmentation. Only set when ExtractConfig::segment_separator is None.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub segments: Vec<String>,
+	/// The file holds code that runs when it is opened or used, the JavaScript or Launch actions of a PDF, a warning sign in a file
+	/// from outside. Only PDFs are checked, see ExtractConfig::pdf_scripts to list the scripts.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub has_scripts: bool,
+	/// When the archive entry this file came from was last modified, in seconds since the Unix epoch, as recorded in the zip, 7z, tar or gzip.
+	/// Files within an entry, such as the pages of a zipped PDF or the entries of a nested archive without their own, have the entry's time.
+	/// None for files that are not in an archive, or an entry with no time.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub modified: Option<i64>,
+	/// What the file holds, whatever its format: `email`, `spreadsheet`, `presentation`, `document`, `scanned image` (text read by OCR,
+	/// or a DjVu), `image`, `drawing`, `archive`, `source code` or `text`. Text generated from a file, such as an email body or a sheet,
+	/// has the class of that file. Only set with ExtractConfig::classify_content, and None for binary and empty files.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub content_class: Option<String>,
+	/// The start of the text before it was cleaned, see ExtractConfig::raw_text_max_bytes. Only set with that option,
+	/// for text that is cleaned: text files and text generated from a file, such as spreadsheet sheets.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub raw_text: Option<String>,
+}
+
+/// Options controlling a directory scan and the text extraction of each file.
+#[derive(Debug, Clone)]
+pub struct ExtractConfig {
+	/// Files (and subfiles) larger than this many bytes are listed but not extracted.
+	pub max_file_size: u64,
+	/// Read at most this many bytes of text (as UTF-8) from each text file, e.g. for logs and data dumps of gigabytes of repetitive text.
+	/// The reading stops there, and the item is marked `truncated`. None (the default) reads all the text.
+	pub max_text_bytes: Option<u64>,
+	/// Read only the last this many lines of each log file, `.log` or a rotated `.log.1`, `.log.2`..., including logs in a `.log.gz`.
+	/// The log is read a line at a time, keeping just those lines, and the item is marked `truncated` if earlier lines were dropped.
+	/// Replaces `max_text_bytes` for logs. None (the default) reads the whole log, also a line at a time, each line cleaned as it is read.
+	pub log_tail_lines: Option<usize>,
+	/// Merge all the FileListItems of each top-level file into a single item, see flatten_file_list_items.
+	pub flatten: bool,
+	/// With `flatten`, written before the text of each subfile to mark where it starts, `{filename}` replaced by the subfile's name,
+	/// e.g. `\n----- {filename} -----\n`. None (the default) joins the texts with just a newline.
+	pub member_header: Option<String>,
+	/// Add the directories of a file inside a zip or 7z archive to its `parent_files`, after the archive name,
+	/// e.g. `["archive.zip", "reports", "2023"]` for `reports/2023/data.csv`.
+	pub archive_paths_in_lineage: bool,
+	/// Only the members of zip, 7z and tar archives whose path in the archive matches one of these glob patterns are written and read,
+	/// e.g. `*.txt` or `*/manifest.json`, where `*` also matches `/`. A nested archive is only read if it matches too.
+	/// The other members are left out of the results. Empty (the default) reads every member.
+	pub archive_member_patterns: Vec<String>,
+	/// Only files whose detected type is in this list are scanned, e.g. `["pdf", "msg", "eml"]`. Empty scans every type.
+	pub include_extensions: Vec<String>,
+	/// Files whose detected type is in this list are not scanned.
+	pub exclude_extensions: Vec<String>,
+	/// Files (and subfiles) with one of these extensions, or detected as one of these types, are listed with their size and crc but no text.
+	/// Defaults to common audio, video and executable types.
+	pub skip_text_extensions: Vec<String>,
+	/// Only the attachments of eml and msg files whose detected type is in this list are read, e.g. `["pdf", "docx", "zip", "eml"]`
+	/// to read documents, archives and attached emails but not media or executables. The others are listed with their size and crc
+	/// but no text, and nothing inside them is read. Empty (the default) reads every attachment.
+	pub attachment_extensions: Vec<String>,
+	/// Attachments of eml and msg files larger than this many bytes are listed with their size and crc but not read.
+	/// None (the default) leaves them to `max_file_size`.
+	pub max_attachment_size: Option<u64>,
+	/// List the internet headers of a msg file, as they were received, with its conversation topic, categories, companies and
+	/// billing information, as a `[Headers]` subfile after the body. Only the properties that are set are listed.
+	pub msg_headers: bool,
+	/// Keep non-ASCII text as decoded, instead of converting accented characters to their base letters and dropping the rest.
+	/// All printable Unicode is kept, only control characters other than whitespace, the U+FFFD replacement character written
+	/// for invalid byte sequences, byte order marks and noncharacters are dropped.
+	pub preserve_unicode: bool,
+	/// Keep only the characters this filter allows, e.g. `CharFilter::new(|c| !c.is_control() || c.is_whitespace())`.
+	/// Replaces the default cleaning of accented characters to base letters and dropping all but ASCII graphic characters and whitespace,
+	/// `preserve_unicode` is then ignored. None (the default) uses the default cleaning.
+	pub allowed_chars: Option<CharFilter>,
+	/// Keep up to this many bytes of each file's text as it was before cleaning, i.e. before the conversion of accented characters
+	/// and dropping of non-ASCII, or the `preserve_unicode` or `allowed_chars` filter, in FileListItem::raw_text. For debugging,
+	/// to tell whether wrong text comes from the extraction or from the cleaning. None (the default) keeps none.
+	pub raw_text_max_bytes: Option<usize>,
+	/// Text extractors by file type, see ExtractorRegistry.
+	pub extractors: ExtractorRegistry,
+	/// Read a `.txt` file whose first lines all have the same number of tabs, such as a database export or a sheet saved as
+	/// "Text (Tab delimited)", as a table: its cells trimmed and written with `cell_separator` and `row_separator`, as the cells of a
+	/// spreadsheet are. Off by default, such files are read as plain text.
+	pub tab_delimited_text: bool,
+	/// Written between the cells of a spreadsheet row, and of a row of tab delimited text, see `tab_delimited_text`.
+	pub cell_separator: String,
+	/// Written after each spreadsheet row, and each row of tab delimited text.
+	pub row_separator: String,
+	/// Read at most this many rows, those with text, of each spreadsheet sheet, e.g. for workbooks of a million rows.
+	/// The sheet is marked `truncated` if rows were left out. None (the default) reads every row.
+	pub max_sheet_rows: Option<usize>,
+	/// Read the rows of each spreadsheet sheet up to this many cells with text, e.g. for a few very wide rows.
+	/// The sheet is marked `truncated` if rows were left out. None (the default) reads every cell.
+	pub max_sheet_cells: Option<usize>,
+	/// Written before each docx and odt paragraph.
+	pub paragraph_separator: String,
+	/// For files of no known type that contain NUL bytes, extract the runs of at least this many printable ASCII characters,
+	/// like the `strings` command. None (the default) lists such files with the note `binary, not extracted` and no text.
+	pub binary_strings_min_len: Option<usize>,
+	/// PDF pages whose text layer has fewer than this many non-whitespace characters are also rendered and read by OCR,
+	/// as for pages that don't allow copying text. 0 (the default) trusts any text layer.
+	pub ocr_fallback_min_text_len: usize,
+	/// Also read by OCR the PDF pages whose text layer looks garbled, as from a font without a ToUnicode map. Such pages are noted
+	/// `likely unextractable text layer` either way, their text is kept.
+	pub ocr_garbled_text_layer: bool,
+	/// The format PDF pages are rendered and PDF images written in to be read by OCR, trading disk space against the time to write
+	/// and read them. PNG (the default) is the smallest. Only used on Linux, xpdf's pdftopng on Windows always writes PNG.
+	pub pdf_ocr_image_format: PdfOcrImageFormat,
+	/// extract_text_from_file_streaming hands over the items extracted so far once their text reaches this many bytes,
+	/// which bounds the text held in memory to about this much plus one subfile. None (the default) hands over all the items of a file at once.
+	/// Ignored with `flatten`, which needs every item.
+	pub stream_batch_text_len: Option<usize>,
+	/// Number of files extract_text_from_files and scan_directory work on at the same time, which also bounds the OCR processes
+	/// running at once to about this many. 1 (the default) extracts them one after another.
+	pub file_threads: usize,
+	/// Images with more pixels than this are downscaled to about this many before OCR, so that tesseract's memory use stays bounded.
+	/// An image too large to be decoded for downscaling is not read, with the note `image too large, not extracted`. 0 reads images at any size.
+	pub ocr_max_image_pixels: u64,
+	/// Extract the images embedded in docx, odt and pptx documents, and the pictures in the RTF body of msg files, which are then read by OCR.
+	/// When false they are not listed at all, for a fast text only extraction of documents full of pictures or scanned pages. Defaults to true.
+	pub ocr_embedded_images: bool,
+	/// A limit on the total time spent in OCR by every file extracted with this config, e.g. to bound the run time of a batch job.
+	/// Once it is spent, images are listed with the note `OCR budget exhausted, not extracted` instead of being read. None (the default) has no limit.
+	pub ocr_budget: Option<OcrBudget>,
+	/// Read images that look the same once, reusing the text of the first for the others, e.g. a logo on every page or a watermark,
+	/// even when they differ in a few bytes from being recompressed. This trades accuracy for speed: a small hash can take two images
+	/// with different text for the same, so only images of the same size are matched, and the pages rendered for OCR never are.
+	/// None (the default) reads every image.
+	pub ocr_image_dedup: Option<ImageDedup>,
+	/// tesseract page segmentation mode (`--psm`), e.g. 6 for a single block of text such as a receipt or 11 for sparse text such as a label.
+	/// None uses tesseract's default, fully automatic segmentation.
+	pub ocr_psm: Option<u8>,
+	/// tesseract OCR engine mode (`--oem`), e.g. 1 for the LSTM engine only. None uses tesseract's default.
+	pub ocr_oem: Option<u8>,
+	/// A second page segmentation mode to read each image with, the pass that finds more text is kept. Doubles the OCR time.
+	pub ocr_alternate_psm: Option<u8>,
+	/// Rejoin words that PDF text splits across lines with a hyphen, e.g. `inter-\nnational`, so that they can be searched for.
+	/// Off by default, as genuine hyphenated compounds that wrap at the hyphen are joined too.
+	pub dehyphenate_pdf_text: bool,
+	/// List the JavaScript and Launch actions of a PDF as a `[Scripts]` subfile, `Scripts:` then a `JavaScript: script` or
+	/// `Launch: program parameters` line per action. FileListItem::has_scripts is set either way.
+	pub pdf_scripts: bool,
+	/// Merge the text layer of the pages of a PDF into one subfile, named `pages`, with this marker written before the text of
+	/// each page, `{page}` replaced by the page number, e.g. `\n[Page {page}]\n`. The page images, OCR text and annotations are
+	/// still listed on their own. None (the default) lists a subfile per page.
+	pub pdf_page_marker: Option<String>,
+	/// Put PDF and OCR text that is mostly right-to-left (Hebrew, Arabic...) into logical order, for documents that store it in visual order,
+	/// where it reads reversed. Text that is already in logical order would be reversed, so this is off by default.
+	/// Needs `preserve_unicode` or `allowed_chars` to keep the right-to-left letters.
+	pub rtl_logical_order: bool,
+	/// Extract the comments of PDF annotations (sticky notes, highlights...) as a `page N annotations` item after each page's text.
+	/// Defaults to false.
+	pub pdf_annotations: bool,
+	/// List the comments of a zip archive and of its entries, which can hold notes such as build information or provenance,
+	/// as the text of the archive's own item.
+	pub archive_comments: bool,
+	/// Passwords tried in turn on each encrypted zip entry, ZipCrypto or AES. Entries that none of them decrypts are listed
+	/// with the note `encrypted, not extracted`, and the rest of the archive is still read.
+	/// An encrypted 7z is tried with each of them in turn, and is listed with that note when none opens it.
+	pub archive_passwords: Vec<String>,
+	/// Check the magic bytes of every file against its extension and set `type_mismatch` on the items of files that are mislabeled,
+	/// e.g. an `invoice.pdf` that is a zip, a sign of renamed files. Files are still read as the type their content is detected as.
+	pub detect_type_mismatch: bool,
+	/// Render Markdown files to plain text, without their syntax (`#` headings, `*` emphasis, code fences, link targets...).
+	/// Off by default, Markdown is then read as it is, like any text file.
+	pub markdown_to_text: bool,
+	/// With `markdown_to_text`, write the URL of each link after its text, e.g. `docs (https://example.com)`.
+	pub markdown_link_urls: bool,
+	/// Search for these terms instead of keeping the full text, e.g. to triage which documents mention something.
+	/// ASCII letters match in any case. The first file (or subfile) whose text contains a term is listed with just the text around it
+	/// and the term in `matched_term`, and the rest of the subfiles are not read. The pages of a PDF after the one with a term
+	/// are not converted either. Files read before it keep no text.
+	/// Empty (the default) keeps the full text.
+	pub search_terms: Vec<String>,
+	/// Split the text of each file into sentences, lines or tokens, for search systems that index segments rather than whole texts.
+	pub segmentation: Segmentation,
+	/// With `segmentation`, the text becomes the segments joined with this separator. None keeps the text as it is and lists the
+	/// segments in FileListItem::segments instead. Defaults to a line break, a segment per line.
+	pub segment_separator: Option<String>,
+	/// Write every line break of the extracted text with this line ending, whatever the file or tool it came from, e.g. the `\r\n`
+	/// of a Windows text file or the `\n` of pdftotext, as in flattened items. Some(LineEnding::default()) is `\n`.
+	/// None (the default) keeps the line endings as each file has them.
+	pub line_ending: Option<LineEnding>,
+	/// Tag each file with a coarse content class, FileListItem::content_class, e.g. to bucket the files of a scan by what they hold.
+	pub classify_content: bool,
+	/// Copy the original bytes of each archive member, attachment and embedded file to this directory, named after its crc,
+	/// e.g. `00a1b2c3d4e5f607.pdf`, and record the copy in `preserved_path`. The copy is made before the temp file is deleted,
+	/// temp files are deleted as usual. Nested archives are not copied, their members are, nor is text generated from a file,
+	/// such as PDF pages, or the top-level file. None (the default) keeps no copies.
+	pub preserve_subfiles_dir: Option<PathBuf>,
+	/// Which symlinks scan_directory follows. Symlink loops are detected and skipped whatever the policy. Defaults to scanning the symlinks
+	/// to files and not walking the symlinks to directories.
+	pub symlinks: SymlinkPolicy,
+	/// Scan a file found under several names once, as hard links or through symlinks, by its device and inode. Only on Unix.
+	pub dedupe_hardlinks: bool,
+	/// Before extract_text_from_files, and so scan_directory, starts, remove the temp folders that earlier runs which crashed or were killed
+	/// left behind, those in which nothing has been modified for this long. The folders of runs still going are kept. Defaults to a day,
+	/// None leaves all temp folders.
+	pub stale_temp_dir_age: Option<Duration>,
+	/// Leave out the items of containers that had subfiles extracted from them, e.g. a zip or a docx, so that only the leaf files are listed.
+	/// Containers with nothing extracted from them are kept. With `flatten`, the first remaining item then names the flattened item.
+	pub exclude_containers: bool,
+	/// Adds up the counts and times of every file extracted with this config, e.g. for monitoring. None (the default) keeps no totals.
+	pub summary: Option<SummaryRecorder>,
+	/// Runs the external programs, pdftotext, tesseract and the like. Defaults to SystemCommandRunner, replace it to stub them out in tests.
+	pub command_runner: Arc<dyn CommandRunner>,
+	/// Bounds the external programs running at once, across every file extracted with this config and its clones, whatever the
+	/// number of `file_threads` or the nesting of the files, e.g. an archive of thousands of scanned PDFs. Defaults to one limit
+	/// of the number of CPUs, shared by every config of the process.
+	pub process_limit: ProcessLimit,
+}
+
+impl Default for ExtractConfig {
+	fn default() -> Self {
+		ExtractConfig {
+			max_file_size: MAX_FILE_SIZE,
+			max_text_bytes: None,
+			log_tail_lines: None,
+			flatten: false,
+			member_header: None,
+			archive_paths_in_lineage: false,
+			archive_member_patterns: Vec::new(),
+			include_extensions: Vec::new(),
+			exclude_extensions: Vec::new(),
+			skip_text_extensions: DEFAULT_SKIP_TEXT_EXTENSIONS.iter().map(|x| x.to_string()).collect(),
+			attachment_extensions: Vec::new(),
+			max_attachment_size: None,
+			msg_headers: false,
+			preserve_unicode: false,
+			allowed_chars: None,
+			raw_text_max_bytes: None,
+			extractors: ExtractorRegistry::default(),
+			tab_delimited_text: false,
+			cell_separator: String::from("\t"),
+			row_separator: String::from("\n"),
+			max_sheet_rows: None,
+			max_sheet_cells: None,
+			paragraph_separator: String::from(DEFAULT_PARAGRAPH_SEPARATOR),
+			binary_strings_min_len: None,
+			ocr_fallback_min_text_len: 0,
+			ocr_garbled_text_layer: false,
+			pdf_ocr_image_format: PdfOcrImageFormat::default(),
+			stream_batch_text_len: None,
+			file_threads: 1,
+			dehyphenate_pdf_text: false,
+			pdf_scripts: false,
+			pdf_page_marker: None,
+			rtl_logical_order: false,
+			pdf_annotations: false,
+			ocr_max_image_pixels: OCR_MAX_IMAGE_PIXELS,
+			ocr_embedded_images: true,
+			ocr_budget: None,
+			ocr_image_dedup: None,
+			ocr_psm: None,
+			ocr_oem: None,
+			ocr_alternate_psm: None,
+			archive_comments: false,
+			archive_passwords: Vec::new(),
+			detect_type_mismatch: false,
+			markdown_to_text: false,
+			markdown_link_urls: false,
+			search_terms: Vec::new(),
+			segmentation: Segmentation::None,
+			segment_separator: Some(String::from("\n")),
+			line_ending: None,
+			classify_content: false,
+			preserve_subfiles_dir: None,
+			symlinks: SymlinkPolicy::default(),
+			dedupe_hardlinks: false,
+			stale_temp_dir_age: Some(STALE_TEMP_DIR_AGE),
+			exclude_containers: false,
+			summary: None,
+			command_runner: Arc::new(SystemCommandRunner),
+			process_limit: ProcessLimit::default(),
+		}
+	}
+}
+
+impl ExtractConfig {
+	/// Checks the extension of a detected file type (as returned by get_effective_file_type) against the include and exclude lists.
+	/// Extensions of the same type, e.g. `jpg` and `jpeg`, match each other.
+	pub fn is_extension_wanted(&self, effective_file_extension: &str) -> bool {
+		let file_type = FileType::from_extension(effective_file_extension);
+		if !self.include_extensions.is_empty()
+			&& !self.include_extensions.iter().any(|x| FileType::from_extension(x) == file_type)
+		{
+			return false;
+		}
+		!self.exclude_extensions.iter().any(|x| FileType::from_extension(x) == file_type)
+	}
+
+	/// Checks the path of a member in an archive, e.g. `reports/2023/data.csv`, against `archive_member_patterns`.
+	/// A pattern that is not a valid glob matches just the path it is.
+	pub fn is_archive_member_wanted(&self, member_path: &str) -> bool {
+		if self.archive_member_patterns.is_empty() {
+			return true;
+		}
+		let member_path = member_path.replace('\\', "/");
+		let member_path = member_path.trim_start_matches('/');
+		self.archive_member_patterns.iter().any(|pattern| match glob::Pattern::new(pattern) {
+			Ok(glob_pattern) => glob_pattern.matches(member_path),
+			Err(_) => pattern == member_path,
+		})
+	}
+
+	/// Checks the extension of the detected type of an email attachment against `attachment_extensions`.
+	/// Extensions of the same type match each other.
+	pub fn is_attachment_wanted(&self, effective_file_extension: &str) -> bool {
+		let file_type = FileType::from_extension(effective_file_extension);
+		self.attachment_extensions.is_empty() || self.attachment_extensions.iter().any(|x| FileType::from_extension(x) == file_type)
+	}
+
+	/// The command_runner, running each command within the process_limit.
+	fn runner(&self) -> LimitedCommandRunner<'_> {
+		LimitedCommandRunner {
+			runner: self.command_runner.as_ref(),
+			limit: &self.process_limit,
+		}
+	}
+
+	/// Checks an extension against `skip_text_extensions`. Extensions of the same type match each other.
+	pub fn is_text_extraction_skipped(&self, extension: &str) -> bool {
+		let file_type = FileType::from_extension(extension);
+		self.skip_text_extensions.iter().any(|x| FileType::from_extension(x) == file_type)
+	}
+}
+
+/// How much work extracting the text of a file is, see estimate_work.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WorkEstimate {
+	/// Files that would be listed, the file itself, archive members, attachments and generated pages included
+	pub files: usize,
+	/// Pages of the PDFs, the file itself or any inside it
+	pub pdf_pages: usize,
+	/// Images that would be read by OCR, embedded in documents. Pages rendered for OCR, when their text layer is too thin,
+	/// are not known until the pages are converted and are not counted.
+	pub images: usize,
+	/// Total size of the files, in bytes
+	pub bytes: u64,
+}
+
+/// Totals of an extraction run, see ExtractConfig::summary.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ExtractSummary {
+	/// Top-level files extracted, e.g. the files found by a directory scan
+	pub top_level_files: usize,
+	/// Top-level files that could not be extracted at all, e.g. a zip without a readable directory. Their items are missing.
+	pub failed_files: usize,
+	/// Files listed, the top-level files and every archive member, attachment and generated page in them, as FileListItems before any flatten
+	pub files: usize,
+	/// Files with their text read, see TextContents::Extracted
+	pub extracted: usize,
+	/// Files not read again as they are unchanged since the pre-scanned items
+	pub unchanged: usize,
+	/// Files not read, e.g. too large or encrypted
+	pub skipped: usize,
+	/// Files whose reading failed
+	pub errored: usize,
+	/// Total size of the files listed, in bytes. Archive members count as well as their archives.
+	pub bytes: u64,
+	/// Files read by OCR, images and rendered pages, with those that reused the text of an image that looks the same
+	pub ocr_files: usize,
+	/// Time spent walking directories in scan_directory
+	pub walk_time: Duration,
+	/// Time spent listing the files in each top-level file: unpacking archives, and converting documents, such as PDF pages with pdftotext
+	pub unpack_time: Duration,
+	/// Time spent reading the text of the files listed, OCR included
+	pub read_time: Duration,
+}
+
+/// Adds up the totals of every extraction done with a config, see ExtractConfig::summary.
+/// Clones share the totals, so one recorder covers every file extracted with a config, from any number of threads, like OcrBudget.
+/// The times of files extracted at the same time add up, so they can be more than the time the run took.
+#[derive(Debug, Clone, Default)]
+pub struct SummaryRecorder {
+	summary: Arc<Mutex<ExtractSummary>>,
+}
+
+impl SummaryRecorder {
+	pub fn new() -> Self {
+		SummaryRecorder::default()
+	}
+
+	/// The totals so far.
+	pub fn summary(&self) -> ExtractSummary {
+		self.summary.lock().unwrap().clone()
+	}
+
+	fn record(&self, update: impl FnOnce(&mut ExtractSummary)) {
+		update(&mut self.summary.lock().unwrap());
+	}
+
+	fn record_items(&self, items: &[FileListItem]) {
+		self.record(|summary| {
+			for item in items {
+				summary.files += 1;
+				summary.bytes += item.size.max(0) as u64;
+				match item.text_contents {
+					TextContents::Extracted(_) => summary.extracted += 1,
+					TextContents::Unchanged => summary.unchanged += 1,
+					TextContents::Skipped(_) => summary.skipped += 1,
+					TextContents::Error(_) => summary.errored += 1,
+				}
+				if item.method.as_deref() == Some("ocr") {
+					summary.ocr_files += 1;
+				}
+			}
+		});
+	}
+}
+
+/// Everything found in a file, listed before any text is read, see extract_text_from_file_with_manifest.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ArchiveManifest {
+	/// The file itself first, then each container followed by its members, as in the FileListItems
+	pub entries: Vec<ManifestEntry>,
+}
+
+/// A file found in an ArchiveManifest.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ManifestEntry {
+	pub filename: String,
+	/// The containers this file was found in, outermost first, as in FileListItem::parent_files
+	pub parent_files: Vec<String>,
+	/// 0 for the file itself, 1 for its members, and so on, the length of parent_files
+	pub depth: usize,
+	pub size: u64,
+	pub crc: i64,
+	/// The detected type, e.g. `msg`, see FileType
+	pub file_type: String,
+	/// Its members are entries of their own, e.g. an archive or a message with attachments
+	pub is_container: bool,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub note: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+/// A file that could not be extracted at all, see ScanReport::errors.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FileError {
+	pub path: PathBuf,
+	pub error: String,
+}
+
+/// Combined results of a directory scan.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ScanReport {
+	pub root: PathBuf,
+	pub items: Vec<FileListItem>,
+	/// The files that failed to extract, or could not be read while walking the directory, with why. They have no items.
+	/// Errors within a file that was extracted, such as a damaged archive member, are in FileListItem::error instead.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub errors: Vec<FileError>,
+}
+
+/// Walks `root` recursively and extracts text from every file found, aggregating all results into one report.
+///
+/// # Arguments
+///
+/// * `root` - The directory to walk
+/// * `config` - Extraction options, applied to every file. The files are extracted `config.file_threads` at a time.
+/// * `keep_going` - Cleared to stop the scan early, the report then holds the files processed so far
+///
+/// # Returns
+///
+/// * A ScanReport with the FileListItems of every file, in file name order whatever the number of threads,
+///   and the files that failed to extract or to be read, in ScanReport::errors. Only an unreadable `root` fails the scan.
+pub fn scan_directory(root: &Path, config: &ExtractConfig, keep_going: Arc<AtomicBool>) -> Result<ScanReport, Box<dyn Error>> {
+	let mut paths: Vec<PathBuf> = Vec::new();
+	let mut errors: Vec<FileError> = Vec::new();
+	let walk_start = Instant::now();
+	let canonical_root = fs::canonicalize(root)?;
+	#[cfg(unix)]
+	let mut seen_inodes: HashSet<(u64, u64)> = HashSet::new();
+
+	//symlinks are checked before they are followed, so a directory symlink out of the root is not walked at all
+	let walker = WalkDir::new(root)
+		.follow_links(matches!(config.symlinks, SymlinkPolicy::FollowWithinRoot | SymlinkPolicy::FollowAll))
+		.sort_by_file_name()
+		.into_iter()
+		.filter_entry(|entry| entry.depth() == 0 || !entry.path_is_symlink() || match config.symlinks {
+			SymlinkPolicy::Files => true,
+			SymlinkPolicy::Skip => false,
+			SymlinkPolicy::FollowWithinRoot => fs::canonicalize(entry.path()).is_ok_and(|x| x.starts_with(&canonical_root)),
+			SymlinkPolicy::FollowAll => true,
+		});
+	for entry in walker {
+		if !keep_going.load(Ordering::Relaxed) {
+			break;
+		}
+		//symlink loops are reported as errors by WalkDir, instead of being followed
+		let entry = match entry {
+			Ok(entry) => entry,
+			Err(e) => {
+				warn!("Skipping {:?} in scan of {:?}: {}", e.path().unwrap_or(root), root, e);
+				errors.push(FileError { path: e.path().unwrap_or(root).to_path_buf(), error: e.to_string() });
+				continue;
+			}
+		};
+		let path = entry.path();
+		// Process only files (not directories)
+		if !path.is_file() {
+			continue;
+		}
+		let metadata = match path.metadata() {
+			Ok(metadata) => metadata,
+			Err(e) => {
+				warn!("Skipping {:?} in scan of {:?}: {}", path, root, e);
+				errors.push(FileError { path: path.to_path_buf(), error: e.to_string() });
+				continue;
+			}
+		};
+		#[cfg(unix)]
+		if config.dedupe_hardlinks {
+			use std::os::unix::fs::MetadataExt;
+			if !seen_inodes.insert((metadata.dev(), metadata.ino())) {
+				debug!("Skipping file {:?}, the same file was already found under another name", path);
+				continue;
+			}
+		}
+		if metadata.len() > config.max_file_size {
+			info!("Skiping file {:?} due to large size.", path);
+			continue;
+		}
+		// filter on the detected type rather than the path extension, so renamed files are handled correctly
+		let file_type = get_effective_file_type(path);
+		if !config.is_extension_wanted(file_type.extension()) {
+			debug!("Skipping file {:?} of unwanted type {:?}", path, file_type);
+			continue;
+		}
+		//the later parts of a split archive are read with the first, unless some are missing
+		if split_archive(path).is_some_and(|x| x.part > 1 && x.is_complete()) {
+			debug!("Skipping split archive part {:?}, read with the first part", path);
+			continue;
+		}
+		paths.push(path.to_path_buf());
+	}
+	if let Some(summary) = &config.summary {
+		summary.record(|x| x.walk_time += walk_start.elapsed());
+	}
+
+	let (items, extract_errors) = extract_text_from_files_with_errors(&paths, config, &[], keep_going);
+	errors.extend(extract_errors);
+	Ok(ScanReport {
+		root: root.to_path_buf(),
+		items,
+		errors,
+	})
+}
+
+/// Extracts text from each file of a list with the same config, `config.file_threads` files at a time.
+///
+/// # Arguments
+///
+/// * `paths` - The files to extract
+/// * `pre_scanned_items` - FileListItems from a previous run over any of the files. Each file is only checked against its own items,
+///   the ones named after it or with it first in `parent_files`, and its unchanged subfiles are skipped: they are listed as
+///   TextContents::Unchanged without being read, so an unchanged image is not read by OCR again. The members of an archive are still
+///   unpacked to find which of them changed.
+/// * `keep_going` - Cleared to stop early, the result then holds the files processed so far
+///
+/// # Returns
+///
+/// * The FileListItems of every file, in the order of `paths`. Files that fail to extract are logged and skipped,
+///   see extract_text_from_files_with_errors to list them.
+pub fn extract_text_from_files(paths: &[PathBuf], config: &ExtractConfig, pre_scanned_items: &[FileListItem], keep_going: Arc<AtomicBool>) -> Vec<FileListItem> {
+	extract_text_from_files_with_errors(paths, config, pre_scanned_items, keep_going).0
+}
+
+/// Extracts text from each file of a list like extract_text_from_files, and also returns the files that failed to extract,
+/// e.g. to report `47000 files extracted, 12 failed` along with the failures.
+///
+/// # Returns
+///
+/// * The FileListItems of every file, in the order of `paths`, and a FileError per file that failed to extract, in the same order
+pub fn extract_text_from_files_with_errors(paths: &[PathBuf], config: &ExtractConfig, pre_scanned_items: &[FileListItem], keep_going: Arc<AtomicBool>) -> (Vec<FileListItem>, Vec<FileError>) {
+	if let Some(max_age) = config.stale_temp_dir_age {
+		let removed = remove_stale_temp_dirs(&tempfiles_location(), max_age);
+		if removed > 0 {
+			info!("Removed {} temp folders left by earlier runs", removed);
+		}
+	}
+	let extract_one = |path: &PathBuf| -> Result<Vec<FileListItem>, String> {
+		let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+		let file_pre_scanned_items: Vec<FileListItem> = pre_scanned_items.iter()
+			.filter(|x| x.parent_files.first().unwrap_or(&x.filename) == &file_name)
+			.cloned()
+			.collect();
+		extract_text_from_file_with_config(path, file_pre_scanned_items, keep_going.clone(), config).map_err(|e| {
+			error!("Error extracting text from file {:?}: {}", path, e);
+			e.to_string()
+		})
+	};
+
+	let mut results: Vec<Result<Vec<FileListItem>, String>> = Vec::with_capacity(paths.len());
+	if config.file_threads <= 1 {
+		for path in paths {
+			if !keep_going.load(Ordering::Relaxed) {
+				break;
+			}
+			results.push(extract_one(path));
+		}
+	} else {
+		//workers take the next unclaimed file, results are put back in path order
+		results.resize_with(paths.len(), || Ok(Vec::new()));
+		let next_index = AtomicUsize::new(0);
+		thread::scope(|scope| {
+			let workers: Vec<_> = (0..config.file_threads.min(paths.len()))
+				.map(|_| scope.spawn(|| {
+					let mut done: Vec<(usize, Result<Vec<FileListItem>, String>)> = Vec::new();
+					while keep_going.load(Ordering::Relaxed) {
+						let index = next_index.fetch_add(1, Ordering::Relaxed);
+						if index >= paths.len() {
+							break;
+						}
+						done.push((index, extract_one(&paths[index])));
+					}
+					done
+				}))
+				.collect();
+			for worker in workers {
+				match worker.join() {
+					Ok(done) => {
+						for (index, file_list_items) in done {
+							results[index] = file_list_items;
+						}
+					}
+					Err(e) => error!("Extraction thread panicked: {:?}", e),
+				}
+			}
+		});
+	}
+
+	let mut items: Vec<FileListItem> = Vec::new();
+	let mut errors: Vec<FileError> = Vec::new();
+	for (path, result) in paths.iter().zip(results) {
+		match result {
+			Ok(file_list_items) => items.extend(file_list_items),
+			Err(error) => errors.push(FileError { path: path.clone(), error }),
+		}
+	}
+	(items, errors)
+}
+
+pub fn extract_text_from_file(filepath: &Path, pre_scanned_items: Vec<FileListItem>, keep_going: Arc<AtomicBool>) -> Result<Vec<FileListItem>, Box<dyn Error>> {
+	extract_text_from_file_with_config(filepath, pre_scanned_items, keep_going, &ExtractConfig::default())
+}
+
+pub fn extract_text_from_file_with_config(filepath: &Path, pre_scanned_items: Vec<FileListItem>, keep_going: Arc<AtomicBool>, config: &ExtractConfig) -> Result<Vec<FileListItem>, Box<dyn Error>> {
+	let mut file_list_items: Vec<FileListItem> = Vec::new();
+	extract_text_from_file_streaming(filepath, pre_scanned_items, keep_going, config, &mut |items| file_list_items.extend(items))?;
+	Ok(file_list_items)
+}
+
+/// Same as extract_text_from_file_with_config, and also returns the manifest of everything found in the file, e.g. for an audit trail.
+/// The manifest lists every member, including those that exclude_containers, pre_scanned_items or the search terms leave out of the FileListItems.
+pub fn extract_text_from_file_with_manifest(filepath: &Path, pre_scanned_items: Vec<FileListItem>, keep_going: Arc<AtomicBool>, config: &ExtractConfig) -> Result<(Vec<FileListItem>, ArchiveManifest), Box<dyn Error>> {
+	let mut file_list_items: Vec<FileListItem> = Vec::new();
+	let mut manifest = ArchiveManifest::default();
+	extract_text_from_file_inner(filepath, pre_scanned_items, keep_going, config, &mut |items| file_list_items.extend(items), Some(&mut manifest))?;
+	Ok((file_list_items, manifest))
+}
+
+/// The manifest entries of the files listed by extract_archive, read before their text is.
+fn manifest_entries(list_of_files_in_archive: &[SubFileItem]) -> Vec<ManifestEntry> {
+	let parent_lineages = parent_lineages(list_of_files_in_archive);
+	list_of_files_in_archive.iter()
+		.map(|sub_file_item| {
+			//text decoded in memory has no file behind it, nor does a member that was only listed
+			let (size, crc, file_type) = match (&sub_file_item.text, sub_file_item.listed_size_crc) {
+				(Some(text), _) => (text.len() as u64, checksum(Crc64Nvme, text.as_bytes()) as i64, FileType::Text),
+				(None, Some((size, crc))) => (size, crc, FileType::from_extension(&sub_file_item.filepath.extension().unwrap_or_default().to_string_lossy())),
+				(None, None) => {
+					let size = sub_file_item.filepath.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+					//an empty file has no magic bytes, e.g. the placeholder of a message attached to a message, which is read from its parent
+					if size == 0 {
+						(0, 0, FileType::from_extension(&sub_file_item.filepath.extension().unwrap_or_default().to_string_lossy()))
+					} else {
+						let crc = sub_file_item.filepath.to_str()
+							.and_then(|x| checksum_file(Crc64Nvme, x, None).ok())
+							.unwrap_or_default() as i64;
+						(size, crc, get_effective_file_type(&sub_file_item.filepath))
+					}
+				}
+			};
+			ManifestEntry {
+				filename: sub_file_item.filepath.file_name().unwrap_or_default().to_string_lossy().to_string(),
+				parent_files: sub_file_item.parent_files.clone(),
+				depth: sub_file_item.parent_files.len(),
+				size,
+				crc,
+				file_type: file_type.extension().to_string(),
+				is_container: parent_lineages.contains(&sub_file_item.lineage()),
+				note: sub_file_item.note.clone(),
+				error: sub_file_item.error.clone(),
+			}
+		})
+		.collect()
+}
+
+/// Estimates how much work extracting the text of a file would be, e.g. to show "1,200 files / 3,400 pages" before a scan is started.
+/// Archives are opened and their members listed as extraction does, but no page is converted: PDF pages are counted with pdfinfo
+/// and their images with `pdfimages -list`, DjVu pages with djvused. No text is read from the files listed and nothing is OCRed.
+///
+/// # Returns
+///
+/// * The counts of files, PDF pages and images, and the total bytes
+pub fn estimate_work(filepath: &Path, config: &ExtractConfig) -> Result<WorkEstimate, Box<dyn Error>> {
+	let mut list_of_files_in_archive: Vec<SubFileItem> = Vec::new();
+	let context = UnpackContext {
+		enumerate_only: true,
+		..Default::default()
+	};
+	extract_archive(filepath, 0, Vec::new(), &mut list_of_files_in_archive, &context, config)?;
+
+	let mut estimate = WorkEstimate {
+		files: list_of_files_in_archive.len(),
+		..Default::default()
+	};
+	let mut temp_dirs_to_remove: HashSet<PathBuf> = HashSet::new();
+	for sub_file_item in &list_of_files_in_archive {
+		//text decoded in memory has no file behind it
+		if let Some(text) = &sub_file_item.text {
+			estimate.bytes += text.len() as u64;
+			continue;
+		}
+		estimate.bytes += sub_file_item.filepath.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+		match get_effective_file_type(&sub_file_item.filepath) {
+			FileType::Pdf => {
+				let page_count = pdf_page_count(&sub_file_item.filepath, &config.runner()).unwrap_or_default() as usize;
+				let image_count = pdf_image_count(&sub_file_item.filepath, &config.runner()).unwrap_or_default();
+				estimate.pdf_pages += page_count;
+				estimate.images += image_count;
+				//the page texts, merged into one with a page marker, and the images the pages would be listed with
+				estimate.files += if config.pdf_page_marker.is_some() { page_count.min(1) } else { page_count };
+				estimate.files += image_count;
+			}
+			FileType::Djvu => estimate.files += djvu_page_count(&sub_file_item.filepath, &config.runner()).unwrap_or_default() as usize,
+			FileType::Image(_) if sub_file_item.ok_to_extract_text => estimate.images += 1,
+			_ => (),
+		}
+		if DELETE_TEMP_FILES && sub_file_item.depth >= 1 {
+			if let Some(temp_dir) = sub_file_item.filepath.parent() {
+				temp_dirs_to_remove.insert(temp_dir.to_path_buf());
+			}
+			_ = std::fs::remove_file(&sub_file_item.filepath);
+		}
+	}
+	for temp_dir in temp_dirs_to_remove {
+		_ = std::fs::remove_dir_all(&temp_dir);
+	}
+	Ok(estimate)
+}
+
+/// Why the text of a file could not be read, by extract_member or by a TextExtractor.
+#[derive(Debug)]
+pub enum ExtractError {
+	/// There is no file of that lineage, given as its names joined by '/'.
+	MemberNotFound(String),
+	/// The top-level file or a container on the way to the file could not be unpacked.
+	Unpack(Box<dyn Error>),
+	/// The file was found but its text could not be read.
+	Text(Box<dyn Error>),
+	/// A TextExtractor failed to read the file, listed as the error of its item.
+	Extractor(Box<dyn Error>),
+	/// A TextExtractor left the file unread on purpose, listed as the note of its item, e.g. `image too large, not extracted`.
+	NotExtracted(String),
+}
+
+impl std::fmt::Display for ExtractError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ExtractError::MemberNotFound(member) => write!(f, "No file {:?}", member),
+			ExtractError::Unpack(e) => write!(f, "Error unpacking: {}", e),
+			ExtractError::Text(e) => write!(f, "Error reading text: {}", e),
+			ExtractError::Extractor(e) => write!(f, "Error in text extractor: {}", e),
+			ExtractError::NotExtracted(note) => write!(f, "{}", note),
+		}
+	}
+}
+
+impl Error for ExtractError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			ExtractError::MemberNotFound(_) | ExtractError::NotExtracted(_) => None,
+			ExtractError::Unpack(e) | ExtractError::Text(e) | ExtractError::Extractor(e) => Some(e.as_ref()),
+		}
+	}
+}
+
+impl From<io::Error> for ExtractError {
+	fn from(e: io::Error) -> Self {
+		ExtractError::Extractor(Box::new(e))
+	}
+}
+
+/// Extracts the text of one file inside another, e.g. the member of an archive that a user picked from its FileListItems,
+/// without reading the text of the other files. The containers on the way to it are unpacked as extraction does, but of the
+/// members of an archive only those named in the lineage are unpacked, at each level, and no other file is read or OCRed.
+/// The temp files are removed before returning.
+///
+/// # Arguments
+///
+/// * `member_lineage` - The file's `parent_files` followed by its `filename`, as in its FileListItem, so starting with the name of `filepath`
+///
+/// # Returns
+///
+/// * The text, cleaned as extraction does
+pub fn extract_member(filepath: &Path, member_lineage: &[String], config: &ExtractConfig) -> Result<String, ExtractError> {
+	//a member is matched by its name, wherever it is in the archive's folders
+	let mut member_patterns: Vec<String> = Vec::new();
+	for name in member_lineage.iter().skip(1) {
+		let name = glob::Pattern::escape(name);
+		member_patterns.push(format!("*/{}", name));
+		member_patterns.push(name);
+	}
+	let member_config = ExtractConfig {
+		archive_member_patterns: member_patterns,
+		..config.clone()
+	};
+	let mut list_of_files_in_archive: Vec<SubFileItem> = Vec::new();
+	let unpacked = extract_archive(filepath, 0, Vec::new(), &mut list_of_files_in_archive, &UnpackContext::default(), &member_config);
+
+	let member_text = match unpacked {
+		Ok(()) => match list_of_files_in_archive.iter().find(|x| x.lineage() == member_lineage) {
+			Some(sub_file_item) => match extract_text_from_subfile(sub_file_item, config) {
+				Ok(SubfileText { error: Some(error), .. }) => Err(ExtractError::Text(error.into())),
+				Ok(subfile_text) => Ok(subfile_text.text),
+				Err(e) => Err(ExtractError::Text(e)),
+			},
+			None => Err(ExtractError::MemberNotFound(member_lineage.join("/"))),
+		},
+		Err(e) => Err(ExtractError::Unpack(e)),
+	};
+
+	if DELETE_TEMP_FILES {
+		let mut temp_dirs_to_remove: HashSet<PathBuf> = HashSet::new();
+		for sub_file_item in list_of_files_in_archive.iter().filter(|x| x.depth >= 1) {
+			if let Some(temp_dir) = sub_file_item.filepath.parent() {
+				temp_dirs_to_remove.insert(temp_dir.to_path_buf());
+			}
+		}
+		for temp_dir in temp_dirs_to_remove {
+			_ = std::fs::remove_dir_all(&temp_dir);
+		}
+	}
+	member_text
+}
+
+/// Extracts the text of input that is not a file, e.g. stdin in a shell pipeline. The input is read to the end and written to a temp file
+/// for the tools that need a path, named `stdin` with the extension of the type detected from its magic bytes, e.g. `stdin.pdf`.
+/// A zip or cfb is told apart by its main part, e.g. a docx or a msg. Input with no known magic bytes is read as text.
+///
+/// # Returns
+///
+/// * The FileListItems as extract_text_from_file_with_config returns them, the first one for the input named as above
+pub fn extract_text_from_reader(reader: &mut dyn Read, keep_going: Arc<AtomicBool>, config: &ExtractConfig) -> Result<Vec<FileListItem>, Box<dyn Error>> {
+	let input_dir = tempfiles_location().join(Uuid::new_v4().simple().to_string());
+	let extracted = write_reader_input(reader, &input_dir)
+		.and_then(|input_path| extract_text_from_file_with_config(&input_path, Vec::new(), keep_going, config));
+	_ = fs::remove_dir_all(&input_dir);
+	extracted
+}
+
+/// Writes the input of extract_text_from_reader to `input_dir`, named by its detected type.
+fn write_reader_input(reader: &mut dyn Read, input_dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+	fs::create_dir_all(input_dir)?;
+	let input_path = input_dir.join("stdin");
+	io::copy(reader, &mut File::create(&input_path)?)?;
+	let file_type = match magic_file_type(&input_path) {
+		Some(magic_file_type) => container_file_type(&input_path, &magic_file_type).unwrap_or(magic_file_type),
+		None => FileType::Text,
+	};
+	debug!("write_reader_input: file_type: {:?}", file_type);
+	let typed_path = input_path.with_extension(file_type.extension());
+	fs::rename(&input_path, &typed_path)?;
+	Ok(typed_path)
+}
+
+/// Same as extract_text_from_file_with_config, but hands the FileListItems to `on_items` while extracting instead of returning them at the end,
+/// so the text of a large archive does not have to be held in memory all at once.
+///
+/// # Arguments
+///
+/// * `on_items` - Called with the next items in order, in batches of about `config.stream_batch_text_len` bytes of text.
+///   With `config.flatten` it is called once, with the single flattened item.
+pub fn extract_text_from_file_streaming(filepath: &Path, pre_scanned_items: Vec<FileListItem>, keep_going: Arc<AtomicBool>, config: &ExtractConfig, on_items: &mut dyn FnMut(Vec<FileListItem>)) -> Result<(), Box<dyn Error>> {
+	extract_text_from_file_inner(filepath, pre_scanned_items, keep_going, config, on_items, None)
+}
+
+fn extract_text_from_file_inner(filepath: &Path, pre_scanned_items: Vec<FileListItem>, keep_going: Arc<AtomicBool>, config: &ExtractConfig, on_items: &mut dyn FnMut(Vec<FileListItem>), manifest: Option<&mut ArchiveManifest>) -> Result<(), Box<dyn Error>> {
+	let mut list_of_files_in_archive: Vec<SubFileItem> = Vec::new();
+	let parent_files: Vec<String> = Vec::new();
+	let unpack_start = Instant::now();
+	//unchanged members are left unopened while unpacking
+	let context = UnpackContext::new(&pre_scanned_items);
+	let unpacked = extract_archive(filepath, 0, parent_files, &mut list_of_files_in_archive, &context, config);
+	if let Some(summary) = &config.summary {
+		summary.record(|x| {
+			x.top_level_files += 1;
+			x.failed_files += unpacked.is_err() as usize;
+			x.unpack_time += unpack_start.elapsed();
+		});
+	}
+	unpacked?;
+	let read_start = Instant::now();
+
+	if let Some(manifest) = manifest {
+		manifest.entries = manifest_entries(&list_of_files_in_archive);
+	}
+
+	if config.exclude_containers {
+		//a container has children, its own lineage is one of the lineages that items were extracted from
+		let parent_lineages = parent_lineages(&list_of_files_in_archive);
+		list_of_files_in_archive.retain(|x| x.ok_to_extract_text || !parent_lineages.contains(&x.lineage()));
+	}
+
+	// debug!("list_of_files_in_archive: {:#?}", list_of_files_in_archive);
+
+	//(filename, parent_files, crc) of the prescanned items, looked up for each subfile of what may be a huge archive
+	let pre_scanned_keys: HashSet<(&str, &[String], i64)> = pre_scanned_items.iter()
+		.map(|x| (x.filename.as_str(), x.parent_files.as_slice(), x.crc))
+		.collect();
+
+	let mut file_list_items: Vec<FileListItem> = Vec::new();
+	//content class of each item by its lineage, for the text generated from it
+	let mut content_classes: HashMap<Vec<String>, &'static str> = HashMap::new();
+	//bytes of text in file_list_items, not yet handed to on_items
+	let mut buffered_text_len: usize = 0;
+
+	//loop list_of_files_in_archive
+	let mut temp_dirs_to_remove: HashSet<PathBuf> = HashSet::new();
+	let mut sub_file_items = list_of_files_in_archive.into_iter();
+	for sub_file_item in sub_file_items.by_ref() {
+		//text decoded in memory has no file behind it, nor does a member that was only listed
+		let file_len_result = match (&sub_file_item.text, sub_file_item.listed_size_crc) {
+			(Some(text), _) => Ok(text.len() as u64),
+			(None, Some((size, _))) => Ok(size),
+			(None, None) => sub_file_item.filepath.metadata().map(|metadata| metadata.len()),
+		};
+		match file_len_result {
+			Ok(file_len) => {
+				let file_name = sub_file_item.filepath.file_name().unwrap().to_string_lossy().to_string();
+				trace!("file_len {}", file_len);
+				if file_len==0 {
+					//add a SubFileItem with empty contents.
+					let text_contents = TextContents::from_extraction(String::new(), &sub_file_item.note, &sub_file_item.error);
+					let file_list_item: FileListItem = FileListItem{
+						filename: file_name,
+						parent_files: sub_file_item.parent_files,
+						note: sub_file_item.note,
+						error: sub_file_item.error,
+						crc: 0,
+						size: file_len as i64,
+						text_contents,
+						..Default::default()
+					};
+					file_list_items.push(file_list_item);
+					continue;
+				}
+				debug!("{:?}", sub_file_item);
+				debug!("\n  file: {:?}\n    depth:{}, {:?}\n      subfile: {:?}", filepath, sub_file_item.depth, sub_file_item.parent_files, sub_file_item.filepath.file_name().unwrap());
+				let type_mismatch = config.detect_type_mismatch && sub_file_item.text.is_none() && has_type_mismatch(&sub_file_item.filepath);
+				if type_mismatch {
+					warn!("File type of {:?} does not match its extension, detected as {:?}", sub_file_item.filepath, get_effective_file_type(&sub_file_item.filepath));
+				}
+
+				let file_crc: i64 = match (&sub_file_item.text, sub_file_item.listed_size_crc) {
+					(Some(text), _) => checksum(Crc64Nvme, text.as_bytes()) as i64,
+					(None, Some((_, crc))) => crc,
+					(None, None) => checksum_file(Crc64Nvme, sub_file_item.filepath.to_str().unwrap(), None).unwrap() as i64,
+				};
+
+				//original bytes of archive members and attachments, not of archives, whose members are copied, or of text generated from a file
+				let preserved_path = match &config.preserve_subfiles_dir {
+					Some(preserve_dir) if sub_file_item.depth >= 1
+						&& sub_file_item.text.is_none()
+						&& sub_file_item.listed_size_crc.is_none()
+						&& sub_file_item.method.is_none()
+						&& !ARCHIVE_FILE_TYPES.contains(&get_effective_file_type(&sub_file_item.filepath)) => preserve_subfile(&sub_file_item.filepath, file_crc, preserve_dir),
+					_ => None,
+				};
+
+				//an unchanged member is listed as unchanged below, with the items carried over from the prescan
+				if file_len > config.max_file_size && !sub_file_item.unchanged {
+					info!("Skiping subfile {} due to large size {}.", file_name, file_len);
+					let file_list_item: FileListItem = FileListItem{
+						filename: file_name,
+						parent_files: sub_file_item.parent_files,
+						note: sub_file_item.note,
+						error: sub_file_item.error,
+						crc: file_crc,
+						size: file_len as i64,
+						text_contents: TextContents::Skipped(format!("larger than max_file_size {}, not extracted", config.max_file_size)),
+						type_mismatch,
+						preserved_path,
+						modified: sub_file_item.modified,
+						..Default::default()
+					};
+					file_list_items.push(file_list_item);
+					continue;
+				}
+
+				//if this is in a prescanned item, then check the filecrc
+				let skip_file = pre_scanned_keys.contains(&(file_name.as_str(), sub_file_item.parent_files.as_slice(), file_crc));
+				
+				//an unchanged subfile is not read, so no OCR or other tool is run on it
+				if skip_file {
+					debug!("Sub file not changed, skipping...");
+					let lineage = sub_file_item.lineage();
+					let file_list_item: FileListItem = FileListItem{
+						filename: file_name,
+						parent_files: sub_file_item.parent_files,
+						note: sub_file_item.note,
+						error: sub_file_item.error,
+						crc: file_crc,
+						size: file_len as i64,
+						text_contents: TextContents::Unchanged,
+						type_mismatch,
+						has_scripts: sub_file_item.has_scripts,
+						preserved_path,
+						modified: sub_file_item.modified,
+						..Default::default()
+					};
+					file_list_items.push(file_list_item);
+					//the items of a member that was not opened are those of the prescan
+					if sub_file_item.unchanged {
+						file_list_items.extend(pre_scanned_items.iter()
+							.filter(|x| x.parent_files.starts_with(&lineage))
+							.map(|x| FileListItem { text_contents: TextContents::Unchanged, ..x.clone() }));
+					}
+				} else {
+					let SubfileText { text: mut subfile_text, method, note, error, encoding, truncated, raw_text } = extract_text_from_subfile(&sub_file_item, config)?;
+					//only the PDF text layer and OCR can be in visual order, other formats store text in logical order
+					if config.rtl_logical_order && matches!(method.as_deref(), Some("pdftotext" | "ocr")) {
+						subfile_text = to_logical_order(&subfile_text);
+					}
+					if let Some(line_ending) = config.line_ending {
+						subfile_text = normalize_line_endings(&subfile_text, line_ending);
+					}
+					// trace!("subfile_text {:?}", subfile_text);
+					//cleanup of temp files and dirs
+					if DELETE_TEMP_FILES {
+						if sub_file_item.depth >= 1 {
+							let temp_dir = sub_file_item.filepath.clone();
+							let temp_dir = temp_dir.parent().unwrap().to_path_buf();
+							temp_dirs_to_remove.insert(temp_dir);
+							_ = std::fs::remove_file(&sub_file_item.filepath); //delete the file
+						}
+					}
+					buffered_text_len += subfile_text.len();
+					let note = sub_file_item.note.or(note);
+					let error = sub_file_item.error.or(error);
+					let mut text_contents = TextContents::from_extraction(subfile_text, &note, &error);
+					//searching keeps just the text around the first match, and stops at the first subfile with one
+					let mut matched_term = None;
+					if !config.search_terms.is_empty()
+						&& let TextContents::Extracted(text) = &text_contents
+						&& !text.is_empty()
+					{
+						text_contents = match find_search_term(text, &config.search_terms) {
+							Some((term, context)) => {
+								matched_term = Some(term);
+								TextContents::Extracted(context)
+							}
+							None => TextContents::Skipped(String::from("no search term found")),
+						};
+					}
+					let is_match = matched_term.is_some();
+					let mut segments = Vec::new();
+					if config.segmentation != Segmentation::None
+						&& let TextContents::Extracted(text) = &text_contents
+					{
+						segments = segment_text(text, config.segmentation);
+						if let Some(separator) = &config.segment_separator {
+							text_contents = TextContents::Extracted(std::mem::take(&mut segments).join(separator));
+						}
+					}
+					let mut class = None;
+					if config.classify_content {
+						let is_generated = sub_file_item.text.is_some() || sub_file_item.method.is_some();
+						let parent_class = content_classes.get(&sub_file_item.parent_files).copied();
+						class = content_class(&get_effective_file_type(&sub_file_item.filepath), method.as_deref(), text_contents.text().unwrap_or_default(), is_generated, parent_class);
+						if let Some(class) = class {
+							let mut lineage = sub_file_item.parent_files.clone();
+							lineage.push(file_name.clone());
+							content_classes.insert(lineage, class);
+						}
+					}
+					let file_list_item: FileListItem = FileListItem{
+						filename: file_name,
+						parent_files: sub_file_item.parent_files,
+						note,
+						error,
+						crc: file_crc,
+						size: file_len as i64,
+						text_contents,
+						method,
+						encoding: encoding.map(|x| x.name().to_string()),
+						truncated: truncated || sub_file_item.truncated,
+						type_mismatch,
+						matched_term,
+						preserved_path,
+						segments,
+						has_scripts: sub_file_item.has_scripts,
+						modified: sub_file_item.modified,
+						content_class: class.map(|x| x.to_string()),
+						raw_text,
+						..Default::default()
+					};
+// println!("file_list_item: {:?}", file_list_item);
+					file_list_items.push(file_list_item);
+					if let Some(batch_text_len) = config.stream_batch_text_len
+						&& !config.flatten
+						&& buffered_text_len >= batch_text_len
+					{
+						if let Some(summary) = &config.summary {
+							summary.record_items(&file_list_items);
+						}
+						on_items(std::mem::take(&mut file_list_items));
+						buffered_text_len = 0;
+					}
+					if is_match {
+						debug!("Search term found in {:?}, not reading the rest of {:?}", sub_file_item.filepath, filepath);
+						break;
+					}
+				}
+			}
+			Err(e) => {
+				keep_going.store(false, Ordering::Relaxed);
+				return Err(format!("Error getting metadata for file: {:?} error: {:?}", sub_file_item.filepath, e).into());
+			}
+		}
+
+		if !keep_going.load(Ordering::Relaxed) {
+			break;
+		}
+	}
+	//subfiles not read, when stopped early, are still in the temp folders
+	if DELETE_TEMP_FILES {
+		for sub_file_item in sub_file_items.filter(|x| x.depth >= 1) {
+			if let Some(temp_dir) = sub_file_item.filepath.parent() {
+				temp_dirs_to_remove.insert(temp_dir.to_path_buf());
+			}
+		}
+	}
+	//remove temp folders
+	for temp_dir in temp_dirs_to_remove {
+		_ = std::fs::remove_dir_all(&temp_dir); //delete the temp dir
+	}
+
+	//counted before they are flattened
+	if let Some(summary) = &config.summary {
+		summary.record_items(&file_list_items);
+		summary.record(|x| x.read_time += read_start.elapsed());
+	}
+	if config.flatten {
+		let mut flattened = flatten_file_list_items_with_header(file_list_items, config.member_header.as_deref());
+		//the texts are joined with \n, and member_header has line breaks of its own
+		if let Some(line_ending) = config.line_ending
+			&& let Some(TextContents::Extracted(text)) = flattened.as_mut().map(|x| &mut x.text_contents)
+		{
+			*text = normalize_line_endings(text, line_ending);
+		}
+		on_items(flattened.into_iter().collect());
+	} else if !file_list_items.is_empty() {
+		on_items(file_list_items);
+	}
+
+	Ok(())
+}
+
+/// Merges the FileListItems of one extract_text_from_file call into a single item for the top-level file.
+///
+/// # Returns
+///
+/// * The top-level item with the text of every item concatenated, the sizes summed and the names of the subfiles in `contained_files`.
+///   The text is Unchanged only if every item was skipped as unchanged, and keeps the top-level item's state if no item has text. None if `items` is empty.
+pub fn flatten_file_list_items(items: Vec<FileListItem>) -> Option<FileListItem> {
+	flatten_file_list_items_with_header(items, None)
+}
+
+/// Same as flatten_file_list_items, with `member_header` written before the text of each subfile that has any,
+/// `{filename}` in it replaced by the subfile's name, e.g. `\n----- {filename} -----\n`.
+pub fn flatten_file_list_items_with_header(items: Vec<FileListItem>, member_header: Option<&str>) -> Option<FileListItem> {
+	let mut items = items.into_iter();
+	let mut flattened = items.next()?;
+	let mut is_unchanged = flattened.text_contents == TextContents::Unchanged;
+	let mut texts: Vec<String> = flattened.text_contents.text().map(|x| x.to_string()).into_iter().collect();
+	for item in items {
+		flattened.size += item.size;
+		match item.text_contents {
+			TextContents::Extracted(text) => match member_header {
+				Some(member_header) if !text.is_empty() => texts.push(member_header.replace("{filename}", &item.filename) + &text),
+				_ => texts.push(text),
+			},
+			TextContents::Unchanged => (),
+			_ => is_unchanged = false,
+		}
+		flattened.segments.extend(item.segments);
+		flattened.truncated |= item.truncated;
+		flattened.has_scripts |= item.has_scripts;
+		flattened.contained_files.push(item.filename);
+	}
+	if !texts.is_empty() {
+		flattened.text_contents = TextContents::Extracted(texts.into_iter().filter(|x| !x.is_empty()).collect::<Vec<String>>().join("\n"));
+	} else if is_unchanged {
+		flattened.text_contents = TextContents::Unchanged;
+	}
+	Some(flattened)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn extract_text_from_file_empty_file() {
+		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let keep_going_flag = keep_going.clone();
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/empty_file"),
+			pre_scanned_items,
+			keep_going_flag
+		).unwrap();
+		//load expected from serde serialization
+		let serial_path = Path::new("./tests/resources/expected/empty_file.json");
+		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
+		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
+		
+		assert_eq!(result, expected);
+    }
+
+	#[test]
+    fn extract_text_from_file_txt_utf8() {
+		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let keep_going_flag = keep_going.clone();
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/txt/text_utf8.txt"),
+			pre_scanned_items,
+			keep_going_flag
+		).unwrap();
+		// //load expected from serde serialization
+		// let serial_path = Path::new("./tests/resources/expected/empty_file.json");
+		// let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
+		// let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
+		//check each byte of contents
+		println!("*** {}", result.len());
+		
+		// assert_eq!(result, None);
+    }
+
+    #[cfg(target_os = "windows")]
+	#[test]
+    fn extract_text_from_file_docs_5407953830_pdf() {
+		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let keep_going_flag = keep_going.clone();
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/5407953830.pdf"),
+			pre_scanned_items,
+			keep_going_flag
+		).unwrap();
+		//load expected from serde serialization
+		let serial_path = Path::new("./tests/resources/expected/docs/5407953830.pdf.windows.json");
+		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
+		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
+		
+		assert_eq!(result, expected);
+    }
+
+    #[cfg(target_os = "linux")]
+	#[test]
+    fn extract_text_from_file_docs_5407953830_pdf() {
+		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let keep_going_flag = keep_going.clone();
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/5407953830.pdf"),
+			pre_scanned_items,
+			keep_going_flag
+		).unwrap();
+		//load expected from serde serialization
+		let serial_path = Path::new("./tests/resources/expected/docs/5407953830.pdf.linux.json");
+		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
+		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
+		
+		assert_eq!(result, expected);
+    }
+
+    #[cfg(target_os = "windows")]
+	#[test]
+    fn extract_text_from_file_emails_msg_in_msg() {
+		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let keep_going_flag = keep_going.clone();
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/emails/msg_in_msg.msg"),
+			pre_scanned_items,
+			keep_going_flag
+		).unwrap();
+		//load expected from serde serialization
+		let serial_path = Path::new("./tests/resources/expected/emails/msg_in_msg.msg.windows.json");
+		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
+		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
+		
+		assert_eq!(result, expected);
+    }
+
+    #[cfg(target_os = "linux")]
+	#[test]
+    fn extract_text_from_file_emails_msg_in_msg() {
+		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let keep_going_flag = keep_going.clone();
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/emails/msg_in_msg.msg"),
+			pre_scanned_items,
+			keep_going_flag
+		).unwrap();
+		//load expected from serde serialization
+		let serial_path = Path::new("./tests/resources/expected/emails/msg_in_msg.msg.linux.json");
+		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
+		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
+		
+		assert_eq!(result, expected);
+    }
+
+	#[test]
+	fn extract_text_from_file_emails_ansi_msg_in_msg() {
+		//the embedded message and its attachment only have the 8 bit 0x001E string streams of an ANSI msg, in windows-1252.
+		//Accents are then removed as in any other text.
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(Path::new("./tests/resources/files_to_scan/emails/ansi_forwarded.msg"), Vec::new(), keep_going).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["ansi_forwarded.msg", "body.txt", "Café inspection.msg", "body.txt", "checklist.txt"]);
+		assert_eq!(result[3].parent_files, vec!["ansi_forwarded.msg", "Café inspection.msg"]);
+		assert_eq!(result[3].text_contents, TextContents::Extracted(String::from("Cafe inspection\n\nKitchen passed. Grease trap due for cleaning, Senor Ortiz to book it.\r\n")));
+		assert_eq!(result[4].parent_files, vec!["ansi_forwarded.msg", "Café inspection.msg"]);
+		assert_eq!(result[4].text_contents, TextContents::Extracted(String::from("Grease trap: due\r\nExtractor hood: ok\r\n")));
+	}
+
+	#[test]
+	fn extract_text_from_file_line_ending() {
+		let path = Path::new("./tests/resources/files_to_scan/emails/ansi_forwarded.msg");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			line_ending: Some(LineEnding::default()),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		let checklist = result.iter().find(|x| x.filename == "checklist.txt").unwrap();
+		assert_eq!(checklist.text_contents, TextContents::Extracted(String::from("Grease trap: due\nExtractor hood: ok\n")));
+		assert!(result.iter().all(|x| !x.text_contents.text().unwrap_or_default().contains('\r')));
+
+		//the \n the texts are joined with too
+		let config = ExtractConfig {
+			line_ending: Some(LineEnding::CrLf),
+			flatten: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let text = result[0].text_contents.text().unwrap();
+		assert!(text.contains("Grease trap: due\r\nExtractor hood: ok\r\n"));
+		assert_eq!(text.matches('\n').count(), text.matches("\r\n").count());
+	}
+
+	#[test]
+	fn text_contents_serde() {
+		for text_contents in [
+			TextContents::Extracted(String::from("hi")),
+			TextContents::Unchanged,
+			TextContents::Skipped(String::from("binary, not extracted")),
+			TextContents::Error(String::from("Truncated archive")),
+		] {
+			let json = serde_json::to_string(&text_contents).unwrap();
+			assert_eq!(serde_json::from_str::<TextContents>(&json).unwrap(), text_contents);
+		}
+		assert_eq!(serde_json::to_string(&TextContents::Unchanged).unwrap(), r#"{"status":"unchanged"}"#);
+
+		//the earlier form, text or null
+		assert_eq!(serde_json::from_str::<TextContents>(r#""hi""#).unwrap(), TextContents::Extracted(String::from("hi")));
+		assert_eq!(serde_json::from_str::<TextContents>("null").unwrap(), TextContents::Unchanged);
+	}
+
+	#[test]
+	fn file_type_extension_mapping() {
+		for (extension, file_type, mime_type) in [
+			("bin", FileType::Bin, "application/octet-stream"),
+			("cfb", FileType::Cfb, "application/x-ole-storage"),
+			("djvu", FileType::Djvu, "image/vnd.djvu"),
+			("doc", FileType::Doc, "application/msword"),
+			("docm", FileType::Docm, "application/vnd.ms-word.document.macroEnabled.12"),
+			("docx", FileType::Docx, "application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+			("dxf", FileType::Dxf, "image/vnd.dxf"),
+			("eml", FileType::Eml, "message/rfc822"),
+			("encrypted", FileType::Encrypted, "application/octet-stream"),
+			("gzip", FileType::Gzip, "application/gzip"),
+			("hwp", FileType::Hwp, "application/x-hwp"),
+			("hwpx", FileType::Hwpx, "application/hwp+zip"),
+			("jpg", FileType::Image(ImageKind::Jpeg), "image/jpeg"),
+			("pgm", FileType::Image(ImageKind::Pgm), "image/x-portable-graymap"),
+			("png", FileType::Image(ImageKind::Png), "image/png"),
+			("ppm", FileType::Image(ImageKind::Ppm), "image/x-portable-pixmap"),
+			("key", FileType::Key, "application/vnd.apple.keynote"),
+			("mht", FileType::Mht, "multipart/related"),
+			("md", FileType::Markdown, "text/markdown"),
+			("msg", FileType::Msg, "application/vnd.ms-outlook"),
+			("numbers", FileType::Numbers, "application/vnd.apple.numbers"),
+			("ods", FileType::Ods, "application/vnd.oasis.opendocument.spreadsheet"),
+			("odt", FileType::Odt, "application/vnd.oasis.opendocument.text"),
+			("one", FileType::One, "application/onenote"),
+			("pages", FileType::Pages, "application/vnd.apple.pages"),
+			("pdf", FileType::Pdf, "application/pdf"),
+			("ppsx", FileType::Ppsx, "application/vnd.openxmlformats-officedocument.presentationml.slideshow"),
+			("pptm", FileType::Pptm, "application/vnd.ms-powerpoint.presentation.macroEnabled.12"),
+			("pptx", FileType::Pptx, "application/vnd.openxmlformats-officedocument.presentationml.presentation"),
+			("rtf", FileType::Rtf, "application/rtf"),
+			("7z", FileType::SevenZip, "application/x-7z-compressed"),
+			("tar", FileType::Tar, "application/x-tar"),
+			("txt", FileType::Text, "text/plain"),
+			("xlam", FileType::Xlam, "application/vnd.ms-excel.addin.macroEnabled.12"),
+			("xls", FileType::Xls, "application/vnd.ms-excel"),
+			("xlsb", FileType::Xlsb, "application/vnd.ms-excel.sheet.binary.macroEnabled.12"),
+			("xlsm", FileType::Xlsm, "application/vnd.ms-excel.sheet.macroEnabled.12"),
+			("xlsx", FileType::Xlsx, "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+			("xps", FileType::Xps, "application/vnd.ms-xpsdocument"),
+			("zip", FileType::Zip, "application/zip"),
+		] {
+			assert_eq!(FileType::from_extension(extension), file_type);
+			assert_eq!(FileType::from_extension(&extension.to_uppercase()), file_type);
+			assert_eq!(file_type.extension(), extension);
+			assert_eq!(file_type.to_string(), extension);
+			assert_eq!(file_type.mime_type(), mime_type);
+		}
+
+		//every known type maps back to itself
+		for file_type in FileType::KNOWN {
+			assert_eq!(FileType::from_extension(file_type.extension()), file_type);
+		}
+
+		//aliases
+		assert_eq!(FileType::from_extension("jpeg"), FileType::Image(ImageKind::Jpeg));
+		assert_eq!(FileType::from_extension("gz"), FileType::Gzip);
+		assert_eq!(FileType::from_extension("tgz"), FileType::Gzip);
+		assert_eq!(FileType::from_extension("mhtml"), FileType::Mht);
+		assert_eq!(FileType::from_extension("djv"), FileType::Djvu);
+		assert_eq!(FileType::from_extension("markdown"), FileType::Markdown);
+		assert_eq!(FileType::from_extension("oxps"), FileType::Xps);
+
+		let other = FileType::from_extension("CSV");
+		assert_eq!(other, FileType::Other(String::from("csv")));
+		assert_eq!(other.extension(), "csv");
+		assert_eq!(other.mime_type(), "application/octet-stream");
+		assert_eq!(FileType::from_extension(""), FileType::Other(String::new()));
+	}
+
+	#[test]
+	fn scan_directory_txt() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig::default();
+		let report = scan_directory(Path::new("./tests/resources/files_to_scan/txt"), &config, keep_going).unwrap();
+		let filenames: Vec<&str> = report.items.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["MsgSearcher.sql", "text_cp1252.txt", "text_utf16le.txt", "text_utf8.txt", "text_utf8bom.txt"]);
+		assert!(report.items.iter().all(|x| x.parent_files.is_empty()));
+		assert!(report.items.iter().all(|x| !x.text_contents.text().unwrap().is_empty()));
+	}
+
+	#[test]
+	fn scan_directory_mixed() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let report = scan_directory(Path::new("./tests/resources/files_to_scan/mixed"), &ExtractConfig::default(), keep_going).unwrap();
+		assert!(report.errors.is_empty());
+		//each top-level file is followed by what it holds: the zip's entry, the page of the pdf named .dat
+		let mut item_counts: Vec<(&str, usize)> = Vec::new();
+		for item in &report.items {
+			match item.parent_files.first() {
+				Some(top_level) => item_counts.last_mut().filter(|(filename, _)| filename == top_level).unwrap().1 += 1,
+				None => item_counts.push((item.filename.as_str(), 1)),
+			}
+		}
+		assert_eq!(item_counts, vec![("archive.zip", 2), ("notes.txt", 1), ("report.dat", 2)]);
+		let inside = report.items.iter().find(|x| x.filename == "inside.txt").unwrap();
+		assert_eq!(inside.parent_files, vec![String::from("archive.zip")]);
+		assert_eq!(inside.text_contents, TextContents::Extracted(String::from("Text inside the zip.\n")));
+		let page = report.items.iter().find(|x| x.parent_files == vec![String::from("report.dat")]).unwrap();
+		assert!(page.text_contents.text().unwrap().contains("Quarterly report"));
+	}
+
+	#[test]
+	fn scan_directory_errors() {
+		let dir = tempfiles_location().join(Uuid::new_v4().simple().to_string());
+		fs::create_dir_all(&dir).unwrap();
+		fs::copy("./tests/resources/files_to_scan/txt/text_utf8.txt", dir.join("a_notes.txt")).unwrap();
+		//the magic bytes of an Outlook message, with no compound file behind them
+		let mut broken = vec![0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+		broken.resize(600, 0);
+		fs::write(dir.join("b_broken.msg"), broken).unwrap();
+		fs::copy("./tests/resources/files_to_scan/txt/text_cp1252.txt", dir.join("c_notes.txt")).unwrap();
+
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let report = scan_directory(&dir, &ExtractConfig::default(), keep_going.clone()).unwrap();
+		let filenames: Vec<&str> = report.items.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["a_notes.txt", "c_notes.txt"]);
+		assert_eq!(report.errors.len(), 1);
+		assert_eq!(report.errors[0].path, dir.join("b_broken.msg"));
+		assert!(!report.errors[0].error.is_empty());
+
+		let paths = vec![dir.join("b_broken.msg"), dir.join("a_notes.txt")];
+		let (items, errors) = extract_text_from_files_with_errors(&paths, &ExtractConfig::default(), &[], keep_going);
+		assert_eq!(items.len(), 1);
+		assert_eq!(errors, report.errors);
+		_ = fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn extract_text_from_file_encoding() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let path = Path::new("./tests/resources/files_to_scan/txt/text_cp1252.txt");
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result[0].encoding, Some(String::from("windows-1252")));
+		let (text, encoding) = read_text_with_encoding(path, &ExtractConfig::default()).unwrap();
+		assert_eq!(encoding, WINDOWS_1252);
+		assert_eq!(result[0].text_contents.text(), Some(text.as_str()));
+
+		let result = extract_text_from_file(Path::new("./tests/resources/files_to_scan/txt/text_utf16le.txt"), Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result[0].encoding, Some(String::from("UTF-16LE")));
+		//documents are not decoded as text
+		let result = extract_text_from_file(Path::new("./tests/resources/files_to_scan/docs/two_images.docx"), Vec::new(), keep_going).unwrap();
+		assert!(result.iter().all(|x| x.encoding.is_none()));
+	}
+
+	#[test]
+	fn extract_text_from_file_raw_text_before_cleaning() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let path = Path::new("./tests/resources/files_to_scan/txt/text_cp1252.txt");
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result[0].raw_text, None);
+		let config = ExtractConfig {
+			raw_text_max_bytes: Some(100),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		assert!(result[0].text_contents.text().unwrap().starts_with("Loic Vital"));
+		assert!(result[0].raw_text.as_ref().unwrap().starts_with("Loïc Vital"));
+		//the cut does not split the ï
+		let config = ExtractConfig {
+			raw_text_max_bytes: Some(3),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result[0].raw_text, Some(String::from("Lo")));
+	}
+
+	#[test]
+	fn scan_directory_threads() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let root = Path::new("./tests/resources/files_to_scan/mixed");
+		let expected = scan_directory(root, &ExtractConfig::default(), keep_going.clone()).unwrap();
+		let config = ExtractConfig {
+			file_threads: 4,
+			..Default::default()
+		};
+		//same items in the same order as a single thread
+		assert_eq!(scan_directory(root, &config, keep_going).unwrap(), expected);
+	}
+
+	#[test]
+	fn process_limit_caps_running_programs() {
+		//each OCR takes a while, so the files' programs would overlap if nothing held them back
+		let running = Arc::new(AtomicUsize::new(0));
+		let most_running = Arc::new(AtomicUsize::new(0));
+		let runner = {
+			let running = running.clone();
+			let most_running = most_running.clone();
+			Arc::new(MockCommandRunner::new(move |_, args| {
+				most_running.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+				thread::sleep(Duration::from_millis(50));
+				running.fetch_sub(1, Ordering::SeqCst);
+				fs::write(format!("{}.txt", args.last().unwrap()), "Figure 1: harbour plan")?;
+				Ok(MockCommandRunner::success(""))
+			}))
+		};
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			process_limit: ProcessLimit::new(2),
+			file_threads: 6,
+			..Default::default()
+		};
+		let paths = vec![PathBuf::from("./tests/resources/files_to_scan/docs/two_images.docx"); 6];
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_files(&paths, &config, &[], keep_going);
+		assert_eq!(runner.calls().len(), 12);
+		assert_eq!(result.iter().filter(|x| x.method.as_deref() == Some("ocr")).count(), 12);
+		//the files' threads don't always overlap, but never run more than the limit at once
+		assert!((1..=2).contains(&most_running.load(Ordering::SeqCst)));
+		assert_eq!(config.process_limit.max_running(), 2);
+		assert!(ProcessLimit::default().max_running() >= 1);
+	}
+
+	#[test]
+	fn extract_text_from_files_mixed() {
+		let paths = vec![
+			PathBuf::from("./tests/resources/files_to_scan/mixed/notes.txt"),
+			PathBuf::from("./tests/resources/files_to_scan/mixed/archive.zip"),
+			PathBuf::from("./tests/resources/files_to_scan/txt/text_utf8.txt"),
+		];
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let mut expected: Vec<FileListItem> = Vec::new();
+		for path in &paths {
+			expected.extend(extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap());
+		}
+
+		let config = ExtractConfig::default();
+		let result = extract_text_from_files(&paths, &config, &[], keep_going.clone());
+		assert_eq!(result, expected);
+
+		let config = ExtractConfig {
+			file_threads: 3,
+			..Default::default()
+		};
+		assert_eq!(extract_text_from_files(&paths, &config, &[], keep_going.clone()), expected);
+
+		//a second run over the same files skips every unchanged item
+		let rescan = extract_text_from_files(&paths, &config, &result, keep_going);
+		assert_eq!(rescan.len(), expected.len());
+		assert!(rescan.iter().filter(|x| x.size > 0).all(|x| x.text_contents == TextContents::Unchanged));
+	}
+
+	#[test]
+	fn scan_directory_include_only_pdf() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			include_extensions: vec![String::from("pdf")],
+			..Default::default()
+		};
+		let report = scan_directory(Path::new("./tests/resources/files_to_scan/mixed"), &config, keep_going).unwrap();
+		//report.dat is a pdf, notes.txt and archive.zip are filtered out
+		assert_eq!(report.items.first().unwrap().filename, "report.dat");
+		assert!(report.items.iter().skip(1).all(|x| x.parent_files == vec![String::from("report.dat")]));
+		assert!(report.items.iter().any(|x| x.text_contents.text().unwrap().contains("Quarterly report")));
+	}
+
+	#[test]
+	fn extract_text_from_file_xlsx_comments_and_defined_names() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/comments_and_names.xlsx"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let names = result.iter().find(|x| x.filename == "[Defined Names]").unwrap();
+		assert!(names.text_contents.text().unwrap().contains("TotalAmount\tBudget!$B$2:$B$3"));
+		let comments = result.iter().find(|x| x.filename == "[Comments]").unwrap();
+		assert!(comments.text_contents.text().unwrap().contains("Budget!B2\tReviewer\tCheck the rent figure against the lease\n"));
+	}
+
+	#[test]
+	fn extract_text_from_file_xls_comments() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/sheet_comments.xls"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let sheet = result.iter().find(|x| x.filename == "Budget").unwrap();
+		assert!(sheet.text_contents.text().unwrap().contains("Rent\t1200"));
+		let comments = result.iter().find(|x| x.filename == "[Comments]").unwrap();
+		assert_eq!(comments.method, Some(String::from("xls biff")));
+		assert_eq!(
+			comments.text_contents,
+			TextContents::Extracted(String::from("Comments:\n\
+				Budget!B2\tReviewer\tCheck the rent figure against the lease\n\
+				Budget!A3\tAuditor\tIncludes the gas bill\n\
+				Suppliers!A2\tMaria\tPagado en   confirmado\n"))
+		);
+		//the text of the last comment is split between one byte and UTF-16 characters
+		let comments = xls::read_comments("./tests/resources/files_to_scan/docs/sheet_comments.xls").unwrap();
+		assert_eq!(comments[2], (String::from("Suppliers!A2"), String::from("María"), String::from("Pagado en € — confirmado")));
+	}
+
+	#[test]
+	fn extract_text_from_file_xlsx_charts_and_data_validation() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/revenue_dashboard.xlsx"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let charts = result.iter().find(|x| x.filename == "[Charts]").unwrap();
+		assert_eq!(charts.method, Some(String::from("xlsx xml")));
+		let charts = charts.text_contents.text().unwrap();
+		//the title is split across two runs
+		assert!(charts.contains("Dashboard chart 1\ttitle\tQuarterly Revenue vs Target\n"));
+		assert!(charts.contains("Dashboard chart 1\tseries\tRevenue\n"));
+		assert!(charts.contains("Dashboard chart 1\taxis title\tNZD (thousands)\n"));
+		let validations = result.iter().find(|x| x.filename == "[Data Validation]").unwrap().text_contents.text().unwrap();
+		assert!(validations.contains("Inputs!B1\tprompt\tRegion: Choose the sales region for this forecast.\n"));
+		assert!(validations.contains("Inputs!B1\terror\tUnknown region: Pick a region from the list.\n"));
+		//a validation without messages has no text
+		assert!(!validations.contains("Inputs!C1:C9"));
+	}
+
+	#[test]
+	fn extract_text_from_file_xlsx_cell_separator() {
+		let config = ExtractConfig {
+			cell_separator: String::from(","),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(
+			Path::new("./tests/resources/files_to_scan/docs/comments_and_names.xlsx"),
+			Vec::new(),
+			keep_going,
+			&config
+		).unwrap();
+		let sheet = result.iter().find(|x| x.filename == "Budget").unwrap();
+		assert_eq!(sheet.text_contents, TextContents::Extracted(String::from("Item,Amount\nRent,1200\nPower,150\n")));
+		let names = result.iter().find(|x| x.filename == "[Defined Names]").unwrap();
+		assert!(names.text_contents.text().unwrap().contains("TotalAmount,Budget!$B$2:$B$3\n"));
+	}
+
+	#[test]
+	fn extract_text_from_file_xlsx_max_sheet_rows() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/meter_readings.xlsx");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		let sheet = result.iter().find(|x| x.filename == "Readings").unwrap();
+		let text = sheet.text_contents.text().unwrap();
+		assert_eq!(text.lines().count(), 50000);
+		assert!(text.ends_with("50000\t100000\n"));
+		assert!(!sheet.truncated);
+
+		let config = ExtractConfig {
+			max_sheet_rows: Some(1000),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		let sheet = result.iter().find(|x| x.filename == "Readings").unwrap();
+		let text = sheet.text_contents.text().unwrap();
+		assert_eq!(text.lines().count(), 1000);
+		assert!(text.starts_with("1\t2\n2\t4\n"));
+		assert!(text.ends_with("1000\t2000\n"));
+		assert!(sheet.truncated);
+
+		//a row that would pass the cap is left out whole
+		let config = ExtractConfig {
+			max_sheet_cells: Some(5),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let sheet = result.iter().find(|x| x.filename == "Readings").unwrap();
+		assert_eq!(sheet.text_contents, TextContents::Extracted(String::from("1\t2\n2\t4\n")));
+		assert!(sheet.truncated);
+	}
+
+	#[test]
+	fn extract_text_from_file_xlsx_damaged_styles() {
+		//xl/styles.xml is cut short, so calamine cannot open the workbook, but its sheets are intact
+		let path = Path::new("./tests/resources/files_to_scan/docs/cut_short_styles.xlsx");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(result[0].error.as_ref().unwrap().starts_with("Error opening workbook: "));
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["cut_short_styles.xlsx", "Stock", "Orders"]);
+		let stock = result.iter().find(|x| x.filename == "Stock").unwrap();
+		assert_eq!(stock.method, Some(String::from("xlsx xml")));
+		//the phonetic reading of a rich text string is left out, and a formula cell is its cached value
+		assert_eq!(stock.text_contents, TextContents::Extracted(String::from("Part\tQuantity\nGate valve 150mm\t12\nFlange gasket\t\t48\n")));
+		let orders = result.iter().find(|x| x.filename == "Orders").unwrap();
+		assert_eq!(orders.text_contents, TextContents::Extracted(String::from("Customer\nHarbour Board & Co\t1\n")));
+
+		//the caps apply to the sheets read from the XML as well
+		let config = ExtractConfig {
+			max_sheet_rows: Some(2),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let stock = result.iter().find(|x| x.filename == "Stock").unwrap();
+		assert_eq!(stock.text_contents, TextContents::Extracted(String::from("Part\tQuantity\nGate valve 150mm\t12\n")));
+		assert!(stock.truncated);
+	}
+
+	#[test]
+	fn extract_text_from_file_xlsx_damaged_cell_refs() {
+		//cell references past the last column XFD are read as the next cell of the row, instead of a column of millions
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/bad_cell_refs.xlsx"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let stock = result.iter().find(|x| x.filename == "Stock").unwrap();
+		assert_eq!(stock.method, Some(String::from("xlsx xml")));
+		assert_eq!(stock.text_contents, TextContents::Extracted(String::from("Part\tQuantity\nGate valve 150mm\t12\nFlange gasket\t\t48\n")));
+	}
+
+	#[test]
+	fn extract_text_from_file_pptx_notes_and_alt_text() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/slides_with_notes.pptx"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["slides_with_notes.pptx", "slide 1", "slide 1 notes", "slide 2", "slide 3", "slide 3 notes"]);
+		let text_of = |filename: &str| result.iter().find(|x| x.filename == filename).unwrap().text_contents.text().unwrap().to_string();
+		assert_eq!(text_of("slide 1"), "Soil Sampling Basics\nWhy we sample\nWhen to sample\n");
+		assert_eq!(text_of("slide 1 notes"), "Notes:\nWelcome everyone and introduce the course goals.\n");
+		assert_eq!(text_of("slide 2"), "Nitrate Results\nAlt text:\nBar chart of nitrate levels by paddock\n");
+		assert_eq!(text_of("slide 3 notes"), "Notes:\nExplain why results vary by season & rainfall.\n");
+	}
+
+	#[test]
+	fn extract_text_from_file_docx_properties() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/with_properties.docx"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["with_properties.docx", "[Properties]"]);
+		assert_eq!(
+			result[1].text_contents,
+			TextContents::Extracted(String::from("Properties:\nTitle: Site Inspection & Review\nAuthor: Jane Tremaine\nKeywords: inspection, north block, 2024\nLast Modified By: Jane Tremaine\nCompany: Harbour Engineering Ltd\n"))
+		);
+		assert_eq!(result[1].parent_files, vec![String::from("with_properties.docx")]);
+	}
+
+	#[test]
+	fn extract_images_docx() {
+		let outdir = tempfiles_location().join(Uuid::new_v4().simple().to_string());
+		let image_paths = extract_images(Path::new("./tests/resources/files_to_scan/docs/two_images.docx"), &outdir).unwrap();
+		assert_eq!(image_paths, vec![outdir.join("image1.png"), outdir.join("image2.jpeg")]);
+		assert!(image_paths.iter().all(|x| x.is_file()));
+		_ = fs::remove_dir_all(&outdir);
+	}
+
+	#[test]
+	fn extract_text_from_file_xlsb_mixed_types() {
+		let config = ExtractConfig {
+			cell_separator: String::from(","),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(
+			Path::new("./tests/resources/files_to_scan/docs/mixed_types.xlsb"),
+			Vec::new(),
+			keep_going,
+			&config
+		).unwrap();
+		let sheet = result.iter().find(|x| x.filename == "Mixed").unwrap();
+		assert_eq!(sheet.text_contents, TextContents::Extracted(String::from("shared text,inline text,42,3.25\nTRUE,#DIV/0!,2024-01-01,rich text\n2,formula text,FALSE,#N/A\n")));
+	}
+
+	#[test]
+	fn extract_text_from_file_docx_nonstandard_main_part() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/document2.docx"),
+			Vec::new(),
+			keep_going.clone()
+		).unwrap();
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from("\n\nMain part with a nonstandard name.\n\nSecond paragraph.")));
+		assert_eq!(result[0].error, None);
+
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/no_content_part.docx"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		assert!(matches!(&result[0].text_contents, TextContents::Error(x) if x.contains("No main document part")));
+		assert!(result[0].error.as_ref().is_some_and(|x| x.contains("No main document part")));
+	}
+
+	#[test]
+	fn extract_text_from_file_method() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/with_properties.docx"),
+			Vec::new(),
+			keep_going.clone()
+		).unwrap();
+		assert_eq!(result[0].method, Some(String::from("docx xml")));
+		assert_eq!(result[1].method, Some(String::from("docprops xml")));
+
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/comments_and_names.xlsx"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		//the workbook itself is only a container
+		assert_eq!(result[0].method, None);
+		assert!(result.iter().skip(1).all(|x| x.method.is_some()));
+		assert_eq!(result.iter().find(|x| x.filename == "[Comments]").unwrap().method, Some(String::from("xlsx xml")));
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_msg_in_msg_flatten() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			flatten: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(
+			Path::new("./tests/resources/files_to_scan/emails/msg_in_msg.msg"),
+			Vec::new(),
+			keep_going,
+			&config
+		).unwrap();
+		assert_eq!(result.len(), 1);
+		let flattened = result.first().unwrap();
+		assert_eq!(flattened.filename, "msg_in_msg.msg");
+		assert!(flattened.parent_files.is_empty());
+		assert!(flattened.contained_files.contains(&String::from("body.txt")));
+		assert!(flattened.contained_files.contains(&String::from("5407953830.pdf")));
+		assert!(flattened.size > 338944);
+		assert!(flattened.text_contents.text().unwrap().contains("This contains a msg in a msg"));
+	}
+
+	#[test]
+	fn extract_member_msg_in_msg() {
+		let path = Path::new("./tests/resources/files_to_scan/emails/msg_in_msg.msg");
+		let inner_msg = String::from("Google Cloud Platform & APIs Your invoice is available for 01C315-14B151-6818D1.msg");
+		let config = ExtractConfig::default();
+		let text = extract_member(path, &[String::from("msg_in_msg.msg"), inner_msg.clone(), String::from("body.txt")], &config).unwrap();
+		assert!(text.starts_with("Google Cloud Platform & APIs: Your invoice is available for 01C315-14B151-6818D1\n"));
+		assert!(text.contains("Invoice number\t 5407953830"));
+		//the outer message's body has the same name
+		let text = extract_member(path, &[String::from("msg_in_msg.msg"), String::from("body.txt")], &config).unwrap();
+		assert!(text.contains("This contains a msg in a msg"));
+		assert!(matches!(
+			extract_member(path, &[String::from("msg_in_msg.msg"), inner_msg, String::from("missing.txt")], &config),
+			Err(ExtractError::MemberNotFound(_))
+		));
+	}
+
+	#[test]
+	fn extract_member_zip_in_zip() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/site_records.zip");
+		let config = ExtractConfig::default();
+		let lineage = |names: &[&str]| names.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+		let text = extract_member(path, &lineage(&["site_records.zip", "inspections.zip", "north.txt"]), &config).unwrap();
+		assert_eq!(text, "Inspection of the north culvert: clear.\n");
+		//the outer archive has a file of the same name
+		let text = extract_member(path, &lineage(&["site_records.zip", "north.txt"]), &config).unwrap();
+		assert_eq!(text, "Site records for the north paddock.\n");
+		//and with the folders in the lineage
+		let config = ExtractConfig {
+			archive_paths_in_lineage: true,
+			..Default::default()
+		};
+		let text = extract_member(path, &lineage(&["site_records.zip", "2023", "inspections.zip", "south.txt"]), &config).unwrap();
+		assert_eq!(text, "Inspection of the south culvert: silted, clean in April.\n");
+		assert!(matches!(
+			extract_member(path, &lineage(&["site_records.zip", "inspections.zip", "east.txt"]), &config),
+			Err(ExtractError::MemberNotFound(_))
+		));
+	}
+
+	#[test]
+	fn extract_text_from_reader_piped_pdf() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig::default();
+		let data = fs::read("./tests/resources/files_to_scan/docs/hyphenated.pdf").unwrap();
+		let result = extract_text_from_reader(&mut io::Cursor::new(data), keep_going.clone(), &config).unwrap();
+		assert_eq!(result[0].filename, "stdin.pdf");
+		assert!(result[1].text_contents.text().unwrap().contains("inter-\nnational"));
+		assert_eq!(result[1].parent_files, vec!["stdin.pdf"]);
+
+		//a docx is told apart from other zips
+		let data = fs::read("./tests/resources/files_to_scan/docs/harbour_memo_transitional.docx").unwrap();
+		let result = extract_text_from_reader(&mut io::Cursor::new(data), keep_going.clone(), &config).unwrap();
+		assert_eq!(result[0].filename, "stdin.docx");
+
+		//anything else is text
+		let result = extract_text_from_reader(&mut "Piped text\n".as_bytes(), keep_going, &config).unwrap();
+		assert_eq!(result[0].filename, "stdin.txt");
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from("Piped text\n")));
+	}
+
+	#[test]
+	fn extract_text_from_file_flatten_member_header() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			flatten: true,
+			member_header: Some(String::from("\n----- {filename} -----\n")),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(
+			Path::new("./tests/resources/files_to_scan/archives/bundle.tgz"),
+			Vec::new(),
+			keep_going,
+			&config
+		).unwrap();
+		assert_eq!(
+			result[0].text_contents.text().unwrap(),
+			"\n----- readme.txt -----\nReadme for the sample bundle.\n\n\n----- q1.txt -----\nQuarterly totals: 42 units.\n\n\n----- q2.txt -----\nSecond quarter totals: 57 units.\n"
+		);
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_odd_quoted_printable() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/emails/odd_quoted_printable.eml"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let body = result.iter().find(|x| x.filename == "body.txt").unwrap();
+		let text = body.text_contents.text().unwrap();
+		//accents are decoded, then folded to base letters by the text cleaning
+		assert!(text.contains("Today the cafe serves creme brulee and a very long line that continues after a soft line break."));
+		assert!(!text.contains("=C3"));
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_plain_7bit() {
+		//letters only, a multiple of 4 of them, which would pass for base64 once the spaces are removed
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/emails/plain_7bit.eml"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let body = result.iter().find(|x| x.filename == "body.txt").unwrap();
+		assert!(body.text_contents.text().unwrap().contains("Today paid please notes back"));
+
+		//without the header, a body is only decoded when it is laid out as base64 and decodes to UTF-8
+		assert_eq!(decode_leftover_transfer_encoding("Today paid please notes back"), None);
+		assert_eq!(decode_leftover_transfer_encoding("VG9kYXkgcGFpZCBwbGVhc2Ug\r\nbm90ZXMgYmFjaw==\r\n"), Some(String::from("Today paid please notes back")));
+		assert_eq!(decode_leftover_transfer_encoding("VG9kYXkg\r\nbm90ZXMgYmFjaw=="), None);
+		assert_eq!(decode_leftover_transfer_encoding("TodaypaidpleasenotesbackTodaypai"), None);
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_forwarded_nested_parts() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/emails/forwarded_nested.eml"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let body = result.iter().find(|x| x.filename == "body.txt").unwrap();
+		let text = body.text_contents.text().unwrap();
+		//the text of every part, each plain and html alternative once
+		assert_eq!(text.matches("Please confirm by Friday.").count(), 1);
+		assert!(text.contains("The inspection starts at 9am on Tuesday at the north gate."));
+		assert_eq!(text.matches("Parking is available behind the depot.").count(), 1);
+		//the attachment is its own item
+		assert!(!text.contains("Bring hard hats"));
+		let attachment = result.iter().find(|x| x.filename == "checklist.txt").unwrap();
+		assert!(attachment.text_contents.text().unwrap().contains("Bring hard hats and boots."));
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_attachment_extensions() {
+		let path = Path::new("./tests/resources/files_to_scan/emails/pdf_and_video.eml");
+		let config = ExtractConfig {
+			attachment_extensions: vec![String::from("pdf")],
+			skip_text_extensions: Vec::new(),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		//the video is listed, but not read
+		let video = result.iter().find(|x| x.filename == "walkthrough.mp4").unwrap();
+		assert_eq!(video.note, Some(String::from("attachment type skipped, not extracted")));
+		assert_eq!(video.text_contents, TextContents::Skipped(String::from("attachment type skipped, not extracted")));
+		assert!(video.size > 0);
+		//the pdf is recursed into, its pages are items of their own
+		assert!(result.iter().any(|x| x.parent_files == ["pdf_and_video.eml", "inspection.pdf"]));
+		assert!(!result.iter().any(|x| x.parent_files.iter().any(|p| p == "walkthrough.mp4")));
+
+		//too large, the pdf is left out too
+		let config = ExtractConfig {
+			max_attachment_size: Some(100),
+			..config
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let pdf = result.iter().find(|x| x.filename == "inspection.pdf").unwrap();
+		assert_eq!(pdf.note, Some(String::from("large attachment, not extracted")));
+		assert!(!result.iter().any(|x| x.parent_files.iter().any(|p| p == "inspection.pdf")));
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_utf8_body_preserve_unicode() {
+		let path = Path::new("./tests/resources/files_to_scan/emails/utf8_accents.eml");
+		let config = ExtractConfig {
+			preserve_unicode: true,
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let body = result.iter().find(|x| x.filename == "body.txt").unwrap();
+		let text = body.text_contents.text().unwrap();
+		assert!(text.contains("Kia ora, the café serves crème brûlée — ngā mihi, Zoë."));
+		assert_eq!(body.size, text.len() as i64);
+	}
+
+	#[test]
+	fn clean_text_preserve_unicode_drops_controls() {
+		let config = ExtractConfig {
+			preserve_unicode: true,
+			..Default::default()
+		};
+		let text = String::from("\u{FEFF}会议纪要\u{0}\u{8}：预算\u{1B}已批准\u{FFFD}。\r\n\t東京\u{9B}オフィス\u{FFFF} 서울\u{200D}\u{7F}\n");
+		assert_eq!(clean_text(text.clone(), &config), "会议纪要：预算已批准。\r\n\t東京オフィス 서울\u{200D}\n");
+		//without preserve_unicode only ASCII is left
+		assert_eq!(clean_text(text, &ExtractConfig::default()), "\r\n\t \n");
+	}
+
+	#[test]
+	fn pdfimages_list_count_rows() {
+		let header = "page   num  type   width height color comp bpc  enc interp  object ID x-ppi y-ppi size ratio\n\
+			--------------------------------------------------------------------------------------------\n";
+		//a page with no images, and output with no header at all
+		assert_eq!(pdfimages_list_count(header), 0);
+		assert_eq!(pdfimages_list_count(""), 0);
+		let rows = "   1     0 image    1700  2200  gray    1   8  jpeg   no        12  0   200   200  179K 4.9%\n\
+			   1     1 smask    1700  2200  gray    1   8  image  no        12  0   200   200 1234B 0.0%\n";
+		assert_eq!(pdfimages_list_count(&format!("{}{}", header, rows)), 2);
+	}
+
+	#[test]
+	fn to_logical_order_rtl() {
+		//visual order reads reversed, numbers keep their left to right order
+		assert_eq!(to_logical_order("םלוע םולש\n12 דומע"), "שלום עולם\nעמוד 12");
+		//mostly left to right text is left as it is, even with some right to left words in it
+		assert_eq!(to_logical_order("Shalom is םולש in Hebrew"), "Shalom is םולש in Hebrew");
+	}
+
+	#[test]
+	fn extract_text_from_file_pdf_rtl_logical_order() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/hebrew_visual.pdf");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			preserve_unicode: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		let page = result.iter().find(|x| x.filename == "page 1").unwrap();
+		assert!(page.text_contents.text().unwrap().contains("םלוע םולש"));
+		let config = ExtractConfig {
+			preserve_unicode: true,
+			rtl_logical_order: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let page = result.iter().find(|x| x.filename == "page 1").unwrap();
+		let text = page.text_contents.text().unwrap();
+		assert!(text.contains("שלום עולם"));
+		assert!(text.contains("עמוד 12"));
+	}
+
+	#[test]
+	fn tesseract_command_modes() {
+		let image = Path::new("./tests/resources/files_to_scan/image/checkerboard_1200x900.png");
+		let args = |command: Command| command.get_args().map(|x| x.to_string_lossy().to_string()).collect::<Vec<String>>();
+		let default_args = args(tesseract_command(image, "ocr", None, None));
+		assert!(!default_args.contains(&String::from("--psm")));
+		assert!(!default_args.contains(&String::from("--oem")));
+
+		let config = ExtractConfig {
+			ocr_psm: Some(6),
+			ocr_oem: Some(1),
+			..Default::default()
+		};
+		let mode_args = args(tesseract_command(image, "ocr", config.ocr_psm, config.ocr_oem));
+		assert!(mode_args.windows(2).any(|x| x == ["--psm", "6"]));
+		assert!(mode_args.windows(2).any(|x| x == ["--oem", "1"]));
+		assert_eq!(mode_args.last(), Some(&String::from("ocr")));
+	}
+
+	#[test]
+	fn downscale_for_ocr_oversized_image() {
+		let path = Path::new("./tests/resources/files_to_scan/image/checkerboard_1200x900.png");
+		assert_eq!(downscale_for_ocr(path, 1200 * 900).unwrap(), None);
+
+		let downscaled_path = downscale_for_ocr(path, 120_000).unwrap().unwrap();
+		let (width, height) = image::image_dimensions(&downscaled_path).unwrap();
+		_ = fs::remove_file(&downscaled_path);
+		assert_eq!((width, height), (400, 300));
+	}
+
+	#[test]
+	fn extract_text_from_file_image_too_large_to_decode() {
+		//30000x30000 by its header, past the image crate's allocation limit
+		let path = Path::new("./tests/resources/files_to_scan/image/huge_scan_truncated.png");
+		assert!(downscale_for_ocr(path, OCR_MAX_IMAGE_PIXELS).is_err());
+		let runner = Arc::new(MockCommandRunner::new(|_, _| Ok(MockCommandRunner::success(""))));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert!(runner.calls().is_empty());
+		assert_eq!(result[0].note, Some(String::from("image too large, not extracted")));
+		assert_eq!(result[0].error, None);
+	}
+
+	#[test]
+	fn extract_text_from_file_allowed_chars() {
+		let path = Path::new("./tests/resources/files_to_scan/small/control_chars.txt");
+		let config = ExtractConfig {
+			allowed_chars: Some(CharFilter::new(|c| c == 'é' || c.is_ascii_graphic() || c == ' ' || c == '\n')),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from("Café 5 total[0m\ndéj vu\n")));
+	}
+
+	#[test]
+	fn extract_text_from_file_custom_extractor() {
+		struct FooExtractor;
+		impl TextExtractor for FooExtractor {
+			fn extract(&self, path: &Path) -> Result<String, ExtractError> {
+				let contents = fs::read_to_string(path)?;
+				let fields: Vec<&str> = contents.trim_end().split('|').skip(1).collect();
+				Ok(fields.join("\n"))
+			}
+		}
+
+		let path = Path::new("./tests/resources/files_to_scan/custom/sample.foo");
+		let mut config = ExtractConfig::default();
+		config.extractors.register("foo", FooExtractor);
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from("first line\nsecond line")));
+
+		//a failure is typed, and listed as the item's error
+		struct BrokenFooExtractor;
+		impl TextExtractor for BrokenFooExtractor {
+			fn extract(&self, _path: &Path) -> Result<String, ExtractError> {
+				Err(ExtractError::Extractor("no fields in record".into()))
+			}
+		}
+		config.extractors.register("foo", BrokenFooExtractor);
+		let result = extract_text_from_file_with_config(path, Vec::new(), Arc::new(AtomicBool::new(true)), &config).unwrap();
+		assert!(result[0].error.as_deref().unwrap().ends_with(": no fields in record"));
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_msg_eml_attachments_without_extension() {
+		let path = Path::new("./tests/resources/files_to_scan/emails/eml_attachments_without_extension.msg");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		//one attachment is tagged message/rfc822, the other is recognised by its headers
+		for (attachment, text) in [
+			("site visit", "The soil samples were collected from paddock seven."),
+			("reply", "Thanks, results are due on Friday."),
+		] {
+			let item = result.iter().find(|x| x.filename == attachment).unwrap();
+			assert_eq!(item.text_contents, TextContents::Extracted(String::new()));
+			let body = result.iter()
+				.find(|x| x.filename == "body.txt" && x.parent_files.last().map(String::as_str) == Some(attachment))
+				.unwrap();
+			assert_eq!(body.parent_files, vec!["eml_attachments_without_extension.msg", attachment]);
+			assert!(body.text_contents.text().unwrap().contains(text));
+		}
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_msg_text_attachment_like_eml() {
+		let path = Path::new("./tests/resources/files_to_scan/emails/mail_rule_attachment.msg");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		//a .txt tagged text/plain is read as text, though it starts like a header
+		let item = result.iter().find(|x| x.filename == "mail rule.txt").unwrap();
+		assert_eq!(item.method, Some(String::from("text")));
+		assert!(item.text_contents.text().unwrap().starts_with("From: accounts@example.com"));
+		assert!(!result.iter().any(|x| x.parent_files.last().map(String::as_str) == Some("mail rule.txt")));
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_msg_headers() {
+		let path = Path::new("./tests/resources/files_to_scan/emails/audit_headers.msg");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		//not listed by default
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(result.iter().all(|x| x.filename != "[Headers]"));
+
+		let config = ExtractConfig {
+			msg_headers: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["audit_headers.msg", "body.txt", "[Headers]"]);
+		let headers = &result[2];
+		assert_eq!(headers.parent_files, vec!["audit_headers.msg"]);
+		assert_eq!(headers.method, Some(String::from("msg")));
+		let text = headers.text_contents.text().unwrap();
+		assert!(text.starts_with("Headers:\nConversation topic: Quarterly audit\nCategories: Finance; Audit\nBilling information: Cost centre 7105\nInternet headers:\n"));
+		assert!(text.contains("Message-ID: <audit-42@example.com>"));
+		assert!(text.contains("X-Mailer: Outlook 16.0"));
+	}
+
+	#[test]
+	fn extract_text_from_file_content_class() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let path = Path::new("./tests/resources/files_to_scan/emails/utf8_accents.eml");
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(result.iter().all(|x| x.content_class.is_none()));
+
+		let config = ExtractConfig {
+			classify_content: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		assert!(result.len() >= 2);
+		assert!(result.iter().all(|x| x.content_class == Some(String::from("email"))));
+
+		let path = Path::new("./tests/resources/files_to_scan/docs/05-05-2022.xlsx");
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		assert!(result.len() >= 2);
+		assert!(result.iter().all(|x| x.content_class == Some(String::from("spreadsheet"))));
+
+		let path = Path::new("./tests/resources/files_to_scan/image/415.jpg");
+		let runner = Arc::new(MockCommandRunner::new(|_, args| {
+			fs::write(format!("{}.txt", args.last().unwrap()), "Received 12 March, invoice 4471")?;
+			Ok(MockCommandRunner::success(""))
+		}));
+		let config = ExtractConfig {
+			command_runner: runner,
+			..config
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result[0].method, Some(String::from("ocr")));
+		assert_eq!(result[0].content_class, Some(String::from("scanned image")));
+	}
+
+	#[test]
+	fn extract_text_from_file_image_ocr_text_cleaned() {
+		let path = Path::new("./tests/resources/files_to_scan/image/415.jpg");
+		let runner = Arc::new(MockCommandRunner::new(|_, args| {
+			fs::write(format!("{}.txt", args.last().unwrap()), "Caf\u{e9} r\u{e9}sum\u{e9} \u{2014} page 2")?;
+			Ok(MockCommandRunner::success(""))
+		}));
+		let config = ExtractConfig {
+			command_runner: runner,
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from("Cafe resume  page 2")));
+
+		let config = ExtractConfig {
+			preserve_unicode: true,
+			..config
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from("Caf\u{e9} r\u{e9}sum\u{e9} \u{2014} page 2")));
+	}
+
+	#[test]
+	fn looks_like_source_code_lines() {
+		assert!(looks_like_source_code("fn main() {\n\tlet x = 1;\n\tprintln!(\"{}\", x);\n}\n"));
+		assert!(!looks_like_source_code("Dear Sam,\nThe pump station report is attached.\nRegards\n"));
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_msg_rtf_body_picture() {
+		let path = Path::new("./tests/resources/files_to_scan/emails/pasted_screenshot.msg");
+		let runner = Arc::new(MockCommandRunner::new(|_, args| {
+			fs::write(format!("{}.txt", args.last().unwrap()), "error: linker exited with code 1")?;
+			Ok(MockCommandRunner::success(""))
+		}));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		//the png of the screenshot, not its metafile fallback
+		assert_eq!(filenames, vec!["pasted_screenshot.msg", "body.txt", "image1.png"]);
+		assert_eq!(result[2].parent_files, vec!["pasted_screenshot.msg"]);
+		assert_eq!(result[2].method, Some(String::from("ocr")));
+		assert_eq!(result[2].text_contents, TextContents::Extracted(String::from("error: linker exited with code 1")));
+
+		let config = ExtractConfig {
+			ocr_embedded_images: false,
+			..config
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert!(!result.iter().any(|x| x.filename.starts_with("image")));
+	}
+
+	#[test]
+	fn extract_text_from_file_emails_msg_html_in_rtf_body() {
+		let path = Path::new("./tests/resources/files_to_scan/emails/html_in_rtf_body.msg");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			preserve_unicode: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let body = result.iter().find(|x| x.filename == "body.txt").unwrap().text_contents.text().unwrap().to_string();
+		assert!(body.starts_with("Nitrate results\n\n"));
+		assert!(body.contains("Hi team,"));
+		assert!(body.contains("The nitrate results for paddock seven are in attached & ready to review."));
+		assert!(body.contains("Meeting at the Café on Friday."));
+		assert!(body.contains("Thanks, Aroha"));
+		for leftover in ["\\", "htmlrtf", "<p>", "margin", "Arial"] {
+			assert!(!body.contains(leftover), "{} left in {:?}", leftover, body);
+		}
+	}
+
+	#[test]
+	fn extract_text_from_file_truncated_zip() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/truncated.zip");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		let archive = result.iter().find(|x| x.filename == "truncated.zip").unwrap();
+		assert!(archive.error.is_some());
+		let first = result.iter().find(|x| x.filename == "first.txt").unwrap();
+		assert_eq!(first.text_contents, TextContents::Extracted(String::from("The first entry is complete.\n")));
+		let second = result.iter().find(|x| x.filename == "second.txt").unwrap();
+		assert_eq!(second.text_contents, TextContents::Extracted(String::from("The second entry is complete too.\n")));
+		assert!(!result.iter().any(|x| x.filename == "third.bin"));
+	}
+
+	#[test]
+	fn extract_text_from_file_tgz() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/archives/bundle.tgz"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["bundle.tgz", "bundle.tar", "readme.txt", "q1.txt", "q2.txt"]);
+		let q2 = result.iter().find(|x| x.filename == "q2.txt").unwrap();
+		assert_eq!(q2.parent_files, vec![String::from("bundle.tgz"), String::from("bundle.tar")]);
+		assert_eq!(q2.text_contents, TextContents::Extracted(String::from("Second quarter totals: 57 units.\n")));
+		assert!(result.iter().all(|x| x.error.is_none()));
+	}
+
+	#[test]
+	fn extract_text_from_file_streaming_batches() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/truncated.zip");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let expected = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		let config = ExtractConfig {
+			stream_batch_text_len: Some(1),
+			..Default::default()
+		};
+		let mut batches: Vec<Vec<FileListItem>> = Vec::new();
+		extract_text_from_file_streaming(path, Vec::new(), keep_going, &config, &mut |items| batches.push(items)).unwrap();
+		assert!(batches.len() > 1);
+		assert_eq!(batches.into_iter().flatten().collect::<Vec<FileListItem>>(), expected);
+	}
+
+	#[test]
+	fn get_effective_file_type_small_bom_file() {
+		let path = Path::new("./tests/resources/files_to_scan/small/bom.dat");
+		assert_eq!(path.metadata().unwrap().len(), 6);
+		assert_eq!(get_effective_file_type(path), FileType::Text);
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from("hi\n")));
+	}
+
+	#[test]
+	fn extract_text_from_file_binary_not_extracted() {
+		let path = Path::new("./tests/resources/files_to_scan/binary/main.exe.bin");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].text_contents, TextContents::Skipped(String::from("binary, not extracted")));
+		assert_eq!(result[0].note, Some(String::from("binary, not extracted")));
+	}
+
+	#[test]
+	fn extract_text_from_file_rtf_and_csv_disguised() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let path = Path::new("./tests/resources/files_to_scan/docs/minutes.doc");
+		assert_eq!(get_effective_file_type(path), FileType::Rtf);
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].method, Some(String::from("rtf")));
+		let text = result[0].text_contents.text().unwrap();
+		assert!(text.contains("Minutes of the board meeting, 3 March"));
+		assert!(text.contains("the new café."));
+		//neither the control words nor the info group
+		assert!(!text.contains("\\par"));
+		assert!(!text.contains("Board minutes"));
+
+		let path = Path::new("./tests/resources/files_to_scan/docs/stock_export.xls");
+		assert_eq!(get_effective_file_type(path), FileType::Other(String::from("csv")));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].note, None);
+		assert_eq!(result[0].method, Some(String::from("text")));
+		assert!(result[0].text_contents.text().unwrap().contains("A-101,Washer M8,1000"));
+		//text that is not delimited is still not an .xls
+		assert!(!is_delimited_text(Path::new("./tests/resources/files_to_scan/docs/release_notes.md"), &[',', ';', '\t']));
+	}
+
+	#[test]
+	fn extract_text_from_file_tab_delimited_text() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/pump_readings.txt");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result[0].method, Some(String::from("text")));
+		assert!(result[0].text_contents.text().unwrap().starts_with("Station   \tFlow (L/s)"));
+
+		let config = ExtractConfig {
+			tab_delimited_text: true,
+			cell_separator: String::from(" | "),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result[0].method, Some(String::from("tab delimited")));
+		//the padding is trimmed, the empty last cell of PS2 keeps its column
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from(
+			"Station | Flow (L/s) | Level (m) | Checked by\n\
+			PS1 Harbour Rd | 42.5 | 3.10 | J. Tane\n\
+			PS2 Quay St | 17.0 | 2.85 | \n\
+			PS3 Wharf Ln | 88.2 | 4.02 | M. Ruru\n"
+		)));
+		//a text file without tabs is read as it is
+		let result = extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/txt/text_cp1252.txt"), Vec::new(), Arc::new(AtomicBool::new(true)), &config).unwrap();
+		assert_eq!(result[0].method, Some(String::from("text")));
+	}
+
+	#[test]
+	fn extract_text_from_file_onenote_unsupported() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/Meeting Notes.one");
+		assert_eq!(get_effective_file_type(path), FileType::One);
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].note, Some(String::from("unsupported format, not extracted")));
+		assert_eq!(result[0].text_contents, TextContents::Skipped(String::from("unsupported format, not extracted")));
+	}
+
+	#[test]
+	fn extract_text_from_file_skipped_type() {
+		let path = Path::new("./tests/resources/files_to_scan/media/clip.mp4");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].filename, "clip.mp4");
+		assert_eq!(result[0].size, path.metadata().unwrap().len() as i64);
+		assert_ne!(result[0].crc, 0);
+		assert_eq!(result[0].text_contents, TextContents::Skipped(String::from("skipped type, not extracted")));
+		assert_eq!(result[0].note, Some(String::from("skipped type, not extracted")));
+
+		//with an empty list it is read like any other file of no known type
+		let config = ExtractConfig {
+			skip_text_extensions: Vec::new(),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result[0].note, Some(String::from("binary, not extracted")));
+	}
+
+	#[test]
+	fn extract_text_from_file_binary_strings() {
+		let path = Path::new("./tests/resources/files_to_scan/binary/blob.bin");
+		let config = ExtractConfig {
+			binary_strings_min_len: Some(6),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from("Serial number: AX-2291-77\n")));
+	}
+
+	#[test]
+	fn extract_text_from_file_docs_pdf_ocr_fallback() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/scanned_with_page_number.pdf");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(result.iter().any(|x| x.filename == "page 1"));
+		assert!(!result.iter().any(|x| x.filename == "page 1 render.png"));
+
+		//the text layer is just the page number, so the page is read by OCR too
+		let config = ExtractConfig {
+			ocr_fallback_min_text_len: 20,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert!(result.iter().any(|x| x.filename == "page 1"));
+		let render = result.iter().find(|x| x.filename == "page 1 render.png").unwrap();
+		assert_eq!(render.parent_files, vec!["scanned_with_page_number.pdf"]);
+	}
+
+	#[test]
+	fn extract_text_from_file_zip_archive_paths_in_lineage() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/nested_dirs.zip");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		let data = result.iter().find(|x| x.filename == "data.csv").unwrap();
+		assert_eq!(data.parent_files, vec!["nested_dirs.zip"]);
+
+		let config = ExtractConfig {
+			archive_paths_in_lineage: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let data = result.iter().find(|x| x.filename == "data.csv").unwrap();
+		assert_eq!(data.parent_files, vec!["nested_dirs.zip", "reports", "2023", "q1"]);
+		let readme = result.iter().find(|x| x.filename == "readme.txt").unwrap();
+		assert_eq!(readme.parent_files, vec!["nested_dirs.zip"]);
+	}
+
+	#[test]
+	fn extract_text_from_file_zip_comments() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/with_comments.zip");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result[0].method, None);
+		let filenames = result.iter().map(|x| x.filename.clone()).collect::<Vec<String>>();
+
+		//the comments are the text of the zip's own item, the entries are listed as before
+		let config = ExtractConfig {
+			archive_comments: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result.iter().map(|x| x.filename.clone()).collect::<Vec<String>>(), filenames);
+		let comments = &result[0];
+		assert_eq!(comments.filename, "with_comments.zip");
+		assert_eq!(comments.method, Some(String::from("zip comment")));
+		assert_eq!(
+			comments.text_contents,
+			TextContents::Extracted(String::from("Archive Comment:\nBuild 4.2.1 from release branch, packaged by CI\nEntry Comments:\nnotes.txt\tReviewed by the site team\n"))
+		);
+	}
+
+	#[test]
+	fn extract_text_from_file_zip_entry_modified() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/dated_entries.zip");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		assert_eq!(result[0].filename, "dated_entries.zip");
+		assert_eq!(result[0].modified, None);
+		let notes = result.iter().find(|x| x.filename == "inspection_notes.txt").unwrap();
+		assert_eq!(notes.modified, Some(1623745800)); //2021-06-15 08:30:00
+		let logs = result.iter().find(|x| x.filename == "station_logs.zip").unwrap();
+		assert_eq!(logs.modified, Some(1578674700)); //2020-01-10 16:45:00
+		let readings = result.iter().find(|x| x.filename == "pump_readings.txt").unwrap();
+		assert_eq!(readings.parent_files, vec!["dated_entries.zip", "station_logs.zip"]);
+		assert_eq!(readings.modified, Some(1551441600)); //2019-03-01 12:00:00
+	}
+
+	#[test]
+	fn extract_text_from_file_preserve_subfiles() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/nested_dirs.zip");
+		let preserve_dir = tempfiles_location().join(Uuid::new_v4().simple().to_string());
+		let config = ExtractConfig {
+			preserve_subfiles_dir: Some(preserve_dir.clone()),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		//the top-level archive is not copied
+		assert_eq!(result[0].preserved_path, None);
+
+		let mut archive = ZipArchive::new(File::open(path).unwrap()).unwrap();
+		for (entry_name, filename) in [("readme.txt", "readme.txt"), ("reports/2023/q1/data.csv", "data.csv")] {
+			let item = result.iter().find(|x| x.filename == filename).unwrap();
+			let preserved_path = item.preserved_path.as_ref().unwrap();
+			assert_eq!(preserved_path, &preserve_dir.join(format!("{:016x}.{}", item.crc as u64, Path::new(filename).extension().unwrap().to_string_lossy())));
+			let mut original = Vec::new();
+			archive.by_name(entry_name).unwrap().read_to_end(&mut original).unwrap();
+			assert_eq!(fs::read(preserved_path).unwrap(), original);
+		}
+		_ = fs::remove_dir_all(&preserve_dir);
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn scan_directory_symlinks() {
+		use std::os::unix::fs::symlink;
+		let root = tempfiles_location().join(Uuid::new_v4().simple().to_string());
+		let outside = tempfiles_location().join(Uuid::new_v4().simple().to_string());
+		fs::create_dir_all(root.join("notes")).unwrap();
+		fs::create_dir_all(&outside).unwrap();
+		fs::write(root.join("notes/a.txt"), "inside").unwrap();
+		fs::write(outside.join("b.txt"), "outside").unwrap();
+		//a loop back to the root, links out of it, and a hard link
+		symlink(&root, root.join("notes/loop")).unwrap();
+		symlink(&outside, root.join("elsewhere")).unwrap();
+		symlink(outside.join("b.txt"), root.join("b_link.txt")).unwrap();
+		fs::hard_link(root.join("notes/a.txt"), root.join("notes/a_copy.txt")).unwrap();
+
+		let scan = |symlinks, dedupe_hardlinks| {
+			let config = ExtractConfig {
+				symlinks,
+				dedupe_hardlinks,
+				..Default::default()
+			};
+			let report = scan_directory(&root, &config, Arc::new(AtomicBool::new(true))).unwrap();
+			report.items.into_iter().map(|x| x.filename).collect::<Vec<String>>()
+		};
+		//by default links to files are read, links to directories are not walked
+		assert_eq!(scan(SymlinkPolicy::default(), false), vec!["b_link.txt", "a.txt", "a_copy.txt"]);
+		assert_eq!(scan(SymlinkPolicy::Skip, false), vec!["a.txt", "a_copy.txt"]);
+		assert_eq!(scan(SymlinkPolicy::FollowWithinRoot, false), vec!["a.txt", "a_copy.txt"]);
+		assert_eq!(scan(SymlinkPolicy::FollowAll, false), vec!["b_link.txt", "b.txt", "a.txt", "a_copy.txt"]);
+		assert_eq!(scan(SymlinkPolicy::FollowAll, true), vec!["b_link.txt", "a.txt"]);
+		_ = fs::remove_dir_all(&root);
+		_ = fs::remove_dir_all(&outside);
+	}
+
+	#[test]
+	fn extract_text_from_file_zip_passwords() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/locked_mixed.zip");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let notes = |result: &[FileListItem]| result.iter().map(|x| (x.filename.clone(), x.note.clone())).collect::<Vec<(String, Option<String>)>>();
+		let encrypted = Some(String::from("encrypted, not extracted"));
+
+		//without passwords the plain entry is still read
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(notes(&result), vec![
+			(String::from("locked_mixed.zip"), None),
+			(String::from("payroll.txt"), encrypted.clone()),
+			(String::from("notice.txt"), None),
+			(String::from("board.txt"), encrypted.clone()),
+		]);
+		assert!(result[2].text_contents.text().unwrap().contains("office closes at 5pm"));
+		//the size and crc32 of an entry that is not decrypted are those in the zip's directory, so a rescan can tell it has not changed
+		assert_eq!((result[1].size, result[1].crc), (42, 0xdddecce4));
+		assert_eq!((result[3].size, result[3].crc), (50, 0x2f931102));
+		let rescan = extract_text_from_file(path, result.clone(), keep_going.clone()).unwrap();
+		assert_eq!(rescan[3].text_contents, TextContents::Unchanged);
+
+		//each entry is tried with every password, board.txt has another one
+		let config = ExtractConfig {
+			archive_passwords: vec![String::from("wrong"), String::from("harbour")],
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(notes(&result)[1], (String::from("payroll.txt"), None));
+		assert_eq!(notes(&result)[3], (String::from("board.txt"), encrypted));
+		assert_eq!(result[1].text_contents.text(), Some("Payroll summary for March: 42 staff paid.\n"));
+		assert!(result.iter().all(|x| x.error.is_none()));
+	}
+
+	#[test]
+	fn extract_text_from_file_7z_passwords() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/with_password.7z");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].note, Some(String::from("encrypted, not extracted")));
+
+		//a wrong password leaves nothing behind, the next one opens the archive
+		let config = ExtractConfig {
+			archive_passwords: vec![String::from("wrong"), String::from("a4")],
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result.len(), 2);
+		assert_eq!(result[0].note, None);
+		assert!(result[1].error.is_none());
+	}
+
+	#[test]
+	fn extract_text_from_file_summary() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/locked_mixed.zip");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			summary: Some(SummaryRecorder::new()),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		//the zip and notice.txt are read, the two encrypted entries are not
+		let summary = config.summary.as_ref().unwrap().summary();
+		assert_eq!(
+			(summary.top_level_files, summary.failed_files, summary.files, summary.extracted, summary.unchanged, summary.skipped, summary.errored, summary.ocr_files),
+			(1, 0, 4, 2, 0, 2, 0, 0)
+		);
+		assert_eq!(summary.bytes, result.iter().map(|x| x.size as u64).sum::<u64>());
 
-					let sheets_metadata = workbook.sheets_metadata().to_owned();
-					for sheet in sheets_metadata {
-						let mut text: String = String::new();
-						// trace!("sheet_metadata: {:?}", sheet);
-						if sheet.typ == calamine::SheetType::WorkSheet {
-							trace!("Reading sheet: {}", sheet.name);
-							if let Ok(range) = workbook.worksheet_range(&sheet.name) {
-								for row in range.rows() {
-									let mut line: String = String::new();
-									for (icell, cell) in row.iter().enumerate() {
-										if icell>0 {
-											line.push_str("\t");
-										}
-										line.push_str(cell.as_string().unwrap_or_default().as_str());
-									}
-									if !line.trim().is_empty() {
-										line.push_str("\n");
-										text.push_str(&line);
-									}
-								}
-							}
+		//a second run over the same file with its items, and a file that cannot be read, add to the same totals
+		extract_text_from_file_with_config(path, result, keep_going.clone(), &config).unwrap();
+		assert!(extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/archives/does_not_exist.zip"), Vec::new(), keep_going, &config).is_err());
+		let summary = config.summary.as_ref().unwrap().summary();
+		assert_eq!((summary.top_level_files, summary.failed_files, summary.files, summary.unchanged), (3, 1, 8, 4));
+	}
 
-							if !text.is_empty() {
-								let mut sheet_name_filename_safe = sheet.name.clone();
-								sheet_name_filename_safe.retain(|c| !FILENAME_ILLEGAL_CHARS.contains(&c));
-								let outpath = tempfiles_location().join(&achive_uuid_subdir).join(format!("{}", sheet_name_filename_safe));
-								fs::create_dir_all(outpath.parent().unwrap())?;
-								match fs::write(&outpath, text) {
-									Ok(_) => {
-										let mut new_parent_files = parent_files.clone();
-										new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-										extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-									},
-									Err(e) => {
-										error!("Error writing to file {:?}: {}", outpath, e)
-									},
-								}
-							}
-						} else {
-							trace!("Skipping sheet {} of type {:?}", sheet.name, sheet.typ);
-						}
-					}
+	#[test]
+	fn extract_text_from_file_split_archive() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(Path::new("./tests/resources/files_to_scan/archives/manuals.zip.001"), Vec::new(), keep_going.clone()).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["manuals.zip.001", "manuals.zip", "install_guide.txt", "warranty.txt"]);
+		let guide = &result[2];
+		assert_eq!(guide.parent_files, vec!["manuals.zip.001", "manuals.zip"]);
+		//the guide is stored across all three parts
+		assert!(guide.text_contents.text().unwrap().contains("Step 1: tighten bolt 1"));
+		assert!(guide.text_contents.text().unwrap().contains("Step 40: tighten bolt 40"));
 
-				}
-				Err(err) => {
-					match err {
-						calamine::Error::Xls(calamine::XlsError::Cfb(msg)) => {
-							warn!("Xls Cfb error: {}, in file {:?}", msg, filepath);
-						}
-						calamine::Error::Ods(calamine::OdsError::Password)
-						| calamine::Error::Xlsb(calamine::XlsbError::Password)
-						| calamine::Error::Xlsx(calamine::XlsxError::Password) => {
-							warn!("Cannot extract text from password protected file: {:?}", filepath);
-						}
-						_ => {warn!("{}", err)} // return Err(Box::new(err)),
-					}
-				}
-			}
+		let result = extract_text_from_file(Path::new("./tests/resources/files_to_scan/archives/manuals.zip.002"), Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].note, Some(String::from("part 2 of split archive manuals.zip, read with the first part")));
+
+		//a scan lists the archive once, as its first part, and each part of an incomplete one
+		let dir = tempfiles_location().join(Uuid::new_v4().simple().to_string());
+		fs::create_dir_all(&dir).unwrap();
+		for part in ["manuals.zip.001", "manuals.zip.002", "manuals.zip.003"] {
+			fs::copy(Path::new("./tests/resources/files_to_scan/archives").join(part), dir.join(part)).unwrap();
 		}
-		"zip" => {
-			list_of_files_in_archive.push(SubFileItem {
-				filepath: filepath.to_path_buf(),
-				depth,
-				parent_files: parent_files.clone(),
-				ok_to_extract_text: false,
-			});
-			
-			let file = File::open(filepath)?;
-			let mut archive = ZipArchive::new(file)?;
-			debug!("Total entries: {}", archive.len());
-			for i in 0..archive.len() {
-				match archive.by_index(i) {
-					Ok(mut zipfile) => {
-						if zipfile.encrypted() {
-							info!("Zip file is encrypted, no text extracted {:?}", filepath);
-							break;
-						}
-						// debug!("  {}: {} ({} bytes)", i, zipfile.name(), zipfile.size());
-						let outpath = tempfiles_location().join(&achive_uuid_subdir).join(zipfile.mangled_name());
-						if zipfile.is_dir() {
-							fs::create_dir_all(&outpath)?;
-							// debug!("Created directory: {:?}", outpath);
-						} else {
-							// Handle files
-							if let Some(parent) = outpath.parent() {
-								fs::create_dir_all(parent)?;
-							}
+		let report = scan_directory(&dir, &ExtractConfig::default(), keep_going.clone()).unwrap();
+		assert_eq!(report.items.len(), 4);
+		fs::remove_file(dir.join("manuals.zip.002")).unwrap();
+		let report = scan_directory(&dir, &ExtractConfig::default(), keep_going).unwrap();
+		let filenames: Vec<&str> = report.items.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["manuals.zip.001", "manuals.zip.003"]);
+		assert!(report.items.iter().all(|x| x.note == Some(String::from("incomplete split archive, not extracted"))));
+		_ = fs::remove_dir_all(&dir);
+	}
 
-							// Extract the file
-							if !outpath.exists() { // if file already exists, as it duplicate filenames can appear in some archives (e.g. if archive created in linux with different case, and Windows does not care about case), just skip it.
-								let mut outfile = File::create(&outpath)?;
-								io::copy(&mut zipfile, &mut outfile)?;
-								debug!("Extracted: {:?}", outpath);
-								let mut new_parent_files = parent_files.clone();
-								new_parent_files.push(filepath.file_name().unwrap_or_default().to_string_lossy().to_string());
-								// new_parent_files passes ownership instead of reference, because we no longer need it after passing into this function
-								extract_archive(outpath.as_path(), depth+1, new_parent_files, list_of_files_in_archive)?;
-								//filepath.file_name().unwrap_or_default().to_string_lossy().to_string()
-							}
-						}
-					}
-					Err(err) => {
-						match err {
-							ZipError::UnsupportedArchive(errtxt) => {
-								info!("Zip file not supported: ({}) {:?}", errtxt, filepath);
-								break;
-							}
-							_ => return Err(Box::new(err)),
-						}
-					}
-				}
-			}
+	#[test]
+	fn remove_stale_temp_dirs_of_crashed_runs() {
+		let root = tempfiles_location().join(Uuid::new_v4().simple().to_string());
+		let two_days_ago = std::time::SystemTime::now() - Duration::from_secs(2 * 24 * 60 * 60);
+		//left by a crashed run, by something else, and a run still going
+		let stale = root.join(Uuid::new_v4().simple().to_string());
+		let other = root.join("notes");
+		let fresh = root.join(Uuid::new_v4().simple().to_string());
+		for dir in [&stale, &other, &fresh] {
+			fs::create_dir_all(dir).unwrap();
+			fs::write(dir.join("page 1"), "text").unwrap();
 		}
-		_ => {
-			list_of_files_in_archive.push(SubFileItem {
-				filepath: filepath.to_path_buf(),
-				depth,
-				parent_files: parent_files.clone(),
-				ok_to_extract_text: true,
-			});
-			
+		for dir in [&stale, &other] {
+			File::options().write(true).open(dir.join("page 1")).unwrap().set_modified(two_days_ago).unwrap();
+			File::open(dir).unwrap().set_modified(two_days_ago).unwrap();
 		}
+		assert_eq!(remove_stale_temp_dirs(&root, STALE_TEMP_DIR_AGE), 1);
+		assert!(!stale.exists());
+		assert!(other.join("page 1").exists());
+		assert!(fresh.join("page 1").exists());
+		//a stale folder with a file still being written to is kept
+		File::open(&fresh).unwrap().set_modified(two_days_ago).unwrap();
+		assert_eq!(remove_stale_temp_dirs(&root, STALE_TEMP_DIR_AGE), 0);
+		_ = fs::remove_dir_all(&root);
 	}
 
+	#[test]
+	fn extract_text_from_file_xps() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/site_report.xps");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["site_report.xps", "page 1", "page 2"]);
+		//pages in the order of the FixedDocument, not of the zip
+		assert_eq!(result[1].text_contents, TextContents::Extracted(String::from("{Draft}\nSite Inspection Report\n")));
+		assert_eq!(result[2].text_contents, TextContents::Extracted(String::from("Findings\nDrainage at the north wall needs repair & review.\n")));
+		assert_eq!(result[2].method, Some(String::from("xps xml")));
+		assert_eq!(result[2].parent_files, vec!["site_report.xps"]);
+	}
 
-	Ok(())
-}
-
-fn ocr(filepath: &Path) -> Result<String, Box<dyn Error>> {
-	// tesseract -l eng "C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\43766efc4742438884b0f109fd6a6bac\image-0001.ppm" C:\Users\hrag\AppData\Local\Temp\extract_text_from_file\43766efc4742438884b0f109fd6a6bac\ocr
-	// https://tesseract-ocr.github.io/tessdoc/Command-Line-Usage.html
-	// https://github.com/tesseract-ocr/tessdata_fast
-	// get traineddata for eng (english) and osd (orientation and script detection)
-	let a_uuid: &str = &Uuid::new_v4().simple().to_string();
-	let outpath = tempfiles_location().join(a_uuid);
-	let mut outpath = format!("{}", outpath.to_string_lossy().to_string());
-	let mut command = Command::new("tesseract");
-	command
-		.arg("-l").arg("eng")
-		.arg(format!("{}", filepath.to_string_lossy().to_string()))
-		.arg(&outpath);
-	trace!("{:#?}", command);
-	match command.output() {
-		Ok(_output) => {
-			//println!("stdout: {}", String::from_utf8_lossy(&output.stdout));
-		}
-		Err(e) => {
-			println!("{:#?}", command);
-			return Err(format!("Failed to execute {:?}: {}", command.get_program(), e).into())
-		}
+	#[test]
+	fn extract_text_from_file_odt_comments_and_notes() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/stormwater_review.odt");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["stormwater_review.odt", "[Comments]", "[Notes]"]);
+		//the body text has the table cells but not the comment or the footnote
+		let body = result[0].text_contents.text().unwrap();
+		assert!(body.contains("The culvert capacity was assessed in April"));
+		assert!(body.contains("North drain\n\n4.2"));
+		assert!(body.contains("Upgrades are recommended for the north drain."));
+		assert!(!body.contains("council data"));
+		assert!(!body.contains("Awa Engineering"));
+		assert_eq!(result[1].text_contents, TextContents::Extracted(String::from("Comments:\nHemi Walker\tCheck these figures against the council data.\n")));
+		assert_eq!(result[2].text_contents, TextContents::Extracted(String::from("Notes:\n1\tAssessment by Awa Engineering, report 2024-117.\n")));
+		assert_eq!(result[2].method, Some(String::from("odt xml")));
+		assert_eq!(result[2].parent_files, vec!["stormwater_review.odt"]);
 	}
-	outpath.push_str(&".txt");
-	let outpath = PathBuf::from(outpath);
-	if outpath.exists() {
-		let contents = read_text_from_file(&outpath)?;
-		_ = std::fs::remove_file(&outpath);
-		return Ok(contents);
+
+	#[test]
+	fn extract_text_from_file_hwpx() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/meeting_minutes.hwpx");
+		assert_eq!(get_effective_file_type(path), FileType::Hwpx);
+		let config = ExtractConfig {
+			preserve_unicode: true,
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["meeting_minutes.hwpx", "section 1", "section 2"]);
+		//sections in number order, not the order of the zip
+		assert_eq!(result[1].text_contents, TextContents::Extracted(String::from("회의록 & 안건\n일시:\t2024년 3월 5일\n첫째 줄\n둘째 줄\n")));
+		//table cells are paragraphs of their own
+		assert_eq!(result[2].text_contents, TextContents::Extracted(String::from("Budget summary\nItem\nAmount\n")));
+		assert_eq!(result[2].method, Some(String::from("hwpx xml")));
+		assert_eq!(result[2].parent_files, vec!["meeting_minutes.hwpx"]);
 	}
 
-	return Ok(String::new());
-}
+	#[test]
+	fn extract_text_from_file_hwp_unsupported() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/budget_2019.hwp");
+		assert_eq!(get_effective_file_type(path), FileType::Hwp);
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].text_contents, TextContents::Skipped(String::from("unsupported format, not extracted")));
+	}
 
-fn convert_accented_manual(s: &str) -> String {
-	s.chars()
-		.map(|c| match c {
-			'á' | 'à' | 'ã' | 'â' => 'a',
-			'é' | 'è' | 'ê' => 'e',
-			'í' | 'ì' | 'î' | 'ï' => 'i',
-			'ó' | 'ò' | 'õ' | 'ô' => 'o',
-			'ú' | 'ù' | 'ũ' | 'û' => 'u',
-			'ñ' => 'n',
-			// Add more mappings as needed
-			_ => c, // Keep other characters as they are
-		})
-		.collect()
-}
+	#[test]
+	fn extract_text_from_file_zip_legacy_names() {
+		//neither entry has the UTF-8 flag, one name is CP437 and the other UTF-8
+		let path = Path::new("./tests/resources/files_to_scan/archives/legacy_names.zip");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["legacy_names.zip", "Résumé.txt", "Café menu.txt"]);
+		assert!(result[1].text_contents.text().unwrap().contains("Curriculum vitae"));
+		assert_eq!(result[2].parent_files, vec!["legacy_names.zip"]);
 
-fn read_text_from_file(filepath: &Path) -> Result<String, Box<dyn Error>> {
-	let file_encoding = detect_encoding(filepath, false);
-	debug!("file_encoding: {:?}", file_encoding);
-	let mut contents = read_file_with_encoding(filepath, file_encoding)?;
-	// if file_encoding == WINDOWS_1252 {
-		//if no 0 or 255 bytes the in the contents, assume this is a text file and convert accented characters to base letters
-		if !(contents.as_bytes().contains(&0) || contents.as_bytes().contains(&255)) {
-			contents = convert_accented_manual(&contents);
-		}
-		//clean all but english letters
-		contents.retain(|c| c.is_ascii_graphic() || c.is_whitespace());
-	// }
-	// debug!("contents: {:?}", contents);
-	return Ok(contents);
-}
+		assert_eq!(zip_entry_path("../../etc/passwd"), PathBuf::from("etc/passwd"));
+		assert_eq!(zip_entry_path("C:\\reports\\2023\\data.csv"), PathBuf::from("reports/2023/data.csv"));
+		assert_eq!(zip_entry_path("/"), PathBuf::new());
+	}
 
-#[allow(dead_code)]
-#[derive(Debug)]
-struct SubFileItem {
-	filepath: PathBuf,
-	depth: u8,
-	parent_files: Vec<String>,
-	ok_to_extract_text: bool,
-}
+	#[test]
+	fn extract_text_from_file_search_terms() {
+		let config = ExtractConfig {
+			search_terms: vec![String::from("installer"), String::from("GUIDE")],
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/docs/outline_two_levels.pdf"), Vec::new(), keep_going, &config).unwrap();
+		let outline = result.iter().find(|x| x.filename == "Outline").unwrap();
+		assert_eq!(outline.text_contents, TextContents::Skipped(String::from("no search term found")));
+		let page_1 = result.iter().find(|x| x.filename == "page 1").unwrap();
+		assert_eq!(page_1.matched_term, Some(String::from("GUIDE")));
+		assert!(page_1.text_contents.text().unwrap().contains("Purpose of this guide."));
+		//stopped at the first match, page 3 has "installer" but is not read
+		assert_eq!(result.last().unwrap().filename, "page 1");
 
-fn extract_text_from_subfile(file_list_item: &SubFileItem) -> Result<String, Box<dyn Error>> {
-	debug!("subfile to extract text: {:?}", file_list_item.filepath);
-	
-	if !file_list_item.ok_to_extract_text {
-		return Ok(String::new())
+		assert_eq!(find_search_term("no match here", &[String::from("absent")]), None);
+		let long_text = format!("{}needle{}", "a".repeat(200), "b".repeat(200));
+		let (term, context) = find_search_term(&long_text, &[String::from("Needle")]).unwrap();
+		assert_eq!(term, "Needle");
+		assert_eq!(context, format!("{}needle{}", "a".repeat(SEARCH_CONTEXT_LEN), "b".repeat(SEARCH_CONTEXT_LEN)));
 	}
-	// let file_extension = file_list_item.filepath.extension().unwrap_or_default().to_string_lossy().to_lowercase();
-	let effective_file_extension = get_effective_file_extension(&file_list_item.filepath);
-	debug!("extract_text_from_subfile: effective_file_extension: {:?}", effective_file_extension);
-
-	match effective_file_extension.as_str() {
-		"docx" | "docm" => {
-			//dotext
-			match <Docx as MsDoc<Docx>>::open(file_list_item.filepath.as_path()) {
-				Ok(mut doc) => {
-					let mut text = String::new();
-					let _ = doc.read_to_string(&mut text);
-					return Ok(text);
-				}
-				Err(e) => {
-					warn!("Error extracting text from docx {:?}\n{:?}", file_list_item.filepath, e);
-					return Ok(String::new());
+
+	#[test]
+	fn extract_text_from_file_search_terms_later_pages_not_converted() {
+		//the term is on page 2 of 4, pages 3 and 4 are never converted
+		let runner = Arc::new(MockCommandRunner::new(|program, args| {
+			match program {
+				"pdfinfo" => Ok(MockCommandRunner::success("Encrypted:      no\nPages:          4\n")),
+				"pdftotext" => {
+					let text = if args[1] == "2" { "the budget was approved\n" } else { "minutes of the meeting\n" };
+					fs::write(&args[5], text)?;
+					Ok(MockCommandRunner::success(""))
 				}
+				_ => Ok(MockCommandRunner::success("")),
 			}
-		}
-		"odt" => {
-			//dotext
-			match <Odt as OpenOfficeDoc<Odt>>::open(file_list_item.filepath.as_path()) {
-				Ok(mut doc) => {
-					let mut text = String::new();
-					let _ = doc.read_to_string(&mut text);
-					return Ok(text);
-				}
-				Err(e) => {
-					warn!("Error extracting text from docx {:?}\n{:?}", file_list_item.filepath, e);
-					return Ok(String::new());
-				}
+		}));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			search_terms: vec![String::from("budget")],
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/docs/hyphenated.pdf"), Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result.last().unwrap().filename, "page 2");
+		assert_eq!(result.last().unwrap().matched_term, Some(String::from("budget")));
+		let calls = runner.calls();
+		let pages_converted: Vec<&str> = calls.iter()
+			.filter(|(program, _)| program == "pdftotext")
+			.map(|(_, args)| args[1].as_str())
+			.collect();
+		assert_eq!(pages_converted, vec!["1", "2"]);
+		//the images of page 1 are listed, those of page 2 on are not
+		let pages_with_images_listed: Vec<&str> = calls.iter()
+			.filter(|(program, _)| program == "pdfimages" || program == "pdftoppm")
+			.map(|(_, args)| args[1].as_str())
+			.collect();
+		assert_eq!(pages_with_images_listed, vec!["1"]);
+	}
+
+	#[test]
+	fn estimate_work_msg_in_msg() {
+		let estimate = estimate_work(Path::new("./tests/resources/files_to_scan/emails/msg_in_msg.msg"), &ExtractConfig::default()).unwrap();
+		//the msg, the attached msg, their bodies, the attached pdf, its 2 pages and the 4 images on them
+		assert_eq!(estimate.files, 11);
+		assert_eq!(estimate.pdf_pages, 2);
+		assert_eq!(estimate.images, 4);
+		assert!(estimate.bytes > 338944 + 94271);
+	}
+
+	#[test]
+	fn estimate_work_pdf_pages_not_converted() {
+		//the pages are counted, and their images listed, but no page is converted
+		let runner = Arc::new(MockCommandRunner::new(|program, args| {
+			match (program, args.first().map(|x| x.as_str())) {
+				("pdfinfo", _) => Ok(MockCommandRunner::success("Encrypted:      no\nPages:          3\n")),
+				("pdfimages", Some("-list")) => Ok(MockCommandRunner::success(
+					"page   num  type   width height color comp bpc  enc interp  object ID x-ppi y-ppi size ratio\n\
+					--------------------------------------------------------------------------------------------\n\
+					   1     0 image     800   600  gray    1   8  jpeg   no        12  0   150   150 41.2K 8.8%\n\
+					   3     1 image     800   600  gray    1   8  jpeg   no        18  0   150   150 39.0K 8.3%\n"
+				)),
+				_ => Ok(MockCommandRunner::success("")),
 			}
+		}));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			..Default::default()
+		};
+		let estimate = estimate_work(Path::new("./tests/resources/files_to_scan/docs/hyphenated.pdf"), &config).unwrap();
+		//the pdf, its 3 pages and 2 images
+		assert_eq!(estimate.files, 6);
+		assert_eq!(estimate.pdf_pages, 3);
+		assert_eq!(estimate.images, 2);
+		let calls = runner.calls();
+		assert!(calls.iter().all(|(program, args)| program == "pdfinfo" || (program == "pdfimages" && args[0] == "-list")), "{:?}", calls);
+	}
+
+	#[test]
+	fn segment_text_sentences() {
+		let paragraph = "Dr. Smith arrived at 9 a.m. on Monday. The meeting covered the budget, e.g. travel and\nequipment.  J. R. Jones asked about Q3! Was it approved? Yes.";
+		assert_eq!(segment_text(paragraph, Segmentation::Sentence), vec![
+			"Dr. Smith arrived at 9 a.m. on Monday.",
+			"The meeting covered the budget, e.g. travel and equipment.",
+			"J. R. Jones asked about Q3!",
+			"Was it approved?",
+			"Yes.",
+		]);
+		assert_eq!(segment_text("Heading\n\nBody text.", Segmentation::Sentence), vec!["Heading", "Body text."]);
+		assert_eq!(segment_text("first line\n\n  second line  \n", Segmentation::Line), vec!["first line", "second line"]);
+		assert_eq!(segment_text("Hex bolt, M8 (250).", Segmentation::Token), vec!["Hex", "bolt", "M8", "250"]);
+
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let path = Path::new("./tests/resources/files_to_scan/txt/text_utf8.txt");
+		let text = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap()[0].text_contents.text().unwrap().to_string();
+		let config = ExtractConfig {
+			segmentation: Segmentation::Line,
+			segment_separator: Some(String::from(" | ")),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		assert_eq!(result[0].text_contents.text(), Some(segment_text(&text, Segmentation::Line).join(" | ").as_str()));
+		assert!(result[0].segments.is_empty());
+		//the segments listed, and the text kept
+		let config = ExtractConfig {
+			segmentation: Segmentation::Line,
+			segment_separator: None,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result[0].text_contents.text(), Some(text.as_str()));
+		assert_eq!(result[0].segments, segment_text(&text, Segmentation::Line));
+	}
+
+	#[test]
+	fn extract_text_from_file_with_manifest_msg_in_msg() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let (result, manifest) = extract_text_from_file_with_manifest(Path::new("./tests/resources/files_to_scan/emails/msg_in_msg.msg"), Vec::new(), keep_going, &ExtractConfig::default()).unwrap();
+		//every file, with the same identity as its FileListItem
+		assert_eq!(manifest.entries.len(), result.len());
+		for (entry, item) in manifest.entries.iter().zip(&result) {
+			assert_eq!((&entry.filename, &entry.parent_files, entry.crc, entry.size as i64), (&item.filename, &item.parent_files, item.crc, item.size));
 		}
-		"jpeg"| "jpg" | "pgm" | "png" | "ppm" => {
-			//tesseract
-			match ocr(file_list_item.filepath.as_path()) {
-				Ok(extracted_text) => {
-					return Ok(extracted_text);
-				}
-				Err(e) => {
-					warn!("Error extracting text from image {:?}\n{:?}", file_list_item.filepath, e);
-					return Ok(String::new());
-				}
+
+		let summary: Vec<(&str, usize, &str, bool)> = manifest.entries.iter()
+			.take(5)
+			.map(|x| (x.filename.as_str(), x.depth, x.file_type.as_str(), x.is_container))
+			.collect();
+		assert_eq!(summary, vec![
+			("msg_in_msg.msg", 0, "msg", true),
+			("body.txt", 1, "txt", false),
+			("Google Cloud Platform & APIs Your invoice is available for 01C315-14B151-6818D1.msg", 1, "msg", true),
+			("body.txt", 2, "txt", false),
+			("5407953830.pdf", 2, "pdf", true),
+		]);
+
+		let json = serde_json::to_string(&manifest).unwrap();
+		assert_eq!(serde_json::from_str::<ArchiveManifest>(&json).unwrap(), manifest);
+	}
+
+	#[test]
+	fn extract_text_from_file_ocr_image_dedup() {
+		//a logo as a png and as a recompressed jpeg, and a different image
+		let dir = tempfiles_location().join(Uuid::new_v4().simple().to_string());
+		fs::create_dir_all(&dir).unwrap();
+		let logo = image::GrayImage::from_fn(120, 60, |x, y| image::Luma([if (x / 20 + y / 15) % 2 == 0 { 30 } else { 220 }]));
+		logo.save(dir.join("logo.png")).unwrap();
+		let mut jpeg = Vec::new();
+		image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 70).encode_image(&logo).unwrap();
+		fs::write(dir.join("logo.jpg"), jpeg).unwrap();
+		let other = image::GrayImage::from_fn(120, 60, |x, _| image::Luma([(x * 2) as u8]));
+		other.save(dir.join("other.png")).unwrap();
+		assert!((image_dhash(&dir.join("logo.png")).unwrap().0 ^ image_dhash(&dir.join("logo.jpg")).unwrap().0).count_ones() <= 5);
+		//the logo at twice the size, and as a rendered page
+		image::imageops::resize(&logo, 240, 120, image::imageops::FilterType::Nearest).save(dir.join("logo large.png")).unwrap();
+		logo.save(dir.join("page 1 render.png")).unwrap();
+
+		//tesseract is stubbed out, each run reads a different text
+		let runs = Arc::new(AtomicUsize::new(0));
+		let runner_runs = runs.clone();
+		let runner = Arc::new(MockCommandRunner::new(move |_, args| {
+			let run = runner_runs.fetch_add(1, Ordering::Relaxed) + 1;
+			fs::write(format!("{}.txt", args.last().unwrap()), format!("text of run {}", run))?;
+			Ok(MockCommandRunner::success(""))
+		}));
+		let config = ExtractConfig {
+			command_runner: runner,
+			ocr_image_dedup: Some(ImageDedup::new(5)),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let text = |filename: &str| {
+			let result = extract_text_from_file_with_config(&dir.join(filename), Vec::new(), keep_going.clone(), &config).unwrap();
+			result[0].text_contents.text().unwrap().to_string()
+		};
+		assert_eq!(text("logo.png"), "text of run 1");
+		assert_eq!(text("logo.jpg"), "text of run 1");
+		assert_eq!(text("other.png"), "text of run 2");
+		assert_eq!(text("logo large.png"), "text of run 3");
+		assert_eq!(text("page 1 render.png"), "text of run 4");
+		assert_eq!(runs.load(Ordering::Relaxed), 4);
+		assert_eq!(config.ocr_image_dedup.as_ref().unwrap().len(), 3);
+		_ = fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn extract_text_from_file_docs_dxf() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/site_plan.dxf");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		assert_eq!(result[0].method, Some(String::from("dxf")));
+		//the block definition's own text and the geometry are left out
+		assert_eq!(result[0].text_contents, TextContents::Extracted(String::from(
+			"NORTH BOUNDARY ⌀150 STORMWATER PIPE\n\
+			GENERAL NOTES\n\
+			All dimensions in millimetres unless noted.\n\
+			Fall to sump 1/100, do not scale.\n\
+			Pipe cover 600 min at road crossings.\n\
+			TITLE: Pump Station 4 Site Plan\n\
+			DWGNO: C-104 Rev B\n"
+		)));
+	}
+
+	#[test]
+	fn extract_text_from_file_docx_strict_ooxml() {
+		//the Strict memo has the purl.oclc.org namespaces, as the default namespace so its elements have no w: prefix
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let strict = extract_text_from_file(Path::new("./tests/resources/files_to_scan/docs/harbour_memo_strict.docx"), Vec::new(), keep_going.clone()).unwrap();
+		let transitional = extract_text_from_file(Path::new("./tests/resources/files_to_scan/docs/harbour_memo_transitional.docx"), Vec::new(), keep_going).unwrap();
+		assert!(strict[0].text_contents.text().unwrap().contains("The dredging contract starts on 3 November."));
+		assert_eq!(strict[0].text_contents, transitional[0].text_contents);
+		assert_eq!(strict[0].method, Some(String::from("docx xml")));
+	}
+
+	#[test]
+	fn extract_text_from_file_docx_bad_media_entry() {
+		//the image before word/document.xml is flagged as encrypted, so it cannot be opened
+		let path = Path::new("./tests/resources/files_to_scan/docs/bad_media_entry.docx");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].method, Some(String::from("docx xml")));
+		assert!(result[0].text_contents.text().unwrap().contains("The site survey found no damage to the seawall."));
+		assert!(result[0].text_contents.text().unwrap().contains("Repairs are not needed this year."));
+	}
+
+	#[test]
+	fn extract_text_from_file_unchanged_image_not_read_again() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/two_images.docx");
+		let runner = Arc::new(MockCommandRunner::new(|_, args| {
+			fs::write(format!("{}.txt", args.last().unwrap()), "Figure 1: harbour plan")?;
+			Ok(MockCommandRunner::success(""))
+		}));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		let tesseract_runs = |runner: &MockCommandRunner| runner.calls().iter().filter(|(program, _)| program == "tesseract").count();
+		assert_eq!(tesseract_runs(&runner), 2);
+
+		//on a rescan nothing has changed, the images are not read by OCR again
+		let runner = Arc::new(MockCommandRunner::new(|_, _| Ok(MockCommandRunner::success(""))));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			..Default::default()
+		};
+		let rescan = extract_text_from_file_with_config(path, result.clone(), keep_going, &config).unwrap();
+		assert_eq!(tesseract_runs(&runner), 0);
+		assert_eq!(rescan.len(), result.len());
+		assert!(rescan.iter().all(|x| x.text_contents == TextContents::Unchanged));
+	}
+
+	#[test]
+	fn extract_text_from_file_unchanged_pdf_members_not_unpacked() {
+		//a PDF in the zip and one in a zip in the zip
+		let path = Path::new("./tests/resources/files_to_scan/archives/survey_reports.zip");
+		let runner = Arc::new(MockCommandRunner::new(|_, _| Ok(MockCommandRunner::success(""))));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		assert!(runner.calls().iter().any(|(program, _)| program.starts_with("pdf")));
+
+		//on a rescan no program is run on the PDFs, the items of the inner zip are carried over
+		let runner = Arc::new(MockCommandRunner::new(|_, _| Ok(MockCommandRunner::success(""))));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			..Default::default()
+		};
+		let rescan = extract_text_from_file_with_config(path, result.clone(), keep_going, &config).unwrap();
+		assert!(runner.calls().is_empty());
+		let names = |items: &[FileListItem]| items.iter().map(|x| (x.filename.clone(), x.parent_files.clone())).collect::<Vec<_>>();
+		assert_eq!(names(&rescan), names(&result));
+		assert!(names(&rescan).contains(&(String::from("draft.pdf"), vec![String::from("survey_reports.zip"), String::from("drafts.zip")])));
+		assert!(rescan.iter().skip(1).all(|x| x.text_contents == TextContents::Unchanged));
+	}
+
+	#[test]
+	fn extract_text_from_file_ocr_embedded_images_disabled() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/two_images.docx");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(result.iter().any(|x| x.filename.starts_with("image")));
+
+		//a spent budget would note any image that reached OCR
+		let config = ExtractConfig {
+			ocr_embedded_images: false,
+			ocr_budget: Some(OcrBudget::new(Duration::ZERO)),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert!(!result.iter().any(|x| x.filename.starts_with("image")));
+		assert!(!result.iter().any(|x| x.method.as_deref() == Some("ocr") || x.note.is_some()));
+		assert_eq!(result[0].method, Some(String::from("docx xml")));
+	}
+
+	#[test]
+	fn extract_text_from_file_ocr_budget() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			ocr_budget: Some(OcrBudget::new(Duration::ZERO)),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/image/415.jpg"), Vec::new(), keep_going.clone(), &config).unwrap();
+		assert_eq!(result[0].note, Some(String::from("OCR budget exhausted, not extracted")));
+		assert_eq!(result[0].text_contents, TextContents::Skipped(String::from("OCR budget exhausted, not extracted")));
+
+		//the first image spends the budget, the second is skipped
+		let config = ExtractConfig {
+			ocr_budget: Some(OcrBudget::new(Duration::from_nanos(1))),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/docs/two_images.docx"), Vec::new(), keep_going, &config).unwrap();
+		let budget_notes: Vec<&Option<String>> = result.iter()
+			.filter(|x| x.filename.starts_with("image"))
+			.map(|x| &x.note)
+			.collect();
+		assert_eq!(budget_notes, vec![&None, &Some(String::from("OCR budget exhausted, not extracted"))]);
+		assert!(config.ocr_budget.unwrap().is_exhausted());
+	}
+
+	#[test]
+	fn extract_text_from_file_markdown() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/release_notes.md");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert_eq!(result[0].method, Some(String::from("text")));
+		assert!(result[0].text_contents.text().unwrap().contains("# Release Notes"));
+
+		let config = ExtractConfig {
+			markdown_to_text: true,
+			markdown_link_urls: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(result[0].method, Some(String::from("markdown")));
+		assert_eq!(
+			result[0].text_contents,
+			TextContents::Extracted(String::from("Release Notes\n\nVersion 2.4 adds faster scanning. See the upgrade guide (https://example.com/upgrade) or https://example.com/faq.\n\nInstall\n\ncargo install extract_text\nSupports md files\nFixes OCR timeouts\n"))
+		);
+	}
+
+	#[test]
+	fn extract_text_from_file_type_mismatch() {
+		//a zip renamed to .pdf
+		let path = Path::new("./tests/resources/files_to_scan/archives/invoice.pdf");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(result.iter().all(|x| !x.type_mismatch));
+
+		let config = ExtractConfig {
+			detect_type_mismatch: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let invoice = result.iter().find(|x| x.filename == "invoice.pdf").unwrap();
+		assert!(invoice.type_mismatch);
+		let entry = result.iter().find(|x| x.filename == "invoice.txt").unwrap();
+		assert!(!entry.type_mismatch);
+		assert!(entry.text_contents.text().unwrap().contains("Invoice 1042"));
+		assert!(!has_type_mismatch(Path::new("./tests/resources/files_to_scan/archives/with_comments.zip")));
+		assert!(!has_type_mismatch(Path::new("./tests/resources/files_to_scan/docs/hebrew_visual.pdf")));
+	}
+
+	#[test]
+	fn extract_text_from_file_exclude_containers() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/nested_dirs.zip");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(result.iter().any(|x| x.filename == "nested_dirs.zip"));
+
+		let config = ExtractConfig {
+			exclude_containers: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["readme.txt", "data.csv"]);
+		assert_eq!(result[0].parent_files, vec!["nested_dirs.zip"]);
+	}
+
+	#[test]
+	fn docx_read_in_small_chunks() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/Teaching Philosophy 2016.docx");
+		let mut full_text = String::new();
+		<Docx as MsDoc<Docx>>::open(path).unwrap().read_to_string(&mut full_text).unwrap();
+		assert!(!full_text.is_empty());
+		//text is parsed on demand, a small buffer sees the same bytes as a full read
+		let mut doc = <Docx as MsDoc<Docx>>::open(path).unwrap();
+		let mut chunked_text: Vec<u8> = Vec::new();
+		let mut buf = [0u8; 7];
+		loop {
+			let len = doc.read(&mut buf).unwrap();
+			if len == 0 {
+				break;
 			}
-			// return Ok(String::new());
+			chunked_text.extend_from_slice(&buf[..len]);
 		}
-		_ => {
-			//text
-			let contents = read_text_from_file(file_list_item.filepath.as_path())?;
-			// debug!("contents: {:?}", contents);
-			return Ok(contents);
+		assert_eq!(String::from_utf8(chunked_text).unwrap(), full_text);
+	}
+
+	/// Returns at most `chunk_len` bytes per read.
+	struct ChunkedReader {
+		data: Vec<u8>,
+		position: usize,
+		chunk_len: usize,
+	}
+
+	impl Read for ChunkedReader {
+		fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+			let len = self.chunk_len.min(buf.len()).min(self.data.len() - self.position);
+			buf[..len].copy_from_slice(&self.data[self.position..self.position + len]);
+			self.position += len;
+			Ok(len)
 		}
 	}
-}
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-pub struct FileListItem {
-	pub filename: String,
-	pub parent_files: Vec<String>,
-	pub crc: i64,
-	pub size: i64,
-	pub text_contents: Option<String>
-}
+	#[test]
+	fn extract_text_from_file_max_text_bytes() {
+		let path = Path::new("./tests/resources/files_to_scan/txt/MsgSearcher.sql");
+		let config = ExtractConfig {
+			max_text_bytes: Some(64),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		let full_text = fs::read_to_string(path).unwrap();
+		assert_eq!(result[0].text_contents, TextContents::Extracted(full_text[..64].to_string()));
+		assert!(result[0].truncated);
+
+		//a file within the limit is read whole
+		let result = extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/txt/text_utf8.txt"), Vec::new(), keep_going, &config).unwrap();
+		assert!(!result[0].truncated);
+
+		//the text is cut back to a whole character
+		let (text, is_truncated) = decode_with_encoding_limit("café au lait".as_bytes(), UTF_8, 4).unwrap();
+		assert_eq!((text.as_str(), is_truncated), ("caf", true));
+		let (text, is_truncated) = decode_with_encoding_limit("café".as_bytes(), UTF_8, 5).unwrap();
+		assert_eq!((text.as_str(), is_truncated), ("café", false));
+	}
+
+	#[test]
+	fn extract_text_from_file_archive_member_patterns() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			archive_member_patterns: vec![String::from("*.txt")],
+			..Default::default()
+		};
+		//the pdfs, documents, image and nested zip are left out
+		let result = extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/archives/202010.zip"), Vec::new(), keep_going.clone(), &config).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["202010.zip", "non archive file in zip.txt"]);
 
-pub fn extract_text_from_file(filepath: &Path, pre_scanned_items: Vec<FileListItem>, keep_going: Arc<AtomicBool>) -> Result<Vec<FileListItem>, Box<dyn Error>> {
-	let mut list_of_files_in_archive: Vec<SubFileItem> = Vec::new();
-	let parent_files: Vec<String> = Vec::new();
-	extract_archive(filepath, 0, parent_files, &mut list_of_files_in_archive)?;
+		//in a 7z the members left out are read past, the pdf after the markdown file is read whole
+		let config = ExtractConfig {
+			archive_member_patterns: vec![String::from("*.pdf")],
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/archives/7z_example.7z"), Vec::new(), keep_going.clone(), &config).unwrap();
+		let filenames: Vec<(&str, i64)> = result.iter().map(|x| (x.filename.as_str(), x.size)).collect();
+		assert_eq!(filenames, vec![("7z_example.7z", 84048), ("051116160000062205_143814334.pdf", 144957)]);
 
-	// debug!("list_of_files_in_archive: {:#?}", list_of_files_in_archive);
+		let config = ExtractConfig {
+			archive_member_patterns: vec![String::from("reports/*2.txt")],
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(Path::new("./tests/resources/files_to_scan/archives/bundle.tgz"), Vec::new(), keep_going, &config).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["bundle.tgz", "bundle.tar", "q2.txt"]);
 
-	let mut file_list_items: Vec<FileListItem> = Vec::new();
+		assert!(config.is_archive_member_wanted("reports\\q2.txt"));
+		assert!(!config.is_archive_member_wanted("reports/q1.txt"));
+		assert!(ExtractConfig::default().is_archive_member_wanted("reports/q1.txt"));
+	}
 
-	//loop list_of_files_in_archive
-	let mut temp_dirs_to_remove: HashSet<PathBuf> = HashSet::new();
-	for sub_file_item in list_of_files_in_archive {
-		match sub_file_item.filepath.metadata() {
-			Ok(metadata) => {
-				let file_name = sub_file_item.filepath.file_name().unwrap().to_string_lossy().to_string();
-				let file_len:u64 = metadata.len();
-				trace!("file_len {}", file_len);
-				if file_len==0 {
-					//add a SubFileItem with empty contents.
-					let file_list_item: FileListItem = FileListItem{
-						filename: file_name,
-						parent_files: sub_file_item.parent_files,
-						crc: 0,
-						size: file_len as i64,
-						text_contents: Some(String::new()),
-					};
-					file_list_items.push(file_list_item);
-					continue;
-				}
-				debug!("{:?}", sub_file_item);
-				debug!("\n  file: {:?}\n    depth:{}, {:?}\n      subfile: {:?}", filepath, sub_file_item.depth, sub_file_item.parent_files, sub_file_item.filepath.file_name().unwrap());
+	#[test]
+	fn extract_text_from_file_log_gz_tail_lines() {
+		let path = Path::new("./tests/resources/files_to_scan/archives/pump_station.log.gz");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		let log = result.iter().find(|x| x.filename == "pump_station.log").unwrap();
+		let text = log.text_contents.text().unwrap();
+		assert!(text.starts_with("2025-10-14 09:00:01 INFO  pump station 4 started\n"));
+		assert!(text.ends_with("2025-10-14 10:05:10 INFO  pump 2 restarted by operator\n"));
+		assert!(!log.truncated);
 
-				let file_crc: i64 = checksum_file(Crc64Nvme, sub_file_item.filepath.to_str().unwrap(), None).unwrap() as i64;
+		let config = ExtractConfig {
+			log_tail_lines: Some(2),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let log = result.iter().find(|x| x.filename == "pump_station.log").unwrap();
+		assert_eq!(log.text_contents, TextContents::Extracted(String::from(
+			"2025-10-14 10:02:47 ERROR pump 2 tripped on overcurrent\n2025-10-14 10:05:10 INFO  pump 2 restarted by operator\n"
+		)));
+		assert!(log.truncated);
 
-				if file_len > MAX_FILE_SIZE {
-					info!("Skiping subfile {} due to large size {}.", file_name, file_len);
-					let file_list_item: FileListItem = FileListItem{
-						filename: file_name,
-						parent_files: sub_file_item.parent_files,
-						crc: file_crc,
-						size: file_len as i64,
-						text_contents: Some(String::new()),
-					};
-					file_list_items.push(file_list_item);
-					continue;
-				}
+		//without a tail, a log is cleaned a line at a time, and cut at a whole character
+		let config = ExtractConfig {
+			raw_text_max_bytes: Some(4),
+			..Default::default()
+		};
+		let (text, raw_text, is_truncated) = decode_clean_lines("caf\u{e9} open\nna\u{ef}ve\n".as_bytes(), UTF_8, None, &config).unwrap();
+		assert_eq!((text.as_str(), raw_text.as_deref(), is_truncated), ("cafe open\nnaive\n", Some("caf"), false));
+		let (text, _, is_truncated) = decode_clean_lines("caf\u{e9} open\nna\u{ef}ve\n".as_bytes(), UTF_8, Some(14), &config).unwrap();
+		assert_eq!((text.as_str(), is_truncated), ("cafe open\nna", true));
 
-				//if this is in a prescanned item, then check the filecrc
-				let mut skip_file = false;
-				for prescanned_item in &pre_scanned_items {
-					if prescanned_item.filename == file_name
-						&& prescanned_item.parent_files == sub_file_item.parent_files
-						&& prescanned_item.crc == file_crc
-					{
-						debug!("Sub file not changed, skipping...");
-						skip_file = true;
-						break;
-					}
-				}
-				
-				if skip_file {
-					let file_list_item: FileListItem = FileListItem{
-						filename: file_name,
-						parent_files: sub_file_item.parent_files,
-						crc: file_crc,
-						size: file_len as i64,
-						text_contents: None,
-					};
-					file_list_items.push(file_list_item);
-				} else {
-					let subfile_text = extract_text_from_subfile(&sub_file_item)?;
-					// trace!("subfile_text {:?}", subfile_text);
-					//cleanup of temp files and dirs
-					if DELETE_TEMP_FILES {
-						if sub_file_item.depth >= 1 {
-							let temp_dir = sub_file_item.filepath.clone();
-							let temp_dir = temp_dir.parent().unwrap().to_path_buf();
-							temp_dirs_to_remove.insert(temp_dir);
-							_ = std::fs::remove_file(&sub_file_item.filepath); //delete the file
-						}
-					}
-					let file_list_item: FileListItem = FileListItem{
-						filename: file_name,
-						parent_files: sub_file_item.parent_files,
-						crc: file_crc,
-						size: file_len as i64,
-						text_contents: Some(subfile_text),
-					};
-// println!("file_list_item: {:?}", file_list_item);
-					file_list_items.push(file_list_item);
-				}
-			}
-			Err(e) => {
-				keep_going.store(false, Ordering::Relaxed);
-				return Err(format!("Error getting metadata for file: {:?} error: {:?}", sub_file_item.filepath, e).into());
-			}
-		}
+		//a log with no more lines than asked for is read whole
+		let (text, is_truncated) = decode_tail_lines("first\nsecond".as_bytes(), UTF_8, 2).unwrap();
+		assert_eq!((text.as_str(), is_truncated), ("first\nsecond", false));
+		assert!(is_log_file(Path::new("pump_station.log.1")));
+		assert!(is_log_file(Path::new("PUMP.LOG")));
+		assert!(!is_log_file(Path::new("manuals.zip.001")));
+		assert!(!is_log_file(Path::new("catalog.txt")));
+	}
 
-		if !keep_going.load(Ordering::Relaxed) {
-			break;
+	#[test]
+	fn decode_with_encoding_split_characters() {
+		//2, 3 and 4 byte UTF-8 characters, and a UTF-16 surrogate pair, each split across reads at some chunk length
+		let text = "naïve café € 😀 end";
+		let utf16le: Vec<u8> = text.encode_utf16().flat_map(|x| x.to_le_bytes()).collect();
+		for chunk_len in 1..=5 {
+			for (data, encoding) in [(text.as_bytes().to_vec(), UTF_8), (utf16le.clone(), UTF_16LE)] {
+				let reader = ChunkedReader { data, position: 0, chunk_len };
+				assert_eq!(decode_with_encoding(reader, encoding).unwrap(), text, "{} in chunks of {}", encoding.name(), chunk_len);
+			}
 		}
+		//a character cut off at the end of the file is replaced, not dropped
+		let reader = ChunkedReader { data: "ab€".as_bytes()[..4].to_vec(), position: 0, chunk_len: 1 };
+		assert_eq!(decode_with_encoding(reader, UTF_8).unwrap(), "ab\u{FFFD}");
 	}
-	//remove temp folders
-	for temp_dir in temp_dirs_to_remove {
-		_ = std::fs::remove_dir_all(&temp_dir); //delete the temp dir
+
+	#[test]
+	fn extract_text_from_file_docs_pass_protected_docx() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/pass_protected.docx"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].filename, "pass_protected.docx");
+		assert_eq!(result[0].text_contents, TextContents::Skipped(String::from("encrypted, not extracted")));
+		assert_eq!(result[0].note, Some(String::from("encrypted, not extracted")));
 	}
 
-	Ok(file_list_items)
-}
+	#[test]
+	fn extract_text_from_file_docs_pdf_outline() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/outline_two_levels.pdf"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		let outline = result.iter().find(|x| x.filename == "Outline").unwrap();
+		assert_eq!(outline.parent_files, vec!["outline_two_levels.pdf"]);
+		assert_eq!(outline.text_contents.text().unwrap(), "Introduction\t1\n\tPurpose\t1\n\tScope\t2\nInstallation\t3\n");
+	}
 
-#[cfg(test)]
-mod tests {
-	use super::*;
+	#[test]
+	fn extract_text_from_file_docs_pdf_page_marker() {
+		let config = ExtractConfig {
+			pdf_page_marker: Some(String::from("[Page {page}]\n")),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(
+			Path::new("./tests/resources/files_to_scan/docs/outline_two_levels.pdf"),
+			Vec::new(),
+			keep_going,
+			&config
+		).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["outline_two_levels.pdf", "Outline", "pages"]);
+		assert_eq!(result[2].method, Some(String::from("pdftotext")));
+		assert_eq!(result[2].parent_files, vec!["outline_two_levels.pdf"]);
+		assert_eq!(
+			result[2].text_contents.text().unwrap(),
+			"[Page 1]\nIntroduction\nPurpose of this guide.\n\n\
+			[Page 2]\nScope of this guide.\n\n\
+			[Page 3]\nInstallation\nRun the installer.\n\n"
+		);
+	}
 
-    #[test]
-    fn extract_text_from_file_empty_file() {
-		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+	#[test]
+	fn extract_text_from_file_pages_preview() {
 		let keep_going = Arc::new(AtomicBool::new(true));
-		let keep_going_flag = keep_going.clone();
 		let result = extract_text_from_file(
-			Path::new("./tests/resources/files_to_scan/empty_file"),
-			pre_scanned_items,
-			keep_going_flag
+			Path::new("./tests/resources/files_to_scan/docs/newsletter.pages"),
+			Vec::new(),
+			keep_going
 		).unwrap();
-		//load expected from serde serialization
-		let serial_path = Path::new("./tests/resources/expected/empty_file.json");
-		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
-		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
-		
-		assert_eq!(result, expected);
-    }
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		//the preview images are not read when there is a preview pdf
+		assert_eq!(filenames, vec!["newsletter.pages", "preview.pdf", "page 1"]);
+		assert_eq!(result[2].parent_files, vec!["newsletter.pages", "preview.pdf"]);
+		assert!(result[2].text_contents.text().unwrap().contains("Spring issue: new site office opens in March."));
+	}
 
 	#[test]
-    fn extract_text_from_file_txt_utf8() {
-		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+	fn extract_text_from_file_mht() {
 		let keep_going = Arc::new(AtomicBool::new(true));
-		let keep_going_flag = keep_going.clone();
 		let result = extract_text_from_file(
-			Path::new("./tests/resources/files_to_scan/txt/text_utf8.txt"),
-			pre_scanned_items,
-			keep_going_flag
+			Path::new("./tests/resources/files_to_scan/docs/access_notice.mht"),
+			Vec::new(),
+			keep_going
 		).unwrap();
-		// //load expected from serde serialization
-		// let serial_path = Path::new("./tests/resources/expected/empty_file.json");
-		// let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
-		// let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
-		//check each byte of contents
-		println!("*** {}", result.len());
-		
-		// assert_eq!(result, None);
-    }
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		//the stylesheet is left out
+		assert_eq!(filenames, vec!["access_notice.mht", "page.txt", "map.png"]);
+		assert!(result.iter().skip(1).all(|x| x.parent_files == vec![String::from("access_notice.mht")]));
+		let page_text = result[1].text_contents.text().unwrap();
+		assert!(page_text.starts_with("Site Access Notice"));
+		assert!(page_text.contains("The north gate is closed from 3 March. Use the east entrance on Harbour Road."));
+		assert!(!page_text.contains("Content-Type"));
+		assert!(!page_text.contains("<p>"));
+		assert!(!page_text.contains("=3D"));
+	}
 
-    #[cfg(target_os = "windows")]
 	#[test]
-    fn extract_text_from_file_docs_5407953830_pdf() {
-		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+	fn extract_text_from_file_mht_resource_name_path() {
 		let keep_going = Arc::new(AtomicBool::new(true));
-		let keep_going_flag = keep_going.clone();
 		let result = extract_text_from_file(
-			Path::new("./tests/resources/files_to_scan/docs/5407953830.pdf"),
-			pre_scanned_items,
-			keep_going_flag
+			Path::new("./tests/resources/files_to_scan/docs/traversal_notice.mht"),
+			Vec::new(),
+			keep_going
 		).unwrap();
-		//load expected from serde serialization
-		let serial_path = Path::new("./tests/resources/expected/docs/5407953830.pdf.windows.json");
-		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
-		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
-		
-		assert_eq!(result, expected);
-    }
+		//a resource named by a path is written inside the temp folder, under its last component
+		let image = result.iter().find(|x| x.filename == "site plan.png").unwrap();
+		assert_eq!(image.parent_files, vec![String::from("traversal_notice.mht")]);
+		//two different logo.png are both kept
+		let logos: Vec<&FileListItem> = result.iter().filter(|x| x.filename == "logo.png").collect();
+		assert_eq!(logos.len(), 2);
+		assert_ne!((logos[0].size, logos[0].crc), (logos[1].size, logos[1].crc));
+	}
 
-    #[cfg(target_os = "linux")]
 	#[test]
-    fn extract_text_from_file_docs_5407953830_pdf() {
-		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+	fn extract_text_from_file_docs_pdf_user_password() {
 		let keep_going = Arc::new(AtomicBool::new(true));
-		let keep_going_flag = keep_going.clone();
 		let result = extract_text_from_file(
-			Path::new("./tests/resources/files_to_scan/docs/5407953830.pdf"),
-			pre_scanned_items,
-			keep_going_flag
+			Path::new("./tests/resources/files_to_scan/docs/user_password.pdf"),
+			Vec::new(),
+			keep_going
 		).unwrap();
-		//load expected from serde serialization
-		let serial_path = Path::new("./tests/resources/expected/docs/5407953830.pdf.linux.json");
-		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
-		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
-		
-		assert_eq!(result, expected);
-    }
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].filename, "user_password.pdf");
+		assert_eq!(result[0].note, Some(String::from("encrypted, not extracted")));
+		assert_eq!(result[0].error, None);
+	}
 
-    #[cfg(target_os = "windows")]
 	#[test]
-    fn extract_text_from_file_emails_msg_in_msg() {
-		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+	fn extract_text_from_file_docs_pdf_dehyphenate() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/hyphenated.pdf");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(result[1].text_contents.text().unwrap().contains("inter-\nnational"));
+
+		let config = ExtractConfig {
+			dehyphenate_pdf_text: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		assert_eq!(
+			result[1].text_contents.text().unwrap(),
+			"Trade is increasingly international\nin scope. See the North-\nWest region report on production\ncosts.\n\n\u{c}"
+		);
+	}
+
+	#[test]
+	fn extract_text_from_file_docs_pdf_mock_command_runner() {
+		//the pdf tools are stubbed out, the page count and the text of each page come from the mock
+		let runner = Arc::new(MockCommandRunner::new(|program, args| {
+			match program {
+				"pdfinfo" => Ok(MockCommandRunner::success("Encrypted:      no\nPages:          3\n")),
+				"pdftotext" => {
+					//pdftotext -f N -l N input output
+					fs::write(&args[5], format!("mock text of page {}\n", args[1]))?;
+					Ok(MockCommandRunner::success(""))
+				}
+				_ => Ok(MockCommandRunner::success("")),
+			}
+		}));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			..Default::default()
+		};
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file_with_config(
+			Path::new("./tests/resources/files_to_scan/docs/hyphenated.pdf"),
+			Vec::new(),
+			keep_going,
+			&config
+		).unwrap();
+		let pages: Vec<(&str, &str)> = result.iter()
+			.filter(|x| x.method == Some(String::from("pdftotext")))
+			.map(|x| (x.filename.as_str(), x.text_contents.text().unwrap()))
+			.collect();
+		assert_eq!(pages, vec![
+			("page 1", "mock text of page 1\n"),
+			("page 2", "mock text of page 2\n"),
+			("page 3", "mock text of page 3\n"),
+		]);
+		let calls = runner.calls();
+		assert_eq!(calls[0].0, "pdfinfo");
+		assert_eq!(calls.iter().filter(|(program, _)| program == "pdftotext").count(), 3);
+	}
+
+	#[test]
+	fn extract_text_from_file_docs_pdf_garbled_text_layer() {
+		//the font of no_tounicode.pdf has no ToUnicode map, pdftotext can only write its glyph ids as characters
+		let runner = Arc::new(MockCommandRunner::new(|program, args| {
+			match program {
+				"pdfinfo" => Ok(MockCommandRunner::success("Pages:          1\n")),
+				"pdftotext" => {
+					fs::write(args.last().unwrap(), ",QYRLFH\u{3}WRWDO\u{3}GXH\u{3}RQ\u{3}UHFHLSW\n3OHDVH\u{3}SD\\\u{3}E\\\u{3}EDQN\u{3}WUDQVIHU\n\n\u{c}")?;
+					Ok(MockCommandRunner::success(""))
+				}
+				//the page render, pdftoppm on linux and pdftopng on windows
+				"pdftoppm" | "pdftopng" => {
+					let outpath = match program {
+						"pdftoppm" => format!("{}.png", args.last().unwrap()),
+						_ => format!("{}-{:06}.png", args.last().unwrap(), 1),
+					};
+					image::GrayImage::new(8, 8).save(outpath).map_err(io::Error::other)?;
+					Ok(MockCommandRunner::success(""))
+				}
+				"tesseract" => {
+					fs::write(format!("{}.txt", args.last().unwrap()), "Invoice total due on receipt\nPlease pay by bank transfer\n")?;
+					Ok(MockCommandRunner::success(""))
+				}
+				_ => Ok(MockCommandRunner::success("")),
+			}
+		}));
+		let path = Path::new("./tests/resources/files_to_scan/docs/no_tounicode.pdf");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig {
+			command_runner: runner.clone(),
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+		let page = result.iter().find(|x| x.filename == "page 1").unwrap();
+		assert_eq!(page.note, Some(String::from("likely unextractable text layer")));
+		assert!(!result.iter().any(|x| x.method == Some(String::from("ocr"))));
+
+		//with the fallback the page is read by OCR as well
+		let config = ExtractConfig {
+			ocr_garbled_text_layer: true,
+			..config
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let ocr = result.iter().find(|x| x.method == Some(String::from("ocr"))).unwrap();
+		assert!(ocr.text_contents.text().unwrap().contains("Invoice total due on receipt"));
+
+		assert!(!is_garbled_text("Invoice total due on receipt\nPlease pay by bank transfer\n"));
+		assert!(!is_garbled_text("Total 1,234.56 NZD 2025-10-14"));
+		//a table of contents, its dot leaders outnumber the letters, and a ruled table
+		let leader = ".".repeat(60);
+		assert!(!is_garbled_text(&format!("Contents\n1 Scope {leader} 1\n2 Site {leader} 3\n3 Works {leader} 7\n4 Costs {leader} 12\n")));
+		assert!(!is_garbled_text(&format!("Item | Qty\n{}\nPipe | 4\n{}\nValve | 2\n", "-".repeat(40), "_".repeat(40))));
+
+		//pdftotext itself writes the glyph ids of the fixture
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going).unwrap();
+		let page = result.iter().find(|x| x.filename == "page 1").unwrap();
+		assert_eq!(page.note, Some(String::from("likely unextractable text layer")));
+	}
+
+	#[test]
+	#[cfg(target_os = "linux")]
+	fn extract_text_from_file_docs_pdf_ocr_image_format() {
+		//pdftoppm writes the page in the format its options ask for
+		let runner = Arc::new(MockCommandRunner::new(|program, args| {
+			match program {
+				"pdfinfo" => Ok(MockCommandRunner::success("Pages:          1\n")),
+				//just the page number, so the page is rendered for OCR
+				"pdftotext" => {
+					fs::write(args.last().unwrap(), "1\n")?;
+					Ok(MockCommandRunner::success(""))
+				}
+				"pdftoppm" => {
+					let extension = if args.iter().any(|x| x == "-png") { "png" } else if args.iter().any(|x| x == "-gray") { "pgm" } else { "ppm" };
+					image::GrayImage::new(8, 8).save(format!("{}.{}", args.last().unwrap(), extension)).map_err(io::Error::other)?;
+					Ok(MockCommandRunner::success(""))
+				}
+				"tesseract" => {
+					fs::write(format!("{}.txt", args.last().unwrap()), "Serial number: AX-2291-77\n")?;
+					Ok(MockCommandRunner::success(""))
+				}
+				_ => Ok(MockCommandRunner::success("")),
+			}
+		}));
+		let path = Path::new("./tests/resources/files_to_scan/docs/scanned_with_page_number.pdf");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		for (format, filename) in [
+			(PdfOcrImageFormat::Png, "page 1 render.png"),
+			(PdfOcrImageFormat::Pgm, "page 1 render.pgm"),
+			(PdfOcrImageFormat::Ppm, "page 1 render.ppm"),
+		] {
+			let config = ExtractConfig {
+				command_runner: runner.clone(),
+				ocr_fallback_min_text_len: 20,
+				pdf_ocr_image_format: format,
+				..Default::default()
+			};
+			let result = extract_text_from_file_with_config(path, Vec::new(), keep_going.clone(), &config).unwrap();
+			let render = result.iter().find(|x| x.filename == filename).unwrap();
+			assert_eq!(render.method, Some(String::from("ocr")));
+			assert_eq!(render.text_contents, TextContents::Extracted(String::from("Serial number: AX-2291-77\n")));
+		}
+		let pdfimages_args = |format: PdfOcrImageFormat| format.pdfimages_args();
+		assert_eq!(pdfimages_args(PdfOcrImageFormat::Png), (&["-png"][..], &["png"][..]));
+		assert_eq!(pdfimages_args(PdfOcrImageFormat::Ppm).0, &[] as &[&str]);
+	}
+
+	#[test]
+	fn extract_text_from_file_docs_pdf_portfolio() {
 		let keep_going = Arc::new(AtomicBool::new(true));
-		let keep_going_flag = keep_going.clone();
 		let result = extract_text_from_file(
-			Path::new("./tests/resources/files_to_scan/emails/msg_in_msg.msg"),
-			pre_scanned_items,
-			keep_going_flag
+			Path::new("./tests/resources/files_to_scan/docs/board_papers_portfolio.pdf"),
+			Vec::new(),
+			keep_going
 		).unwrap();
-		//load expected from serde serialization
-		let serial_path = Path::new("./tests/resources/expected/emails/msg_in_msg.msg.windows.json");
-		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
-		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
-		
-		assert_eq!(result, expected);
-    }
+		let agenda = result.iter().find(|x| x.filename == "agenda.txt").unwrap();
+		assert_eq!(agenda.parent_files, vec!["board_papers_portfolio.pdf"]);
+		assert!(agenda.text_contents.text().unwrap().contains("Harbour redevelopment budget"));
+		//an embedded pdf is read like any other
+		assert!(result.iter().any(|x| x.filename == "treasurer report.pdf"));
+		let report_page = result.iter()
+			.find(|x| x.parent_files == vec!["board_papers_portfolio.pdf", "treasurer report.pdf"] && x.filename == "page 1")
+			.unwrap();
+		assert!(report_page.text_contents.text().unwrap().contains("Trade is increasingly"));
+		//and so is the cover page
+		let cover_page = result.iter().find(|x| x.parent_files == vec!["board_papers_portfolio.pdf"] && x.filename == "page 1").unwrap();
+		assert!(cover_page.text_contents.text().unwrap().contains("This portfolio holds the board papers."));
+	}
 
-    #[cfg(target_os = "linux")]
 	#[test]
-    fn extract_text_from_file_emails_msg_in_msg() {
-		let pre_scanned_items: Vec<FileListItem> = Vec::new();
+	fn extract_text_from_file_docs_pdf_portfolio_name_clash() {
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let config = ExtractConfig { max_file_size: 100_000, ..ExtractConfig::default() };
+		let result = extract_text_from_file_with_config(
+			Path::new("./tests/resources/files_to_scan/docs/site_minutes_portfolio.pdf"),
+			Vec::new(),
+			keep_going,
+			&config
+		).unwrap();
+		//files of the same name do not overwrite each other
+		let minutes = result.iter()
+			.filter(|x| x.filename == "minutes.txt")
+			.map(|x| x.text_contents.text().unwrap().trim().to_string())
+			.collect::<Vec<String>>();
+		assert_eq!(minutes, vec!["North site minutes: fence repaired", "South site minutes: pump serviced"]);
+		//nor does a cover page overwrite a file named like it
+		let pages = result.iter()
+			.filter(|x| x.parent_files == vec!["site_minutes_portfolio.pdf"] && x.filename == "page 1")
+			.map(|x| x.text_contents.text().unwrap().trim().to_string())
+			.collect::<Vec<String>>();
+		assert_eq!(pages.len(), 2);
+		assert!(pages.contains(&String::from("Notes named like a page of the cover")));
+		assert!(pages.iter().any(|x| x.contains("Minutes of both sites.")));
+		//a file inflating past max_file_size is not decompressed
+		let backup = result.iter().find(|x| x.filename == "backup.txt").unwrap();
+		assert_eq!(backup.text_contents, TextContents::Skipped(String::from("larger than max_file_size 100000, not extracted")));
+	}
+
+	#[test]
+	fn extract_text_from_file_djvu() {
 		let keep_going = Arc::new(AtomicBool::new(true));
-		let keep_going_flag = keep_going.clone();
 		let result = extract_text_from_file(
-			Path::new("./tests/resources/files_to_scan/emails/msg_in_msg.msg"),
-			pre_scanned_items,
-			keep_going_flag
+			Path::new("./tests/resources/files_to_scan/docs/text_layer.djvu"),
+			Vec::new(),
+			keep_going.clone()
 		).unwrap();
-		//load expected from serde serialization
-		let serial_path = Path::new("./tests/resources/expected/emails/msg_in_msg.msg.linux.json");
-		let obj_as_json = fs::read_to_string(serial_path).expect("Error reading serialized file.");
-		let expected: Vec<FileListItem> = serde_json::from_str(&obj_as_json).expect("Error loading serialized json.");
-		
-		assert_eq!(result, expected);
-    }
+		assert_eq!(result[1].filename, "page 1");
+		assert_eq!(result[1].parent_files, vec!["text_layer.djvu"]);
+		assert_eq!(result[1].method, Some(String::from("djvutxt")));
+		assert!(result[1].text_contents.text().unwrap().contains("The scanned page has a text layer."));
+
+		//a page without a text layer is rendered for OCR
+		let result = extract_text_from_file(
+			Path::new("./tests/resources/files_to_scan/docs/no_text_layer.djvu"),
+			Vec::new(),
+			keep_going
+		).unwrap();
+		assert!(result.iter().any(|x| x.filename == "page 1 render.pgm"));
+	}
+
+	#[test]
+	fn extract_text_from_file_docs_pdf_scripts() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/scripted.pdf");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(result[0].has_scripts);
+		assert!(result.iter().skip(1).all(|x| !x.has_scripts));
+		assert!(result.iter().all(|x| x.filename != "[Scripts]"));
+		//a PDF without scripts
+		let result = extract_text_from_file(Path::new("./tests/resources/files_to_scan/docs/annotated.pdf"), Vec::new(), keep_going.clone()).unwrap();
+		assert!(!result[0].has_scripts);
+
+		let config = ExtractConfig {
+			pdf_scripts: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["scripted.pdf", "[Scripts]", "page 1"]);
+		assert_eq!(result[1].method, Some(String::from("pdf scripts")));
+		//the OpenAction, the document level script in its own stream, and the link's Launch action
+		assert_eq!(result[1].text_contents, TextContents::Extracted(String::from(
+			"Scripts:\n\
+			JavaScript: app.alert('Enable editing to view the invoice');\n\
+			JavaScript: var total = this.getField('total');\nthis.submitForm('http://example.invalid/collect');\n\
+			Launch: cmd.exe /c start update.exe\n"
+		)));
+	}
+
+	#[test]
+	fn extract_text_from_file_docs_pdf_annotations() {
+		let path = Path::new("./tests/resources/files_to_scan/docs/annotated.pdf");
+		let keep_going = Arc::new(AtomicBool::new(true));
+		//not listed by default
+		let result = extract_text_from_file(path, Vec::new(), keep_going.clone()).unwrap();
+		assert!(!result.iter().any(|x| x.filename.ends_with("annotations")));
+
+		let config = ExtractConfig {
+			pdf_annotations: true,
+			..Default::default()
+		};
+		let result = extract_text_from_file_with_config(path, Vec::new(), keep_going, &config).unwrap();
+		let filenames: Vec<&str> = result.iter().map(|x| x.filename.as_str()).collect();
+		assert_eq!(filenames, vec!["annotated.pdf", "page 1", "page 2", "page 2 annotations"]);
+		let annotations = &result[3];
+		assert_eq!(annotations.parent_files, vec!["annotated.pdf"]);
+		assert_eq!(annotations.method, Some(String::from("pdf annotations")));
+		assert_eq!(
+			annotations.text_contents.text().unwrap(),
+			"Annotations:\nText (R. Okafor): Check the load figures against the 2023 survey.\nHighlight (J. Tremaine): Clause 4.2 conflicts with the contract\n"
+		);
+	}
 
 	//this one is large and slow
 	// #[test]
@@ -10,6 +10,7 @@ use simplelog::*;
 use std::{
 	error::Error,
 	fs,
+	io,
 	path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -19,13 +20,16 @@ use std::{
 };
 
 fn main()  -> Result<(), Box<dyn Error>> {
+	//`-` reads the input from stdin and writes its text to stdout, e.g. `cat report.pdf | extract_text -`
+	let read_stdin = std::env::args().nth(1).as_deref() == Some("-");
+
     let logger_config = ConfigBuilder::new()
 		.set_time_offset_to_local().expect("Failed to get local time offset")
 		.set_time_format_custom(format_description!("[hour]:[minute]:[second].[subsecond digits:3]"))
         .build();
 	CombinedLogger::init(
         vec![
-            TermLogger::new(LevelFilter::Trace, logger_config, TerminalMode::Mixed, ColorChoice::Auto),
+            TermLogger::new(LevelFilter::Trace, logger_config, if read_stdin { TerminalMode::Stderr } else { TerminalMode::Mixed }, ColorChoice::Auto),
 			// TermLogger::new(LevelFilter::Debug, Config::default(), TerminalMode::Mixed, ColorChoice::Auto),
             // WriteLogger::new(LevelFilter::Info, Config::default(), File::create("my_rust_binary.log").unwrap()),
         ]
@@ -36,6 +40,18 @@ fn main()  -> Result<(), Box<dyn Error>> {
     // debug!("This level is currently not enabled for any logger");
 
     let keep_going = Arc::new(AtomicBool::new(true));
+
+	//stdin is the input, so there is no watching it for a quit key
+	if read_stdin {
+		let contents = extract_text_from_reader(&mut io::stdin().lock(), keep_going, &ExtractConfig::default())?;
+		for item in &contents {
+			if let Some(text) = item.text_contents.text() {
+				print!("{}", text);
+			}
+		}
+		return Ok(());
+	}
+
     let keep_going_flag = keep_going.clone();
     let _watch_for_quit_handle = thread::spawn(move || {watch_for_quit(keep_going_flag);});
 